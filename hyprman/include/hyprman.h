@@ -0,0 +1,33 @@
+/* C interface to hyprman, built as libhyprman.so (see src/ffi.rs). */
+#ifndef HYPRMAN_H
+#define HYPRMAN_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct HyprmanConnection HyprmanConnection;
+
+/* Connects to the daemon socket and subscribes with the given filter line
+ * ("all" when NULL). Returns NULL on failure. */
+HyprmanConnection *hyprman_connect(const char *socket_path, const char *subscription);
+
+/* Blocks for the next event and returns it as a JSON string, or NULL once
+ * the daemon hangs up. Free the result with hyprman_free_string. */
+char *hyprman_next_event(HyprmanConnection *connection);
+
+/* Sends a request to Hyprland's socket1 (e.g. "j/clients") and returns the
+ * response, or NULL on failure. Free the result with hyprman_free_string. */
+char *hyprman_query(const char *query);
+
+/* Closes a connection returned by hyprman_connect. */
+void hyprman_disconnect(HyprmanConnection *connection);
+
+/* Releases a string returned by this library. */
+void hyprman_free_string(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* HYPRMAN_H */
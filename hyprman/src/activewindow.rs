@@ -0,0 +1,175 @@
+use crate::{
+    Config, connect_unix_socket,
+    output::{Escape, KeyCase, Output},
+    parse_event,
+};
+use hyprman::error::{HyprmanError, Result};
+use hyprman::event::{Address, HyprlandEvent};
+use hyprman::hypr::{Client, query_clients, query_monitors, query_socket, set_instance_signature};
+use log::info;
+use serde::Deserialize;
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+};
+
+// === Activewindow Client Configuration ===
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ActiveWindowConfig {
+    // Output keys to keep (e.g. ["class", "title", "address"]); all when unset.
+    // Overridden by `-a <fields>`.
+    pub fields: Option<Vec<String>>,
+}
+
+// === Activewindow Client Mode ===
+
+/// State the activewindow client keeps between events.
+struct ActiveWindowState {
+    /// Every window, keyed by address.
+    clients: HashMap<Address, Client>,
+    /// Monitor names keyed by monitor id.
+    monitors: HashMap<i64, String>,
+    /// Windows that asked to be minimized.
+    minimized: HashSet<Address>,
+}
+
+impl ActiveWindowState {
+    fn query() -> Result<ActiveWindowState> {
+        let mut state = ActiveWindowState {
+            clients: query_clients()?,
+            monitors: HashMap::new(),
+            minimized: HashSet::new(),
+        };
+        state.refresh_monitors()?;
+        Ok(state)
+    }
+
+    fn refresh_monitors(&mut self) -> Result<()> {
+        self.monitors = query_monitors()?
+            .into_iter()
+            .map(|m| (m.id, m.name))
+            .collect();
+        Ok(())
+    }
+
+    /// Prints a window with its monitor name resolved, trimmed to the
+    /// configured fields.
+    fn print(
+        &self,
+        config: &ActiveWindowConfig,
+        escape: Escape,
+        keys: KeyCase,
+        client: &Client,
+        output: &mut Output,
+    ) {
+        let mut client = client.clone();
+        if !client.address.is_empty() {
+            client.monitor_name = self.monitors.get(&client.monitor).cloned();
+            client.minimized = Some(self.minimized.contains(&client.address));
+        }
+        for text in [
+            &mut client.class,
+            &mut client.title,
+            &mut client.initial_class,
+            &mut client.initial_title,
+        ] {
+            *text = escape.apply(text);
+        }
+        // Fields are named as printed, in the configured key case.
+        let mut value = keys.apply(serde_json::to_value(&client).unwrap());
+        if let (Some(fields), Some(object)) = (&config.fields, value.as_object_mut()) {
+            object.retain(|key, _| fields.contains(key));
+        }
+        output.write(&value.to_string());
+    }
+}
+
+/// Prints the active window as json
+pub fn run_activewindow_client(config: &Config, mut output: Output) -> Result<()> {
+    let subscription_line = String::from(
+        "activewindowv2,fullscreen,closewindow,movewindow,changefloatingmode,moveintogroup,moveoutofgroup,togglegroup,pin,minimized,windowtitle,monitoraddedv2,monitorremoved,hyprlandrestarted\n",
+    );
+    info!("Using subscription line: {}", subscription_line);
+    let mut connection = connect_unix_socket(config, subscription_line)?;
+    let escape = config.escape;
+    let keys = config.key_case;
+    let config = &config.activewindow;
+    let mut state = ActiveWindowState::query()?;
+    state.print(config, escape, keys, &Client::default(), &mut output);
+    while let Some(event_line) = connection.next_line()? {
+        info!("Read eventline: {}", event_line.clone());
+        let event = parse_event(&event_line)?;
+        info!(
+            "Parsed event: {}",
+            serde_json::to_string(&event.clone()).unwrap()
+        );
+        match event {
+            HyprlandEvent::ActiveWindowV2 { window_address } => {
+                if !window_address.is_empty() {
+                    if let Some(client) = state.clients.get(&window_address) {
+                        state.print(config, escape, keys, client, &mut output);
+                    } else {
+                        state.clients = query_clients()?;
+                        let Some(client) = state.clients.get(&window_address) else {
+                            return Err(io::Error::new(
+                                io::ErrorKind::NotFound,
+                                format!("failed to find window address {}", window_address),
+                            )
+                            .into());
+                        };
+                        state.print(config, escape, keys, client, &mut output);
+                    }
+                } else {
+                    info!("No active window.");
+                    state.print(config, escape, keys, &Client::default(), &mut output);
+                }
+            }
+            HyprlandEvent::Minimized {
+                window_address,
+                minimized,
+            } => {
+                if minimized {
+                    state.minimized.insert(window_address);
+                } else {
+                    state.minimized.remove(&window_address);
+                }
+                let active_client = query_active_client()?;
+                state.print(config, escape, keys, &active_client, &mut output);
+            }
+            HyprlandEvent::MonitorAddedV2 { .. } | HyprlandEvent::MonitorRemoved { .. } => {
+                state.refresh_monitors()?;
+            }
+            HyprlandEvent::HyprlandRestarted { instance_signature } => {
+                set_instance_signature(instance_signature);
+                state = ActiveWindowState::query()?;
+                let active_client = query_active_client()?;
+                state.print(config, escape, keys, &active_client, &mut output);
+            }
+            _ => {
+                state.clients = query_clients()?;
+                let clients = &state.clients;
+                state
+                    .minimized
+                    .retain(|address| clients.contains_key(address));
+                let active_client = query_active_client()?;
+                state.print(config, escape, keys, &active_client, &mut output);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn query_active_client() -> Result<Client> {
+    let query = "j/activewindow";
+    let response = query_socket(query)?;
+    if response != "{}" {
+        serde_json::from_str(&response).map_err(|source| HyprmanError::Parse {
+            context: "active window".to_string(),
+            source,
+        })
+    } else {
+        info!("Active window is empty.");
+        Ok(Client::default())
+    }
+}
@@ -0,0 +1,95 @@
+use hyprman::{event::HyprlandEvent, hypr::query_socket};
+use log::{error, info};
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// === Audit Log ===
+
+/// Appends a JSON line for every process spawned or command sent to
+/// Hyprland on behalf of an event, so surprising automation can be traced
+/// back to what triggered it.
+pub struct AuditLog {
+    file: Option<Mutex<File>>,
+}
+
+/// One action taken by hyprman.
+#[derive(Serialize)]
+pub struct AuditRecord<'a> {
+    /// What ran the action, e.g. `hook`.
+    pub source: &'a str,
+    /// Name of the hook or rule, if it has one.
+    pub name: Option<&'a str>,
+    pub trigger: Option<&'a HyprlandEvent>,
+    pub command: String,
+    /// `{"spawned": <pid>}`, `{"exited": <code>}`, `{"reply": <reply>}`,
+    /// `{"error": <message>}` or `{"dry_run": true}`.
+    pub result: Value,
+}
+
+impl AuditRecord<'_> {
+    /// Sends the command to Hyprland as `dispatch <command>`, or only logs
+    /// it in a dry run, and records and returns the outcome.
+    pub fn dispatch(mut self, dry_run: bool, audit: &AuditLog) -> Value {
+        if dry_run {
+            info!("Would dispatch for {}: {}", self.source, self.command);
+            self.result = json!({ "dry_run": true });
+        } else {
+            self.result = match query_socket(&format!("dispatch {}", self.command)) {
+                Ok(reply) => json!({ "reply": reply.trim() }),
+                Err(e) => {
+                    error!("Failed to dispatch {}: {}", self.command, e);
+                    json!({ "error": e.to_string() })
+                }
+            };
+        }
+        audit.record(&self);
+        self.result
+    }
+}
+
+#[derive(Serialize)]
+struct TimedRecord<'a> {
+    timestamp_ms: u64,
+    #[serde(flatten)]
+    record: &'a AuditRecord<'a>,
+}
+
+impl AuditLog {
+    /// Opens `path` for appending; without a path nothing is recorded.
+    pub fn open(path: Option<&str>) -> AuditLog {
+        let file = path.and_then(|path| {
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .inspect_err(|e| error!("Failed to open audit log {}: {}", path, e))
+                .ok()
+        });
+        AuditLog {
+            file: file.map(Mutex::new),
+        }
+    }
+
+    pub fn record(&self, record: &AuditRecord) {
+        let Some(file) = &self.file else {
+            return;
+        };
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64);
+        let line = TimedRecord {
+            timestamp_ms,
+            record,
+        };
+        let json = serde_json::to_string(&line).unwrap();
+        if let Err(e) = writeln!(file.lock().unwrap(), "{}", json) {
+            error!("Failed to write audit record: {}", e);
+        }
+    }
+}
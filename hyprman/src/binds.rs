@@ -0,0 +1,79 @@
+use crate::check::Findings;
+use hyprman::hypr::query_socket;
+use log::{error, info};
+use serde::Deserialize;
+use std::env;
+
+// === Trigger Binds ===
+
+/// A key bind registered in Hyprland that runs `hyprman trigger <name>`,
+/// configured as `[[binds]]`. Hooks listen for it as a `trigger` event,
+/// e.g. with `match = { name = "<name>" }`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Bind {
+    // Modifiers and key as in Hyprland's `bind`, e.g. "SUPER SHIFT, M".
+    keys: String,
+    // Name passed to `hyprman trigger`.
+    trigger: String,
+}
+
+/// Reports binds Hyprland would reject and keys bound twice.
+pub fn check(binds: &[Bind], findings: &mut Findings) {
+    let mut seen = Vec::new();
+    for (i, bind) in binds.iter().enumerate() {
+        let context = format!("binds[{}] ({})", i, bind.trigger);
+        if !bind.keys.contains(',') {
+            findings.error(format!(
+                "{}: keys '{}' should be modifiers and key, e.g. \"SUPER SHIFT, M\"",
+                context, bind.keys
+            ));
+        }
+        if bind.trigger.trim().is_empty() {
+            findings.error(format!("{}: the trigger name is empty", context));
+        }
+        let keys = bind.keys.to_uppercase().replace(' ', "");
+        if seen.contains(&keys) {
+            findings.warning(format!("{}: keys '{}' are bound twice", context, bind.keys));
+        }
+        seen.push(keys);
+    }
+}
+
+/// Adds the binds to Hyprland, replacing any bind on the same keys.
+pub fn register(binds: &[Bind]) {
+    if binds.is_empty() {
+        return;
+    }
+    let exe = match env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            error!("Cannot register binds without the hyprman path: {}", e);
+            return;
+        }
+    };
+    for bind in binds {
+        keyword(&format!("unbind {}", bind.keys));
+        keyword(&format!(
+            "bind {}, exec, {} trigger {}",
+            bind.keys,
+            exe.display(),
+            bind.trigger
+        ));
+    }
+    info!("Registered {} binds", binds.len());
+}
+
+/// Removes the binds from Hyprland.
+pub fn unregister(binds: &[Bind]) {
+    for bind in binds {
+        keyword(&format!("unbind {}", bind.keys));
+    }
+}
+
+fn keyword(command: &str) {
+    match query_socket(&format!("keyword {}", command)) {
+        Ok(reply) if reply.trim() == "ok" => {}
+        Ok(reply) => error!("Hyprland rejected 'keyword {}': {}", command, reply.trim()),
+        Err(e) => error!("Failed to send 'keyword {}': {}", command, e),
+    }
+}
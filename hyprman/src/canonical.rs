@@ -0,0 +1,126 @@
+use hyprman::event::{HyprlandEvent, event_type};
+use std::{collections::HashSet, sync::Mutex};
+
+// === Canonical Events ===
+
+/// Legacy events and the newer event Hyprland sends right after them with
+/// the same and more information.
+const SUPERSEDED: &[(&str, &str)] = &[
+    ("workspace", "workspacev2"),
+    ("createworkspace", "createworkspacev2"),
+    ("destroyworkspace", "destroyworkspacev2"),
+    ("moveworkspace", "moveworkspacev2"),
+    ("movewindow", "movewindowv2"),
+    ("windowtitle", "windowtitlev2"),
+    ("monitoradded", "monitoraddedv2"),
+    ("monitorremoved", "monitorremovedv2"),
+    // Neither half has every field, so both give way to a merged event.
+    ("activewindow", "activewindowv2"),
+    ("activewindowv2", "activewindowv2"),
+    ("focusedmon", "focusedmonv2"),
+    ("focusedmonv2", "focusedmonv2"),
+];
+
+/// Pairs of events Hyprland sends for the same change, collapsed into one
+/// for clients subscribed with `canonical`. A legacy event is only dropped
+/// once its successor has been seen, as older Hyprland releases lack some.
+#[derive(Debug, Default)]
+pub struct Canonical {
+    seen: Mutex<HashSet<&'static str>>,
+    /// Class and title of the last `activewindow`, for its `activewindowv2`.
+    active_window: Mutex<Option<(String, String)>>,
+    /// Workspace name of the last `focusedmon`, for its `focusedmonv2`.
+    focused_workspace: Mutex<Option<String>>,
+}
+
+impl Canonical {
+    /// Whether canonical subscribers get another event for this change
+    /// instead of `event`.
+    pub fn supersedes(&self, event: &HyprlandEvent) -> bool {
+        successor(event).is_some_and(|successor| self.seen.lock().unwrap().contains(successor))
+    }
+
+    /// Whether `event` is an old event Hyprland follows with a newer one, as
+    /// dropped by `suppress_legacy_events`.
+    pub fn is_legacy(&self, event: &HyprlandEvent) -> bool {
+        successor(event).is_some_and(|successor| {
+            successor != event_type(event) && self.seen.lock().unwrap().contains(successor)
+        })
+    }
+
+    /// Notes which events Hyprland sends and returns the merged event
+    /// `event` completes, if any.
+    pub fn handle(&self, event: &HyprlandEvent) -> Option<HyprlandEvent> {
+        if let Some((_, successor)) = SUPERSEDED
+            .iter()
+            .find(|(_, successor)| *successor == event_type(event))
+        {
+            self.seen.lock().unwrap().insert(*successor);
+        }
+        match event {
+            HyprlandEvent::ActiveWindow {
+                window_class,
+                window_title,
+            } => {
+                *self.active_window.lock().unwrap() =
+                    Some((window_class.clone(), window_title.clone()));
+                None
+            }
+            HyprlandEvent::ActiveWindowV2 { window_address } => {
+                let (window_class, window_title) = self
+                    .active_window
+                    .lock()
+                    .unwrap()
+                    .take()
+                    .unwrap_or_default();
+                Some(HyprlandEvent::ActiveWindowChanged {
+                    window_address: window_address.clone(),
+                    window_class,
+                    window_title,
+                })
+            }
+            HyprlandEvent::FocusedMon { workspace_name, .. } => {
+                *self.focused_workspace.lock().unwrap() = Some(workspace_name.clone());
+                None
+            }
+            HyprlandEvent::FocusedMonV2 {
+                monitor_name,
+                workspace_id,
+            } => Some(HyprlandEvent::FocusedMonChanged {
+                monitor_name: monitor_name.clone(),
+                workspace_id: *workspace_id,
+                workspace_name: self
+                    .focused_workspace
+                    .lock()
+                    .unwrap()
+                    .take()
+                    .unwrap_or_default(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+fn successor(event: &HyprlandEvent) -> Option<&'static str> {
+    let name = event_type(event);
+    SUPERSEDED
+        .iter()
+        .find(|(legacy, _)| *legacy == name)
+        .map(|(_, successor)| *successor)
+}
+
+/// The names a canonical subscriber naming `filter` wants: the event itself
+/// and what replaces it.
+pub fn filter_names(filter: &str) -> Vec<String> {
+    let mut names = vec![filter.to_string()];
+    match filter {
+        "activewindow" | "activewindowv2" => names.push("activewindowchanged".to_string()),
+        "focusedmon" | "focusedmonv2" => names.push("focusedmonchanged".to_string()),
+        _ => {
+            if let Some((_, successor)) = SUPERSEDED.iter().find(|(legacy, _)| *legacy == filter) {
+                names.push(successor.to_string());
+            }
+        }
+    }
+    names
+}
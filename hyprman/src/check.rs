@@ -0,0 +1,152 @@
+use crate::{Config, binds, control, hooks, windowrules};
+use hyprman::{
+    error::{HyprmanError, Result},
+    event::EVENT_TYPES,
+    hypr::{socket1_path, socket2_path},
+};
+use std::{collections::HashMap, path::Path};
+
+// === Config Check ===
+
+/// Longest socket path the kernel accepts, `sun_path` without its NUL.
+const MAX_SOCKET_PATH: usize = 107;
+
+/// Problems found in the config, printed by `hyprman check`.
+#[derive(Default)]
+pub struct Findings {
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl Findings {
+    /// Something that keeps the daemon from working as configured.
+    pub fn error(&mut self, message: String) {
+        self.errors.push(message);
+    }
+
+    /// Something that works but is most likely not what was meant.
+    pub fn warning(&mut self, message: String) {
+        self.warnings.push(message);
+    }
+
+    /// Reports an event name that no event has, suggesting the closest one.
+    pub fn check_event(&mut self, context: &str, name: &str) {
+        if EVENT_TYPES.contains(&name) {
+            return;
+        }
+        let closest = EVENT_TYPES
+            .iter()
+            .map(|known| (distance(name, known), known))
+            .min()
+            .filter(|(distance, _)| *distance <= 3);
+        match closest {
+            Some((_, known)) => self.error(format!(
+                "{}: unknown event '{}', did you mean '{}'?",
+                context, name, known
+            )),
+            None => self.error(format!(
+                "{}: unknown event '{}'; 'hyprman completions' lists the known ones",
+                context, name
+            )),
+        }
+    }
+}
+
+/// Edit distance between two event names.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+fn check_sockets(config: &Config, findings: &mut Findings) {
+    let mut sockets = vec![
+        ("client_socket_path", config.client_socket_path.as_str()),
+        ("control_socket_path", config.control_socket_path.as_str()),
+    ];
+    if let Some(path) = &config.sandbox_socket_path {
+        sockets.push(("sandbox_socket_path", path));
+    }
+    if let Some(path) = &config.raw_socket_path {
+        sockets.push(("raw_socket_path", path));
+    }
+    let mut seen: HashMap<&str, &str> = HashMap::new();
+    for (key, path) in sockets {
+        if path.len() > MAX_SOCKET_PATH {
+            findings.error(format!(
+                "{}: {} is {} bytes long, sockets allow at most {}",
+                key,
+                path,
+                path.len(),
+                MAX_SOCKET_PATH
+            ));
+        }
+        if let Some(parent) = Path::new(path).parent()
+            && !parent.is_dir()
+        {
+            findings.error(format!(
+                "{}: directory {} does not exist",
+                key,
+                parent.display()
+            ));
+        }
+        if let Some(other) = seen.insert(path, key) {
+            findings.error(format!("{} and {} are both {}", other, key, path));
+        }
+    }
+    if let Some(group) = &config.socket_group
+        && control::group_id(group).is_none()
+    {
+        findings.error(format!("socket_group: no group named {}", group));
+    }
+    for (name, path) in [("request", socket1_path()), ("event", socket2_path())] {
+        match path {
+            Ok(path) if !Path::new(&path).exists() => findings.warning(format!(
+                "Hyprland's {} socket {} does not exist; is Hyprland running?",
+                name, path
+            )),
+            Ok(_) => {}
+            Err(e) => findings.warning(format!("Hyprland's {} socket: {}", name, e)),
+        }
+    }
+}
+
+/// Checks the loaded config without starting anything and prints what it
+/// found. Fails with a config error if anything would not work.
+pub fn run_check(config: &Config, config_path: &str) -> Result<()> {
+    let mut findings = Findings::default();
+    check_sockets(config, &mut findings);
+    hooks::check(&config.hooks, &config.sound_player, &mut findings);
+    windowrules::check(&config.window_rules, &mut findings);
+    binds::check(&config.binds, &mut findings);
+    if let Some(multicast) = &config.multicast {
+        multicast.check(&mut findings);
+    }
+
+    for error in &findings.errors {
+        println!("error: {}", error);
+    }
+    for warning in &findings.warnings {
+        println!("warning: {}", warning);
+    }
+    if findings.errors.is_empty() {
+        println!("{}: OK ({} warnings)", config_path, findings.warnings.len());
+        return Ok(());
+    }
+    Err(HyprmanError::Config {
+        path: config_path.to_string(),
+        reason: format!(
+            "{} errors, {} warnings",
+            findings.errors.len(),
+            findings.warnings.len()
+        ),
+    })
+}
@@ -0,0 +1,311 @@
+use crate::completions::Shell;
+use crate::usage::{Flag, GLOBAL_FLAGS, SUBCOMMANDS, Subcommand};
+use hyprman::error::{HyprmanError, Result};
+
+// === Command Line ===
+
+/// What to run.
+pub enum Command {
+    Daemon {
+        rules_dry_run: bool,
+        /// Skip daemonizing.
+        foreground: bool,
+    },
+    Restart,
+    Kill,
+    Client {
+        filter: String,
+    },
+    ActiveWindow {
+        fields: Option<Vec<String>>,
+    },
+    Workspaces,
+    Groups,
+    Check,
+    Trace,
+    Schema,
+    /// A line for the running daemon's control socket, e.g. `dnd toggle`.
+    Control(String),
+    Version,
+    Completions(Shell),
+    Manpage,
+    /// General help, or that of one command.
+    Help(Option<&'static Subcommand>),
+}
+
+pub struct Cli {
+    pub command: Command,
+    pub output: Option<String>,
+    pub dedupe_output: bool,
+    pub config: Option<String>,
+    pub profile: Option<String>,
+    pub instance: Option<String>,
+    pub socket1: Option<String>,
+    pub socket2: Option<String>,
+}
+
+impl Cli {
+    /// Modes that connect to the daemon's client socket, which
+    /// `$HYPRMAN_SOCKET` overrides.
+    pub fn is_client_mode(&self) -> bool {
+        matches!(
+            self.command,
+            Command::Client { .. }
+                | Command::ActiveWindow { .. }
+                | Command::Workspaces
+                | Command::Groups
+        )
+    }
+}
+
+fn find_command(name: &str) -> Option<&'static Subcommand> {
+    SUBCOMMANDS
+        .iter()
+        .find(|c| c.name == name || c.aliases.contains(&name))
+}
+
+/// Finds an option any command knows, to tell whether it takes a value.
+fn find_flag(arg: &str) -> Option<&'static Flag> {
+    let all = GLOBAL_FLAGS
+        .iter()
+        .chain(SUBCOMMANDS.iter().flat_map(|c| c.flags));
+    match arg.strip_prefix("--") {
+        Some(long) => all.into_iter().find(|f| f.long == long),
+        None => {
+            let mut chars = arg.strip_prefix('-')?.chars();
+            let short = chars.next()?;
+            if chars.next().is_some() {
+                return None;
+            }
+            all.into_iter().find(|f| f.short == Some(short))
+        }
+    }
+}
+
+/// Parses the arguments after the program name. Options may come before or
+/// after the command and take their value as `--name VALUE` or
+/// `--name=VALUE`; `--` ends the options.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Cli> {
+    parse_args(args).map_err(HyprmanError::Usage)
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> std::result::Result<Cli, String> {
+    let mut args = args.into_iter();
+    let mut command: Option<&'static Subcommand> = None;
+    let mut positional: Vec<String> = Vec::new();
+    let mut flags: Vec<(&'static Flag, Option<String>)> = Vec::new();
+    while let Some(arg) = args.next() {
+        if command.is_none()
+            && positional.is_empty()
+            && let Some(found) = find_command(&arg)
+        {
+            command = Some(found);
+            continue;
+        }
+        if arg == "--" {
+            positional.extend(args.by_ref());
+            break;
+        }
+        if !arg.starts_with('-') || arg == "-" {
+            positional.push(arg);
+            continue;
+        }
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_string())),
+            None => (arg.as_str(), None),
+        };
+        let Some(flag) = find_flag(name) else {
+            return Err(format!("unknown option '{}'", name));
+        };
+        let value = match (flag.value, inline) {
+            (Some(_), Some(value)) => Some(value),
+            (Some(placeholder), None) => Some(
+                args.next()
+                    .ok_or_else(|| format!("--{} needs {}", flag.long, placeholder))?,
+            ),
+            (None, Some(_)) => return Err(format!("--{} takes no value", flag.long)),
+            (None, None) => None,
+        };
+        flags.push((flag, value));
+    }
+
+    let explicit = command.is_some();
+    let command = command.unwrap_or(&SUBCOMMANDS[0]);
+    for (flag, _) in &flags {
+        if !GLOBAL_FLAGS
+            .iter()
+            .chain(command.flags)
+            .any(|f| f.long == flag.long)
+        {
+            return Err(format!(
+                "'{}' does not take --{}; see 'hyprman help {}'",
+                command.name, flag.long, command.name
+            ));
+        }
+    }
+    let value = |long: &str| {
+        flags
+            .iter()
+            .rev()
+            .find(|(f, _)| f.long == long)
+            .map(|(_, value)| value.clone())
+    };
+    let mut cli = Cli {
+        command: Command::Help(explicit.then_some(command)),
+        output: value("output").flatten(),
+        dedupe_output: value("dedupe-output").is_some(),
+        config: value("config").flatten(),
+        profile: value("profile").flatten(),
+        instance: value("instance").flatten(),
+        socket1: value("socket1").flatten(),
+        socket2: value("socket2").flatten(),
+    };
+    if value("help").is_some() {
+        return Ok(cli);
+    }
+    let usage = || format!("usage: hyprman {} {}", command.name, command.args);
+    let arguments = positional.len();
+    let at_most = |count: usize| match arguments <= count {
+        true => Ok(()),
+        false => Err(usage()),
+    };
+    let line = positional.join(" ");
+    let control = |name: &str| match line.is_empty() {
+        true => name.to_string(),
+        false => format!("{} {}", name, line),
+    };
+    cli.command = match command.name {
+        "client" => {
+            at_most(1)?;
+            Command::Client {
+                filter: positional.pop().unwrap_or_else(|| "all".to_string()),
+            }
+        }
+        "activewindow" => {
+            at_most(1)?;
+            let fields = value("fields").flatten().or(positional.pop());
+            Command::ActiveWindow {
+                fields: fields.map(|f| f.split(',').map(|f| f.trim().to_string()).collect()),
+            }
+        }
+        "workspaces" => at_most(0).map(|_| Command::Workspaces)?,
+        "groups" => at_most(0).map(|_| Command::Groups)?,
+        "daemon" => at_most(0).map(|_| Command::Daemon {
+            rules_dry_run: value("rules-dry-run").is_some(),
+            foreground: value("foreground").is_some() || value("no-daemonize").is_some(),
+        })?,
+        "restart" => at_most(0).map(|_| Command::Restart)?,
+        "kill" => at_most(0).map(|_| Command::Kill)?,
+        "dnd" | "profile" => at_most(1).map(|_| Command::Control(control(command.name)))?,
+        "reload" | "status" => at_most(0).map(|_| Command::Control(control(command.name)))?,
+        "trigger" if arguments == 1 => Command::Control(control("trigger")),
+        "get" if arguments > 0 => Command::Control(control("get")),
+        "check" => at_most(0).map(|_| Command::Check)?,
+        "trace" => at_most(0).map(|_| Command::Trace)?,
+        "schema" => at_most(0).map(|_| Command::Schema)?,
+        "version" => at_most(0).map(|_| Command::Version)?,
+        "completions" if arguments == 1 => Command::Completions(
+            Shell::parse(&line).ok_or_else(|| format!("unknown shell '{}'", line))?,
+        ),
+        "manpage" => at_most(0).map(|_| Command::Manpage)?,
+        "help" => {
+            at_most(1)?;
+            match positional.first() {
+                Some(name) => Command::Help(Some(
+                    find_command(name).ok_or_else(|| format!("unknown command '{}'", name))?,
+                )),
+                None => Command::Help(None),
+            }
+        }
+        _ => return Err(usage()),
+    };
+    Ok(cli)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> std::result::Result<Cli, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn runs_the_all_client_without_a_command() {
+        for args in [&[][..], &["client"], &["-f"], &["--filter"]] {
+            match parse(args).unwrap().command {
+                Command::Client { filter } => assert_eq!(filter, "all"),
+                _ => panic!("expected the client for {:?}", args),
+            }
+        }
+        match parse(&["-o", "/tmp/events"]).unwrap() {
+            Cli {
+                command: Command::Client { filter },
+                output,
+                ..
+            } => {
+                assert_eq!(filter, "all");
+                assert_eq!(output.as_deref(), Some("/tmp/events"));
+            }
+            _ => panic!("expected the client with --output"),
+        }
+    }
+
+    #[test]
+    fn keeps_the_legacy_flags() {
+        match parse(&["-f", "workspacev2,activewindowv2"])
+            .unwrap()
+            .command
+        {
+            Command::Client { filter } => assert_eq!(filter, "workspacev2,activewindowv2"),
+            _ => panic!("expected the client"),
+        }
+        match parse(&["-a", "class, title"]).unwrap().command {
+            Command::ActiveWindow { fields } => {
+                assert_eq!(fields.unwrap(), ["class", "title"])
+            }
+            _ => panic!("expected the activewindow client"),
+        }
+        assert!(matches!(
+            parse(&["--activewindow"]).unwrap().command,
+            Command::ActiveWindow { fields: None }
+        ));
+        assert!(matches!(
+            parse(&["-d"]).unwrap().command,
+            Command::Daemon {
+                rules_dry_run: false,
+                foreground: false
+            }
+        ));
+        assert!(matches!(
+            parse(&["--daemon", "--foreground"]).unwrap().command,
+            Command::Daemon {
+                foreground: true,
+                ..
+            }
+        ));
+        assert!(matches!(
+            parse(&["-w"]).unwrap().command,
+            Command::Workspaces
+        ));
+        assert!(matches!(parse(&["-g"]).unwrap().command, Command::Groups));
+        assert!(matches!(parse(&["-r"]).unwrap().command, Command::Restart));
+        assert!(matches!(parse(&["-k"]).unwrap().command, Command::Kill));
+        assert!(matches!(parse(&["-V"]).unwrap().command, Command::Version));
+    }
+
+    #[test]
+    fn takes_options_around_the_command() {
+        let cli = parse(&["--profile", "work", "-w", "--output=/tmp/ws"]).unwrap();
+        assert!(matches!(cli.command, Command::Workspaces));
+        assert_eq!(cli.profile.as_deref(), Some("work"));
+        assert_eq!(cli.output.as_deref(), Some("/tmp/ws"));
+        match parse(&["dnd", "toggle"]).unwrap().command {
+            Command::Control(line) => assert_eq!(line, "dnd toggle"),
+            _ => panic!("expected a control command"),
+        }
+        assert!(parse(&["--bogus"]).is_err());
+        assert!(parse(&["-w", "--fields", "class"]).is_err());
+        assert!(parse(&["-k", "extra"]).is_err());
+    }
+}
@@ -0,0 +1,205 @@
+use crate::event::{Envelope, HyprlandEvent, PROTOCOL_VERSION};
+use log::{info, warn};
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+};
+
+// === Client Side of the hyprman Socket ===
+
+/// An event subscription to a running hyprman daemon.
+pub struct Connection {
+    reader: BufReader<UnixStream>,
+    metadata: String,
+    capabilities: Vec<String>,
+    last_seq: Option<u64>,
+}
+
+impl Connection {
+    /// Connects to the daemon socket, sends the subscription line and reads
+    /// the metadata header. Append `,resume_from=<seq>` to the subscription
+    /// to be sent the events missed since `last_seq` of an earlier connection.
+    /// Keys are asked for as declared, whatever `key_case` the daemon
+    /// defaults to, so `next_event` can read them; a `keys=` option in the
+    /// subscription still takes precedence.
+    pub fn connect(socket_path: &str, subscription: &str) -> io::Result<Connection> {
+        let mut stream = UnixStream::connect(socket_path)?;
+        stream.write_all(format!("keys=mixed,{}\n", subscription.trim_end()).as_bytes())?;
+        let mut reader = BufReader::new(stream);
+        let mut metadata = String::new();
+        reader.read_line(&mut metadata)?;
+        let metadata = metadata.trim_end().to_string();
+        let mut capabilities = Vec::new();
+        match serde_json::from_str::<HyprlandEvent>(&metadata) {
+            Ok(HyprlandEvent::Metadata {
+                protocol_version,
+                capabilities: advertised,
+                ..
+            }) => {
+                capabilities = advertised;
+                info!("Daemon metadata: {}", metadata);
+                if protocol_version != PROTOCOL_VERSION {
+                    warn!(
+                        "Daemon speaks protocol version {}, expected {}",
+                        protocol_version, PROTOCOL_VERSION
+                    );
+                }
+            }
+            _ => warn!("Unexpected metadata header from daemon: {}", metadata),
+        }
+        Ok(Connection {
+            reader,
+            metadata,
+            capabilities,
+            last_seq: None,
+        })
+    }
+
+    /// The raw metadata header sent by the daemon.
+    pub fn metadata(&self) -> &str {
+        &self.metadata
+    }
+
+    /// Whether the daemon advertised a feature, e.g. `"ack"` or
+    /// `"format=protobuf"`.
+    pub fn supports(&self, capability: &str) -> bool {
+        self.capabilities.iter().any(|c| c == capability)
+    }
+
+    /// Asks the daemon to hold back events, e.g. while a widget is hidden.
+    pub fn pause(&mut self) -> io::Result<()> {
+        self.reader.get_mut().write_all(b"pause\n")
+    }
+
+    /// Resumes delivery; the daemon first sends what was held back, or a
+    /// `Snapshot` if too much was missed.
+    pub fn resume(&mut self) -> io::Result<()> {
+        self.reader.get_mut().write_all(b"resume\n")
+    }
+
+    /// Acknowledges every event up to `seq` on a connection subscribed with
+    /// `ack=<window>`, letting the daemon send the next ones.
+    pub fn ack(&mut self, seq: u64) -> io::Result<()> {
+        self.reader
+            .get_mut()
+            .write_all(format!("ack {}\n", seq).as_bytes())
+    }
+
+    /// Asks the daemon to run a Hyprland dispatch such as `"workspace 2"`;
+    /// the outcome arrives as a `DispatchReply` among the events.
+    pub fn dispatch(&mut self, command: &str) -> io::Result<()> {
+        self.reader
+            .get_mut()
+            .write_all(format!("dispatch {}\n", command).as_bytes())
+    }
+
+    /// Reads the next raw JSON line, or `None` once the daemon hangs up.
+    pub fn next_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(line.trim_end().to_string()))
+    }
+
+    /// Sequence number of the last event read with `next_event`.
+    pub fn last_seq(&self) -> Option<u64> {
+        self.last_seq
+    }
+
+    /// Reads and deserializes the next event. Events meant for this
+    /// connection only, such as `DispatchReply` and `DaemonShutdown`, come
+    /// without an envelope and leave `last_seq` as it is.
+    pub fn next_event(&mut self) -> io::Result<Option<HyprlandEvent>> {
+        let Some(line) = self.next_line()? else {
+            return Ok(None);
+        };
+        match serde_json::from_str::<Envelope>(&line) {
+            Ok(envelope) => {
+                self.last_seq = Some(envelope.seq);
+                Ok(Some(envelope.event))
+            }
+            Err(e) => serde_json::from_str::<HyprlandEvent>(&line)
+                .map(Some)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, e)),
+        }
+    }
+}
+
+impl Iterator for Connection {
+    type Item = io::Result<HyprlandEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_event().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{os::unix::net::UnixListener, thread};
+
+    /// Connects to a fake daemon that answers the handshake with `lines`.
+    fn connect(name: &str, lines: &'static [&'static str]) -> Connection {
+        let path =
+            std::env::temp_dir().join(format!("hyprman-{}-{}.sock", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut handshake = String::new();
+            BufReader::new(stream.try_clone().unwrap())
+                .read_line(&mut handshake)
+                .unwrap();
+            for line in lines {
+                writeln!(stream, "{}", line).unwrap();
+            }
+        });
+        let connection = Connection::connect(path.to_str().unwrap(), "all").unwrap();
+        let _ = std::fs::remove_file(&path);
+        connection
+    }
+
+    const METADATA: &str = r#"{"event":"Metadata","data":{"hyprland_version":null,"instance_signature":"test","hyprman_version":"0.1.0","protocol_version":2,"seq":0,"capabilities":["dispatch"]}}"#;
+
+    #[test]
+    fn reads_bare_dispatch_replies() {
+        let mut connection = connect(
+            "dispatch",
+            &[
+                METADATA,
+                r#"{"seq":3,"timestamp":0,"event":"Submap","data":{"submap_name":"resize"}}"#,
+                r#"{"event":"DispatchReply","data":{"command":"workspace 2","reply":"ok","error":null}}"#,
+            ],
+        );
+        assert!(matches!(
+            connection.next_event().unwrap(),
+            Some(HyprlandEvent::Submap { .. })
+        ));
+        match connection.next_event().unwrap() {
+            Some(HyprlandEvent::DispatchReply { reply, .. }) => {
+                assert_eq!(reply.as_deref(), Some("ok"))
+            }
+            other => panic!("expected a DispatchReply, got {:?}", other),
+        }
+        assert_eq!(connection.last_seq(), Some(3));
+        assert!(connection.next_event().unwrap().is_none());
+    }
+
+    #[test]
+    fn reads_daemon_shutdown() {
+        let connection = connect(
+            "shutdown",
+            &[
+                METADATA,
+                r#"{"seq":1,"timestamp":0,"event":"ConfigReloaded"}"#,
+                r#"{"event":"DaemonShutdown"}"#,
+            ],
+        );
+        let events: Vec<HyprlandEvent> = connection.map(Result::unwrap).collect();
+        assert!(matches!(
+            events.as_slice(),
+            [HyprlandEvent::ConfigReloaded, HyprlandEvent::DaemonShutdown]
+        ));
+    }
+}
@@ -0,0 +1,260 @@
+use crate::usage::{Flag, GLOBAL_FLAGS, SUBCOMMANDS, Subcommand};
+use hyprman::event::EVENT_TYPES;
+
+// === Shell Completions ===
+
+/// Shells `hyprman completions` writes a script for.
+#[derive(Debug, Clone, Copy)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    pub const NAMES: &[&str] = &["bash", "zsh", "fish"];
+
+    pub fn parse(name: &str) -> Option<Shell> {
+        match name {
+            "bash" => Some(Shell::Bash),
+            "zsh" => Some(Shell::Zsh),
+            "fish" => Some(Shell::Fish),
+            _ => None,
+        }
+    }
+}
+
+/// Words offered for the positional arguments of a command.
+fn arguments(command: &Subcommand) -> Vec<&'static str> {
+    match command.name {
+        "client" => std::iter::once("all")
+            .chain(EVENT_TYPES.iter().copied())
+            .collect(),
+        "dnd" => vec!["on", "off", "toggle"],
+        "get" => vec!["title-history", "focus-history", "groups", "fullscreen"],
+        "help" => SUBCOMMANDS.iter().map(|c| c.name).collect(),
+        "completions" => Shell::NAMES.to_vec(),
+        _ => Vec::new(),
+    }
+}
+
+/// Options a command takes, its own first.
+fn flags(command: &Subcommand) -> impl Iterator<Item = &'static Flag> + '_ {
+    command.flags.iter().chain(GLOBAL_FLAGS)
+}
+
+/// Whether the value of a flag names a file.
+fn takes_path(flag: &Flag) -> bool {
+    matches!(flag.value, Some("PATH" | "FILE"))
+}
+
+fn command_names() -> String {
+    SUBCOMMANDS
+        .iter()
+        .map(|c| c.name)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn flag_names<'a>(flags: impl IntoIterator<Item = &'a Flag>) -> Vec<String> {
+    flags
+        .into_iter()
+        .flat_map(|f| {
+            let short = f.short.map(|short| format!("-{}", short));
+            std::iter::once(format!("--{}", f.long)).chain(short)
+        })
+        .collect()
+}
+
+/// First sentence of a description, as completion menus have little room.
+fn summary(description: &str) -> &str {
+    description
+        .split_once(". ")
+        .map_or(description, |(first, _)| first)
+        .trim_end_matches('.')
+}
+
+/// Prints the completion script for a shell.
+pub fn print_completions(shell: Shell) {
+    match shell {
+        Shell::Bash => print_bash(),
+        Shell::Zsh => print_zsh(),
+        Shell::Fish => print_fish(),
+    }
+}
+
+fn print_bash() {
+    let mut path_flags = flag_names(
+        SUBCOMMANDS
+            .iter()
+            .flat_map(|c| c.flags)
+            .chain(GLOBAL_FLAGS)
+            .filter(|f| takes_path(f)),
+    );
+    path_flags.sort();
+    path_flags.dedup();
+    println!("_hyprman() {{");
+    println!("    local cur=\"${{COMP_WORDS[COMP_CWORD]}}\"");
+    println!("    local prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"");
+    println!("    local command=\"\" i");
+    println!("    for ((i = 1; i < COMP_CWORD; i++)); do");
+    println!("        case \"${{COMP_WORDS[i]}}\" in");
+    println!("            {})", command_names().replace(' ', "|"));
+    println!("                command=\"${{COMP_WORDS[i]}}\"");
+    println!("                break;;");
+    println!("        esac");
+    println!("    done");
+    println!("    case \"$prev\" in");
+    println!("        {})", path_flags.join("|"));
+    println!("            COMPREPLY=($(compgen -f -- \"$cur\"))");
+    println!("            return;;");
+    println!("    esac");
+    println!("    local words");
+    println!("    case \"$command\" in");
+    println!(
+        "        \"\") words=\"{} {}\";;",
+        command_names(),
+        flag_names(flags(&SUBCOMMANDS[0])).join(" ")
+    );
+    for command in SUBCOMMANDS {
+        let mut words = flag_names(flags(command));
+        words.extend(arguments(command).into_iter().map(str::to_string));
+        println!("        {}) words=\"{}\";;", command.name, words.join(" "));
+    }
+    println!("    esac");
+    println!("    COMPREPLY=($(compgen -W \"$words\" -- \"$cur\"))");
+    println!("}}");
+    println!("complete -F _hyprman hyprman");
+}
+
+/// Quotes a description for an `_arguments` spec.
+fn zsh_description(description: &str) -> String {
+    summary(description)
+        .replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace(':', "\\:")
+}
+
+fn zsh_flag_spec(flag: &Flag) -> String {
+    let description = zsh_description(flag.description);
+    let value = match flag.value {
+        Some(_) if takes_path(flag) => ":path:_files".to_string(),
+        Some(value) => format!(":{}: ", value.to_lowercase()),
+        None => String::new(),
+    };
+    match flag.short {
+        Some(short) => format!(
+            "'(-{short} --{long})'{{-{short},--{long}}}'[{description}]{value}'",
+            short = short,
+            long = flag.long,
+        ),
+        None => format!("'--{}[{}]{}'", flag.long, description, value),
+    }
+}
+
+fn print_zsh() {
+    println!("#compdef hyprman");
+    println!();
+    println!("_hyprman() {{");
+    println!("    local -a commands");
+    println!("    commands=(");
+    for command in SUBCOMMANDS {
+        println!(
+            "        '{}:{}'",
+            command.name,
+            summary(command.description).replace('\'', "'\\''")
+        );
+    }
+    println!("    )");
+    println!("    local command i");
+    println!("    for ((i = 2; i < CURRENT; i++)); do");
+    println!("        case $words[i] in");
+    println!("            {})", command_names().replace(' ', "|"));
+    println!("                command=$words[i]");
+    println!("                break;;");
+    println!("        esac");
+    println!("    done");
+    println!("    case $command in");
+    println!("        '')");
+    println!("            _arguments \\");
+    for flag in flags(&SUBCOMMANDS[0]) {
+        println!("                {} \\", zsh_flag_spec(flag));
+    }
+    println!("                '*: :_describe command commands';;");
+    for command in SUBCOMMANDS {
+        println!("        {})", command.name);
+        println!("            _arguments \\");
+        for flag in flags(command) {
+            println!("                {} \\", zsh_flag_spec(flag));
+        }
+        let arguments = arguments(command);
+        match arguments.is_empty() {
+            true => println!("                '*: :';;"),
+            false => println!("                '*: :({})';;", arguments.join(" ")),
+        }
+    }
+    println!("    esac");
+    println!("}}");
+    println!();
+    println!("_hyprman \"$@\"");
+}
+
+fn fish_flag(condition: &str, flag: &Flag) -> String {
+    let mut line = String::from("complete -c hyprman");
+    if !condition.is_empty() {
+        line.push(' ');
+        line.push_str(condition);
+    }
+    if let Some(short) = flag.short {
+        line.push_str(&format!(" -s {}", short));
+    }
+    line.push_str(&format!(" -l {}", flag.long));
+    if takes_path(flag) {
+        line.push_str(" -r -F");
+    } else if flag.value.is_some() {
+        line.push_str(" -x");
+    }
+    line.push_str(&format!(" -d '{}'", fish_quote(flag.description)));
+    line
+}
+
+fn fish_quote(description: &str) -> String {
+    summary(description)
+        .replace('\\', "\\\\")
+        .replace('\'', "\\'")
+}
+
+fn print_fish() {
+    let names = command_names();
+    println!("complete -c hyprman -f");
+    for command in SUBCOMMANDS {
+        println!(
+            "complete -c hyprman -n 'not __fish_seen_subcommand_from {}' -a {} -d '{}'",
+            names,
+            command.name,
+            fish_quote(command.description)
+        );
+    }
+    for flag in GLOBAL_FLAGS {
+        println!("{}", fish_flag("", flag));
+    }
+    for flag in SUBCOMMANDS[0].flags {
+        let condition = format!("-n 'not __fish_seen_subcommand_from {}'", names);
+        println!("{}", fish_flag(&condition, flag));
+    }
+    for command in SUBCOMMANDS {
+        let condition = format!("-n '__fish_seen_subcommand_from {}'", command.name);
+        for flag in command.flags {
+            println!("{}", fish_flag(&condition, flag));
+        }
+        let arguments = arguments(command);
+        if !arguments.is_empty() {
+            println!(
+                "complete -c hyprman {} -a '{}'",
+                condition,
+                arguments.join(" ")
+            );
+        }
+    }
+}
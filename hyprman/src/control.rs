@@ -0,0 +1,555 @@
+use crate::{
+    Config, Daemon, SocketFile, bind_socket, binds, dispatch, hooks::Hooks, logging,
+    try_load_config,
+};
+use hyprman::error::{self, HyprmanError};
+use hyprman::event::{Address, HyprlandEvent};
+use log::{error, info};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::{
+    ffi::{CStr, CString},
+    fs,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    os::unix::{
+        fs::PermissionsExt,
+        io::AsRawFd,
+        net::{UnixListener, UnixStream},
+    },
+    path::Path,
+    sync::{Arc, atomic::Ordering},
+    thread,
+};
+
+// === Control Commands ===
+
+pub fn default_control_socket_path() -> String {
+    "control.sock".to_string()
+}
+
+pub fn default_control_socket_mode() -> u32 {
+    0o600
+}
+
+/// A request sent as a single line over the control socket. The daemon
+/// answers with a single JSON line and closes the connection.
+#[derive(Debug)]
+pub enum ControlCommand {
+    /// Report or change the do-not-disturb state.
+    Dnd(Option<Switch>),
+    /// Report or switch the active hook profile; `none` deactivates it.
+    Profile(Option<String>),
+    /// Re-read the config file and replace the hooks.
+    Reload,
+    /// Report the daemon's state and resource use.
+    Status,
+    /// Send a `trigger` event for hooks to react to.
+    Trigger(String),
+    /// Report state the daemon keeps track of.
+    Get(Query),
+}
+
+/// What `get` reports.
+#[derive(Debug)]
+pub enum Query {
+    /// Recent titles of a window, oldest first.
+    TitleHistory(Address),
+    /// Recently focused windows, newest first, optionally only the latest few.
+    FocusHistory(Option<usize>),
+    /// Window groups with their tabs in order.
+    Groups,
+    /// The fullscreen window of each workspace that has one.
+    Fullscreen,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Switch {
+    On,
+    Off,
+    Toggle,
+}
+
+impl ControlCommand {
+    /// The name used on the wire and in `control_permissions`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ControlCommand::Dnd(_) => "dnd",
+            ControlCommand::Profile(_) => "profile",
+            ControlCommand::Reload => "reload",
+            ControlCommand::Status => "status",
+            ControlCommand::Trigger(_) => "trigger",
+            ControlCommand::Get(_) => "get",
+        }
+    }
+
+    /// Whether the command changes anything; sandboxed peers may only run
+    /// the others.
+    pub fn is_privileged(&self) -> bool {
+        !matches!(self, ControlCommand::Status | ControlCommand::Get(_))
+    }
+
+    /// Returns `None` when the line does not name a known command.
+    pub fn parse(line: &str) -> Option<Result<ControlCommand, String>> {
+        let mut words = line.split_whitespace();
+        let command = words.next()?.to_lowercase();
+        let args: Vec<&str> = words.collect();
+        match command.as_str() {
+            "dnd" => Some(match args.as_slice() {
+                [] => Ok(ControlCommand::Dnd(None)),
+                ["on"] => Ok(ControlCommand::Dnd(Some(Switch::On))),
+                ["off"] => Ok(ControlCommand::Dnd(Some(Switch::Off))),
+                ["toggle"] => Ok(ControlCommand::Dnd(Some(Switch::Toggle))),
+                _ => Err("usage: dnd [on|off|toggle]".to_string()),
+            }),
+            "profile" => Some(match args.as_slice() {
+                [] => Ok(ControlCommand::Profile(None)),
+                [name] => Ok(ControlCommand::Profile(Some(name.to_string()))),
+                _ => Err("usage: profile [NAME|none]".to_string()),
+            }),
+            "reload" => Some(match args.as_slice() {
+                [] => Ok(ControlCommand::Reload),
+                _ => Err("usage: reload".to_string()),
+            }),
+            "status" => Some(match args.as_slice() {
+                [] => Ok(ControlCommand::Status),
+                _ => Err("usage: status".to_string()),
+            }),
+            "trigger" => Some(match args.as_slice() {
+                [name] => Ok(ControlCommand::Trigger(name.to_string())),
+                _ => Err("usage: trigger NAME".to_string()),
+            }),
+            "get" => Some(match args.as_slice() {
+                ["title-history", address] => Ok(ControlCommand::Get(Query::TitleHistory(
+                    Address::from(*address),
+                ))),
+                ["groups"] => Ok(ControlCommand::Get(Query::Groups)),
+                ["fullscreen"] => Ok(ControlCommand::Get(Query::Fullscreen)),
+                ["focus-history"] => Ok(ControlCommand::Get(Query::FocusHistory(None))),
+                ["focus-history", count] => match count.parse() {
+                    Ok(count) => Ok(ControlCommand::Get(Query::FocusHistory(Some(count)))),
+                    Err(_) => Err(format!("invalid count '{}'", count)),
+                },
+                _ => Err(
+                    "usage: get title-history ADDRESS | get focus-history [COUNT] | get groups | get fullscreen"
+                        .to_string(),
+                ),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Binds the control socket, which is kept separate from the client socket
+/// so event consumers cannot run admin commands.
+pub fn bind_control_socket(
+    control_socket_path: &str,
+    mode: u32,
+) -> error::Result<(UnixListener, SocketFile)> {
+    let bound = bind_socket(control_socket_path)?;
+    if let Err(e) = fs::set_permissions(control_socket_path, fs::Permissions::from_mode(mode)) {
+        error!(
+            "Failed to set mode {:o} on control socket {}: {}",
+            mode, control_socket_path, e
+        );
+    }
+    info!("Control server listening on {}", control_socket_path);
+    Ok(bound)
+}
+
+pub fn control_server_thread(listener: UnixListener, daemon: Arc<Daemon>) {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let daemon = daemon.clone();
+                thread::spawn(move || control_handler(stream, daemon));
+            }
+            Err(e) => error!("Failed to accept control connection: {}", e),
+        }
+    }
+}
+
+fn control_handler(stream: UnixStream, daemon: Arc<Daemon>) {
+    let mut reader = BufReader::new(stream.try_clone().expect("Failed to clone stream"));
+    let mut writer = BufWriter::new(stream);
+    let mut line = String::new();
+    if let Err(e) = reader.read_line(&mut line) {
+        error!("Failed to read control command: {}", e);
+        return;
+    }
+    let line = line.trim();
+    info!("Control command: {}", line);
+    let peer = match peer_credentials(writer.get_ref()) {
+        Ok(peer) => peer,
+        Err(e) => {
+            error!("Failed to read control peer credentials: {}", e);
+            return;
+        }
+    };
+    let reply = match ControlCommand::parse(line) {
+        Some(Ok(command)) if command.is_privileged() && is_sandboxed(&peer) => {
+            info!(
+                "Denied control command '{}' for sandboxed pid {}",
+                command.name(),
+                peer.pid
+            );
+            json!({ "error": format!("permission denied for '{}' from a sandbox", command.name()) })
+        }
+        Some(Ok(command)) if !is_allowed(&daemon, command.name(), &peer) => {
+            info!(
+                "Denied control command '{}' for uid {} (pid {})",
+                command.name(),
+                peer.uid,
+                peer.pid
+            );
+            json!({ "error": format!("permission denied for '{}'", command.name()) })
+        }
+        Some(Ok(command)) => execute(&daemon, command),
+        Some(Err(e)) => json!({ "error": e }),
+        None => json!({ "error": format!("unknown command '{}'", line) }),
+    };
+    if let Err(e) = writeln!(writer, "{}", reply).and_then(|_| writer.flush()) {
+        error!("Failed to send control reply: {}", e);
+    }
+}
+
+// === Authorization ===
+
+/// Grants the listed commands to the listed users and groups, configured as
+/// `[[control_permissions]]`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ControlPermission {
+    /// Command names such as `dnd`, `dispatch` for dispatches sent over the
+    /// client socket, or `*` for all of them.
+    commands: Vec<String>,
+    #[serde(default)]
+    uids: Vec<u32>,
+    /// Group names; matched against the primary and supplementary groups.
+    #[serde(default)]
+    groups: Vec<String>,
+}
+
+impl ControlPermission {
+    fn grants(&self, command: &str, uid: u32, gid: u32) -> bool {
+        self.commands.iter().any(|c| c == "*" || c == command)
+            && (self.uids.contains(&uid) || self.groups.iter().any(|g| in_group(uid, gid, g)))
+    }
+}
+
+/// Whether the peer may run the named command. Besides the control commands
+/// this covers `dispatch` sent over the client socket.
+pub fn is_allowed(daemon: &Daemon, command: &str, peer: &libc::ucred) -> bool {
+    let permissions = daemon.control_permissions.lock().unwrap();
+    permissions.is_empty()
+        || permissions
+            .iter()
+            .any(|p| p.grants(command, peer.uid, peer.gid))
+}
+
+/// Whether the peer runs inside a Flatpak sandbox, which mounts
+/// `/.flatpak-info` into every sandboxed process.
+pub fn is_sandboxed(peer: &libc::ucred) -> bool {
+    Path::new(&format!("/proc/{}/root/.flatpak-info", peer.pid)).exists()
+}
+
+/// Credentials of the process on the other end of a unix socket.
+pub fn peer_credentials(stream: &UnixStream) -> io::Result<libc::ucred> {
+    let mut cred = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = size_of::<libc::ucred>() as libc::socklen_t;
+    let ret = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(cred)
+}
+
+/// The id of the named group.
+pub fn group_id(group: &str) -> Option<libc::gid_t> {
+    let group = CString::new(group).ok()?;
+    let mut buf = vec![0 as libc::c_char; 16384];
+    let mut grp: libc::group = unsafe { std::mem::zeroed() };
+    let mut result: *mut libc::group = std::ptr::null_mut();
+    let ret = unsafe {
+        libc::getgrnam_r(
+            group.as_ptr(),
+            &mut grp,
+            buf.as_mut_ptr(),
+            buf.len(),
+            &mut result,
+        )
+    };
+    (ret == 0 && !result.is_null()).then_some(grp.gr_gid)
+}
+
+/// Whether the user is in the named group, either as primary group or as
+/// listed member.
+fn in_group(uid: u32, gid: u32, group: &str) -> bool {
+    let Ok(group) = CString::new(group) else {
+        return false;
+    };
+    let mut buf = vec![0 as libc::c_char; 16384];
+    let mut grp: libc::group = unsafe { std::mem::zeroed() };
+    let mut result: *mut libc::group = std::ptr::null_mut();
+    let ret = unsafe {
+        libc::getgrnam_r(
+            group.as_ptr(),
+            &mut grp,
+            buf.as_mut_ptr(),
+            buf.len(),
+            &mut result,
+        )
+    };
+    if ret != 0 || result.is_null() {
+        return false;
+    }
+    if grp.gr_gid == gid {
+        return true;
+    }
+    let Some(user) = user_name(uid) else {
+        return false;
+    };
+    let mut member = grp.gr_mem;
+    while !member.is_null() && !unsafe { *member }.is_null() {
+        if unsafe { CStr::from_ptr(*member) }.to_bytes() == user.as_bytes() {
+            return true;
+        }
+        member = unsafe { member.add(1) };
+    }
+    false
+}
+
+fn user_name(uid: u32) -> Option<String> {
+    let mut buf = vec![0 as libc::c_char; 16384];
+    let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut result: *mut libc::passwd = std::ptr::null_mut();
+    let ret = unsafe { libc::getpwuid_r(uid, &mut pwd, buf.as_mut_ptr(), buf.len(), &mut result) };
+    if ret != 0 || result.is_null() {
+        return None;
+    }
+    Some(
+        unsafe { CStr::from_ptr(pwd.pw_name) }
+            .to_string_lossy()
+            .into_owned(),
+    )
+}
+
+/// Runs a command inside the daemon and builds the reply.
+pub fn execute(daemon: &Daemon, command: ControlCommand) -> Value {
+    match command {
+        ControlCommand::Dnd(switch) => {
+            let (previous, enabled) = match switch {
+                None => {
+                    let enabled = daemon.dnd.load(Ordering::SeqCst);
+                    (enabled, enabled)
+                }
+                Some(Switch::On) => (daemon.dnd.swap(true, Ordering::SeqCst), true),
+                Some(Switch::Off) => (daemon.dnd.swap(false, Ordering::SeqCst), false),
+                Some(Switch::Toggle) => {
+                    let previous = daemon.dnd.fetch_xor(true, Ordering::SeqCst);
+                    (previous, !previous)
+                }
+            };
+            if previous != enabled {
+                dispatch(daemon, HyprlandEvent::Dnd { enabled });
+            }
+            json!({ "dnd": enabled })
+        }
+        ControlCommand::Profile(None) => {
+            json!({ "profile": *daemon.profile.lock().unwrap() })
+        }
+        ControlCommand::Profile(Some(name)) => {
+            let profile = (name != "none").then_some(name);
+            if let Some(name) = &profile
+                && !daemon.hooks.lock().unwrap().has_profile(name)
+            {
+                return json!({ "error": format!("unknown profile '{}'", name) });
+            }
+            let previous = std::mem::replace(&mut *daemon.profile.lock().unwrap(), profile.clone());
+            if previous != profile {
+                dispatch(
+                    daemon,
+                    HyprlandEvent::ProfileChanged {
+                        profile: profile.clone(),
+                    },
+                );
+            }
+            json!({ "profile": profile })
+        }
+        ControlCommand::Status => status(daemon),
+        ControlCommand::Get(Query::TitleHistory(address)) => {
+            let titles = daemon.titles.lock().unwrap().get(&address);
+            json!({ "window_address": address, "titles": titles })
+        }
+        ControlCommand::Get(Query::FocusHistory(count)) => {
+            let mut focus_history = daemon.focus.lock().unwrap().entries();
+            if let Some(count) = count {
+                focus_history.truncate(count);
+            }
+            json!({ "focus_history": focus_history })
+        }
+        ControlCommand::Get(Query::Groups) => {
+            json!({ "groups": daemon.groups.lock().unwrap().groups() })
+        }
+        ControlCommand::Get(Query::Fullscreen) => {
+            json!({ "fullscreen": daemon.fullscreen.lock().unwrap().windows() })
+        }
+        ControlCommand::Trigger(name) => {
+            dispatch(daemon, HyprlandEvent::Trigger { name: name.clone() });
+            json!({ "triggered": name })
+        }
+        ControlCommand::Reload => {
+            match try_load_config(&daemon.config_path, daemon.config_profile.as_deref()) {
+                Ok(config) => {
+                    // The client socket stays bound to the path it was started with.
+                    *daemon.hooks.lock().unwrap() = Hooks::new(config.hooks, config.sound_player);
+                    daemon
+                        .window_rules
+                        .lock()
+                        .unwrap()
+                        .set_rules(config.window_rules);
+                    daemon
+                        .focus_restore
+                        .lock()
+                        .unwrap()
+                        .set_enabled(config.restore_focus);
+                    daemon
+                        .overlays
+                        .lock()
+                        .unwrap()
+                        .set_namespaces(config.overlay_namespaces);
+                    daemon
+                        .submap_timeouts
+                        .lock()
+                        .unwrap()
+                        .set_timeouts(config.submap_timeouts);
+                    daemon
+                        .suppress_legacy_events
+                        .store(config.suppress_legacy_events, Ordering::SeqCst);
+                    *daemon.control_permissions.lock().unwrap() = config.control_permissions;
+                    logging::set_level(
+                        config.log_level.as_deref().unwrap_or(logging::DAEMON_LEVEL),
+                    );
+                    let mut registered = daemon.binds.lock().unwrap();
+                    binds::unregister(&registered);
+                    binds::register(&config.binds);
+                    *registered = config.binds;
+                    info!("Reloaded config from {}", daemon.config_path);
+                    dispatch(
+                        daemon,
+                        HyprlandEvent::HyprmanReloaded {
+                            config_path: daemon.config_path.clone(),
+                        },
+                    );
+                    json!({ "reloaded": daemon.config_path })
+                }
+                Err(e) => {
+                    error!("Failed to reload config {}: {}", daemon.config_path, e);
+                    json!({ "error": format!("failed to reload {}: {}", daemon.config_path, e) })
+                }
+            }
+        }
+    }
+}
+
+fn status(daemon: &Daemon) -> Value {
+    let clients: Vec<Value> = daemon
+        .subscriptions
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|client| {
+            json!({
+                "subscription": client.subscription.describe(),
+                "queued": client.queue.depth.load(Ordering::SeqCst),
+            })
+        })
+        .collect();
+    let durable: serde_json::Map<String, Value> = daemon
+        .durable
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(name, entry)| {
+            let state = json!({
+                "connected": entry.connected,
+                "buffered": entry.events.len(),
+                "dropped": entry.dropped,
+            });
+            (name.clone(), state)
+        })
+        .collect();
+    let (rss_bytes, threads) = process_stats();
+    let rates = daemon.rates.lock().unwrap();
+    json!({
+        "pid": std::process::id(),
+        "uptime_secs": daemon.started.elapsed().as_secs(),
+        "hyprland_connected": daemon.hyprland_connected.load(Ordering::SeqCst),
+        "rss_bytes": rss_bytes,
+        "threads": threads,
+        "seq": daemon.seq.load(Ordering::SeqCst),
+        "dnd": daemon.dnd.load(Ordering::SeqCst),
+        "profile": *daemon.profile.lock().unwrap(),
+        "client_count": clients.len(),
+        "clients": clients,
+        "durable": durable,
+        "history": daemon.history.lock().unwrap().len(),
+        "events": rates.totals(),
+        "rates": rates.report(),
+    })
+}
+
+/// Resident memory in bytes and thread count from /proc/self/status.
+fn process_stats() -> (Option<u64>, Option<u64>) {
+    let Ok(status) = fs::read_to_string("/proc/self/status") else {
+        return (None, None);
+    };
+    let field = |name: &str| {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .and_then(|value| value.split_whitespace().next())
+            .and_then(|value| value.parse::<u64>().ok())
+    };
+    (field("VmRSS:").map(|kb| kb * 1024), field("Threads:"))
+}
+
+/// Sends a command to the running daemon, prints its reply and fails if
+/// the daemon rejected it.
+pub fn run_control_command(config: &Config, line: &str) -> error::Result<()> {
+    let reply = send_command(&config.control_socket_path, line)?;
+    println!("{}", reply);
+    match serde_json::from_str::<Value>(&reply) {
+        Ok(reply) if reply.get("error").is_none() => Ok(()),
+        Ok(reply) => Err(HyprmanError::Daemon(format!(
+            "the daemon rejected '{}': {}",
+            line,
+            reply["error"].as_str().unwrap_or("no reason given")
+        ))),
+        Err(source) => Err(HyprmanError::Parse {
+            context: format!("the reply to '{}'", line),
+            source,
+        }),
+    }
+}
+
+fn send_command(socket_path: &str, line: &str) -> error::Result<String> {
+    let mut stream = UnixStream::connect(socket_path).map_err(|source| HyprmanError::Connect {
+        path: socket_path.to_string(),
+        source,
+    })?;
+    writeln!(stream, "{}", line)?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    Ok(reply.trim_end().to_string())
+}
@@ -0,0 +1,62 @@
+use std::io;
+use thiserror::Error;
+
+// === Errors ===
+
+/// Everything that can stop hyprman, grouped by what the user has to fix.
+#[derive(Debug, Error)]
+pub enum HyprmanError {
+    /// The command line does not parse.
+    #[error("{0}")]
+    Usage(String),
+    #[error("config file {path}: {reason}")]
+    Config { path: String, reason: String },
+    #[error("environment variable {0} is not set")]
+    Environment(&'static str),
+    /// Hyprland or the daemon is not listening on the socket.
+    #[error("could not connect to {path}: {source}")]
+    Connect {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+    /// Hyprland or the daemon sent something unexpected.
+    #[error("could not parse {context}: {source}")]
+    Parse {
+        context: String,
+        #[source]
+        source: serde_json::Error,
+    },
+    /// The daemon is already running, not running when it has to be, or
+    /// rejected a control command.
+    #[error("{0}")]
+    Daemon(String),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, HyprmanError>;
+
+impl HyprmanError {
+    /// The status the process exits with on this error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            HyprmanError::Io(_) => 1,
+            HyprmanError::Usage(_) => 2,
+            HyprmanError::Config { .. } => 3,
+            HyprmanError::Environment(_) => 4,
+            HyprmanError::Connect { .. } => 5,
+            HyprmanError::Parse { .. } => 6,
+            HyprmanError::Daemon(_) => 7,
+        }
+    }
+}
+
+impl From<HyprmanError> for io::Error {
+    fn from(e: HyprmanError) -> io::Error {
+        match e {
+            HyprmanError::Io(e) => e,
+            e => io::Error::other(e),
+        }
+    }
+}
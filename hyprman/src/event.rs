@@ -0,0 +1,838 @@
+use crate::hypr::{Client, State, Workspace};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{
+    error::Error,
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Version of the line protocol spoken on the client socket, raised
+/// whenever clients written for the previous one would misread it. Version
+/// 2 wraps events in envelopes carrying `seq` and `timestamp`, and gives
+/// socket2 fields their types: flags as booleans, states as enums.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Newest Hyprland release whose added socket2 events are all parsed.
+/// Raise it along with the events added to `define_events!` below.
+pub const HYPRLAND_EVENT_SET: &str = "0.50";
+
+// === Hyprland Event Types ===
+
+/// Declares `HyprlandEvent` from one table, along with `event_type`,
+/// `EVENT_TYPES`, the parsing of socket2 lines and the JSON Schema. Each variant names the
+/// event it is subscribed to by; `socket2` marks the ones Hyprland sends,
+/// whose comma-separated fields are parsed in declaration order, the last
+/// one taking the rest of the line. Aliases follow the name after `|`.
+macro_rules! define_events {
+    (
+        $(#[$enum_attr:meta])*
+        pub enum $enum:ident {
+            $(
+                $(#[$attr:meta])*
+                $variant:ident($name:literal $(| $alias:literal)* $(, $socket2:ident)?)
+                $({
+                    $($(#[$field_attr:meta])* $field:ident: $ty:ty),* $(,)?
+                })?
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$enum_attr])*
+        pub enum $enum {
+            $(
+                $(#[$attr])*
+                $variant $({ $($(#[$field_attr])* $field: $ty),* })?,
+            )*
+        }
+
+        pub fn event_type(event: &$enum) -> &'static str {
+            match event {
+                $($enum::$variant { .. } => $name,)*
+            }
+        }
+
+        /// Every name `event_type` returns, e.g. for completing subscription filters.
+        pub const EVENT_TYPES: &[&str] = &[$($name),*];
+
+        /// The event a socket2 line names, or `None` if it is not one of them.
+        fn parse_known_event(
+            name: &str,
+            data: &str,
+            mode: ParseMode,
+        ) -> Result<Option<$enum>, Box<dyn Error>> {
+            Ok(match name {
+                $(
+                    $name $(| $alias)* => parse_fields!(
+                        data, mode, [$($socket2)?] $enum::$variant $({ $($field: $ty),* })?
+                    ),
+                )*
+                _ => None,
+            })
+        }
+
+        /// The schema of every variant, for `schema::envelope_schema`.
+        pub fn variant_schemas() -> Vec<serde_json::Value> {
+            vec![$(
+                crate::schema::variant(
+                    stringify!($variant),
+                    data_schema!($({ $($field: $ty),* })?),
+                ),
+            )*]
+        }
+    };
+}
+
+/// The fields of a variant's `data` with their schemas; `None` for unit
+/// variants, which have no `data`.
+macro_rules! data_schema {
+    () => {
+        None
+    };
+    ({ $($field:ident: $ty:ty),* }) => {
+        Some(vec![$((stringify!($field), <$ty as crate::schema::Schema>::schema())),*])
+    };
+}
+
+/// Builds a variant from the fields in `data`; `None` for variants that do
+/// not come from socket2.
+macro_rules! parse_fields {
+    ($data:ident, $mode:ident, [] $($rest:tt)*) => {
+        None
+    };
+    ($data:ident, $mode:ident, [socket2] $enum:ident::$variant:ident) => {
+        Some($enum::$variant)
+    };
+    (
+        $data:ident,
+        $mode:ident,
+        [socket2] $enum:ident::$variant:ident { $($field:ident: $ty:ty),* }
+    ) => {{
+        let mut fields = $data.splitn([$(stringify!($field)),*].len(), ',');
+        Some($enum::$variant {
+            $(
+                $field: match fields.next() {
+                    Some(field) => match <$ty as Field>::parse(field) {
+                        Ok(value) => value,
+                        // An empty field is as good as a missing one.
+                        Err(_) if field.trim().is_empty() => {
+                            missing_field($mode, stringify!($variant), stringify!($field))?
+                        }
+                        Err(e) => return Err(e),
+                    },
+                    None => missing_field($mode, stringify!($variant), stringify!($field))?,
+                },
+            )*
+        })
+    }};
+}
+
+define_events! {
+    #[derive(Debug, Serialize, Deserialize, Clone)]
+    #[serde(tag = "event", content = "data")]
+    pub enum HyprlandEvent {
+        Workspace("workspace", socket2) {
+            workspace_name: String,
+        },
+        WorkspaceV2("workspacev2", socket2) {
+            workspace_id: i64,
+            workspace_name: String,
+        },
+        FocusedMon("focusedmon", socket2) {
+            monitor_name: String,
+            workspace_name: String,
+        },
+        FocusedMonV2("focusedmonv2", socket2) {
+            monitor_name: String,
+            workspace_id: i64,
+        },
+        ActiveWindow("activewindow", socket2) {
+            window_class: String,
+            window_title: String,
+        },
+        ActiveWindowV2("activewindowv2", socket2) {
+            window_address: Address,
+        },
+        Fullscreen("fullscreen", socket2) {
+            status: FullscreenState,
+        },
+        MonitorRemoved("monitorremoved", socket2) {
+            monitor_name: String,
+        },
+        MonitorAdded("monitoradded", socket2) {
+            monitor_name: String,
+        },
+        MonitorAddedV2("monitoraddedv2", socket2) {
+            monitor_id: i64,
+            monitor_name: String,
+            monitor_description: String,
+        },
+        CreateWorkspace("createworkspace", socket2) {
+            workspace_name: String,
+        },
+        CreateWorkspaceV2("createworkspacev2", socket2) {
+            workspace_id: i64,
+            workspace_name: String,
+        },
+        DestroyWorkspace("destroyworkspace", socket2) {
+            workspace_name: String,
+        },
+        DestroyWorkspaceV2("destroyworkspacev2", socket2) {
+            workspace_id: i64,
+            workspace_name: String,
+        },
+        MoveWorkspace("moveworkspace", socket2) {
+            workspace_name: String,
+            monitor_name: String,
+        },
+        MoveWorkspaceV2("moveworkspacev2", socket2) {
+            workspace_id: i64,
+            workspace_name: String,
+            monitor_name: String,
+        },
+        RenameWorkspace("renameworkspace", socket2) {
+            workspace_id: i64,
+            new_name: String,
+        },
+        ActiveSpecial("activespecial", socket2) {
+            workspace_name: String,
+            monitor_name: String,
+        },
+        ActiveLayout("activelayout", socket2) {
+            keyboard_name: String,
+            layout_name: String,
+        },
+        OpenWindow("openwindow", socket2) {
+            window_address: Address,
+            workspace_name: String,
+            window_class: String,
+            window_title: String,
+        },
+        CloseWindow("closewindow", socket2) {
+            window_address: Address,
+        },
+        MoveWindow("movewindow", socket2) {
+            window_address: Address,
+            workspace_name: String,
+        },
+        MoveWindowV2("movewindowv2", socket2) {
+            window_address: Address,
+            workspace_id: i64,
+            workspace_name: String,
+        },
+        OpenLayer("openlayer", socket2) {
+            namespace: String,
+        },
+        CloseLayer("closelayer", socket2) {
+            namespace: String,
+        },
+        Submap("submap", socket2) {
+            submap_name: String,
+        },
+        ChangeFloatingMode("changefloatingmode", socket2) {
+            window_address: Address,
+            floating: FloatingState,
+        },
+        Urgent("urgent", socket2) {
+            window_address: Address,
+        },
+        Screencast("screencast", socket2) {
+            state: bool,
+            owner: ScreencastOwner,
+        },
+        WindowTitle("windowtitle", socket2) {
+            window_address: Address,
+        },
+        WindowTitleV2("windowtitlev2", socket2) {
+            window_address: Address,
+            window_title: String,
+        },
+        ToggleGroup("togglegroup", socket2) {
+            toggle_status: bool,
+            window_addresses: Vec<Address>,
+        },
+        MoveIntoGroup("moveintogroup", socket2) {
+            window_address: Address,
+        },
+        MoveOutOfGroup("moveoutofgroup", socket2) {
+            window_address: Address,
+        },
+        IgnoreGroupLock("ignoregrouplock", socket2) {
+            value: bool,
+        },
+        LockGroups("lockgroups", socket2) {
+            value: bool,
+        },
+        ConfigReloaded("configreloaded", socket2),
+        Pin("pin", socket2) {
+            window_address: Address,
+            pin_state: bool,
+        },
+        // Messages generated by hyprman itself.
+        /// Sent once right after the subscription handshake.
+        Metadata("metadata") {
+            hyprland_version: Option<String>,
+            instance_signature: String,
+            hyprman_version: String,
+            protocol_version: u32,
+            seq: u64,
+            /// Optional features of the daemon, named after their handshake
+            /// options; missing when talking to older daemons.
+            #[serde(default)]
+            capabilities: Vec<String>,
+        },
+        /// A socket2 line that could not be parsed. Opt-in via the `parseerror` filter.
+        ParseError("parseerror") {
+            line: String,
+            error: String,
+        },
+        /// Do-not-disturb was switched on or off; hooks are silenced while enabled.
+        Dnd("dnd") {
+            enabled: bool,
+        },
+        /// The active hook profile changed; `None` means only unassigned hooks run.
+        ProfileChanged("profilechanged") {
+            profile: Option<String>,
+        },
+        /// hyprman re-read its config file; clients should reload their settings.
+        /// Not to be confused with Hyprland's own `configreloaded`.
+        HyprmanReloaded("hyprmanreloaded") {
+            config_path: String,
+        },
+        /// Sent on `resume` instead of the buffered events when more than the
+        /// buffer limit arrived while the client was paused.
+        Snapshot("snapshot") {
+            missed: u64,
+            state: State,
+        },
+        /// Answer to `resume_from=<seq>` when the missed events are no longer
+        /// buffered; the client has to rebuild its state from socket1.
+        ResyncRequired("resyncrequired") {
+            resume_from: u64,
+        },
+        /// `hyprman trigger <name>` was run, usually from a bind hyprman
+        /// registered in Hyprland.
+        Trigger("trigger") {
+            name: String,
+        },
+        /// A workspace got a different fullscreen window, or no longer has one.
+        FullscreenChanged("fullscreenchanged") {
+            workspace_id: i64,
+            workspace_name: String,
+            window_address: Option<Address>,
+        },
+        /// The first layer surface of a namespace listed in `overlay_namespaces`
+        /// opened, e.g. a launcher.
+        OverlayShown("overlayshown") {
+            namespace: String,
+        },
+        /// The last layer surface of such a namespace closed.
+        OverlayHidden("overlayhidden") {
+            namespace: String,
+        },
+        /// Answer to a `dispatch <args>` line, sent only to the client that
+        /// wrote it: Hyprland's reply, or why it was not forwarded.
+        DispatchReply("dispatchreply") {
+            command: String,
+            reply: Option<String>,
+            error: Option<String>,
+        },
+        /// The daemon is shutting down and closes the connection after this;
+        /// without it the client only sees EOF, as after a crash.
+        DaemonShutdown("daemonshutdown"),
+        /// Hyprland exited and the daemon reconnected to the instance that
+        /// replaced it; state queried from the old one is stale.
+        HyprlandRestarted("hyprlandrestarted") {
+            instance_signature: String,
+        },
+        /// A socket2 event this version does not know, e.g. one added by a newer
+        /// Hyprland, with its data unparsed. Opt-in via the `unknown` filter.
+        Unknown("unknown") {
+            name: String,
+            data: String,
+        },
+        /// A window asked to be minimized or restored; Hyprland itself does not
+        /// hide it, that is left to taskbars.
+        Minimized("minimized" | "minimize", socket2) {
+            window_address: Address,
+            minimized: bool,
+        },
+        /// A terminal rang its bell, e.g. an XTerm-style `\a`.
+        Bell("bell", socket2) {
+            window_address: Address,
+        },
+        MonitorRemovedV2("monitorremovedv2", socket2) {
+            monitor_id: i64,
+            monitor_name: String,
+            monitor_description: String,
+        },
+        /// `activewindow` and `activewindowv2` in one, sent in their place to
+        /// `canonical` subscribers.
+        ActiveWindowChanged("activewindowchanged") {
+            window_address: Address,
+            window_class: String,
+            window_title: String,
+        },
+        /// `focusedmon` and `focusedmonv2` in one, sent in their place to
+        /// `canonical` subscribers.
+        FocusedMonChanged("focusedmonchanged") {
+            monitor_name: String,
+            workspace_id: i64,
+            workspace_name: String,
+        },
+        /// The focused window, monitor or workspace changed, with what had the
+        /// focus before. The workspace is the special workspace open on the
+        /// focused monitor, if any; an empty address means no window.
+        FocusChanged("focuschanged") {
+            window_address: Address,
+            monitor_name: String,
+            workspace_name: String,
+            previous_window_address: Address,
+            previous_monitor_name: String,
+            previous_workspace_name: String,
+        },
+        /// The last window left a workspace, by closing or moving away.
+        WorkspaceEmpty("workspaceempty") {
+            workspace_id: i64,
+            workspace_name: String,
+        },
+        /// A workspace without windows got one.
+        WorkspaceOccupied("workspaceoccupied") {
+            workspace_id: i64,
+            workspace_name: String,
+        },
+    }
+}
+
+/// An event as written to clients, tagged with its sequence number so a
+/// reconnecting client can ask for what it missed with `resume_from=<seq>`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Envelope {
+    pub seq: u64,
+    /// Milliseconds since the Unix epoch when the daemon dispatched the
+    /// event, for ordering events merged from several daemons.
+    #[serde(default)]
+    pub timestamp: u64,
+    /// The window a window event is about, as `j/clients` reports it. Only
+    /// written to clients that subscribed with `enrich`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window: Option<Box<Client>>,
+    /// The workspace a workspace event is about, as `j/workspaces` reports
+    /// it with its monitor and window count. Only written to clients that
+    /// subscribed with `enrich`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<Box<Workspace>>,
+    #[serde(flatten)]
+    pub event: HyprlandEvent,
+}
+
+impl Envelope {
+    /// Wraps an event dispatched now.
+    pub fn new(seq: u64, event: HyprlandEvent) -> Envelope {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64);
+        Envelope {
+            seq,
+            timestamp,
+            window: None,
+            workspace: None,
+            event,
+        }
+    }
+}
+
+// === Event Field Types ===
+
+/// A window address. Accepted with or without the `0x` prefix socket1 uses
+/// and in either case; written as lowercase hex without the prefix, as in
+/// socket2 events.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub struct Address(String);
+
+impl Address {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The address as socket1 and dispatchers expect it, e.g. `0x5f3a`.
+    pub fn prefixed(&self) -> String {
+        format!("0x{}", self.0)
+    }
+}
+
+impl From<&str> for Address {
+    fn from(address: &str) -> Address {
+        let address = address.trim();
+        let hex = address
+            .strip_prefix("0x")
+            .or_else(|| address.strip_prefix("0X"))
+            .unwrap_or(address);
+        Address(hex.to_ascii_lowercase())
+    }
+}
+
+impl From<String> for Address {
+    fn from(address: String) -> Address {
+        Address::from(address.as_str())
+    }
+}
+
+impl From<Address> for String {
+    fn from(address: Address) -> String {
+        address.0
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FullscreenState {
+    #[default]
+    Off,
+    On,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FloatingState {
+    #[default]
+    Tiled,
+    Floating,
+}
+
+/// What a screencast captures.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScreencastOwner {
+    #[default]
+    Monitor,
+    Window,
+}
+
+// === Hyprland Events parsing ===
+
+/// Parses an id, tolerating surrounding whitespace and `0x` hex.
+fn parse_int(field: &str) -> Result<i64, Box<dyn Error>> {
+    let field = field.trim();
+    let (negative, digits) = match field.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, field),
+    };
+    let value = match digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        Some(hex) => i64::from_str_radix(hex, 16)?,
+        None => digits.parse::<i64>()?,
+    };
+    Ok(if negative { -value } else { value })
+}
+
+/// Parses a flag Hyprland writes as `0`/`1`; any other number counts as
+/// set, and `true`/`false` are accepted as well.
+fn parse_flag(field: &str) -> Result<bool, Box<dyn Error>> {
+    match field.trim() {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        other => Ok(parse_int(other)? != 0),
+    }
+}
+
+/// A field of a socket2 event; lenient parsing fills in the default of
+/// missing ones.
+trait Field: Sized + Default {
+    fn parse(field: &str) -> Result<Self, Box<dyn Error>>;
+
+    /// The value of a field the line leaves out, if that is not an error.
+    fn missing() -> Option<Self> {
+        None
+    }
+}
+
+impl Field for String {
+    fn parse(field: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(field.to_string())
+    }
+}
+
+impl Field for i64 {
+    fn parse(field: &str) -> Result<Self, Box<dyn Error>> {
+        parse_int(field)
+    }
+}
+
+impl Field for bool {
+    fn parse(field: &str) -> Result<Self, Box<dyn Error>> {
+        parse_flag(field)
+    }
+}
+
+impl Field for Address {
+    fn parse(field: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Address::from(field))
+    }
+}
+
+/// The rest of the line as a list, e.g. the members of a group.
+impl Field for Vec<Address> {
+    fn parse(field: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(field
+            .split(',')
+            .filter(|address| !address.trim().is_empty())
+            .map(Address::from)
+            .collect())
+    }
+
+    fn missing() -> Option<Self> {
+        Some(Vec::new())
+    }
+}
+
+impl Field for FullscreenState {
+    fn parse(field: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(match parse_flag(field)? {
+            true => FullscreenState::On,
+            false => FullscreenState::Off,
+        })
+    }
+}
+
+impl Field for FloatingState {
+    fn parse(field: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(match parse_flag(field)? {
+            true => FloatingState::Floating,
+            false => FloatingState::Tiled,
+        })
+    }
+}
+
+impl Field for ScreencastOwner {
+    fn parse(field: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(match parse_flag(field)? {
+            true => ScreencastOwner::Window,
+            false => ScreencastOwner::Monitor,
+        })
+    }
+}
+
+/// The value of a field a line leaves out or leaves empty: its default in
+/// lenient mode, an error in strict mode unless the field may be left out.
+fn missing_field<T: Field>(mode: ParseMode, event: &str, field: &str) -> Result<T, Box<dyn Error>> {
+    match T::missing() {
+        Some(value) => Ok(value),
+        None if mode == ParseMode::Lenient => {
+            warn!("{} event without {}, using the default", event, field);
+            Ok(T::default())
+        }
+        None => Err(format!("Missing {}", field).into()),
+    }
+}
+
+/// How to treat socket2 lines that end before all fields of their event.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParseMode {
+    /// Reject them, e.g. to diagnose what Hyprland sends.
+    Strict,
+    /// Fill in defaults and log a warning; Hyprland occasionally sends short
+    /// lines.
+    #[default]
+    Lenient,
+}
+
+/// Parses one line of socket2. Hyprland does not escape commas, so the last
+/// field of an event takes the rest of the line and keeps any it contains.
+pub fn parse_event_line(line: &str, mode: ParseMode) -> Result<HyprlandEvent, Box<dyn Error>> {
+    let line = line.trim();
+    // Only the first `>>` separates the name; titles may contain more.
+    let (event_name, data) = line.split_once(">>").unwrap_or((line, ""));
+    let data = data.trim();
+    Ok(
+        parse_known_event(event_name, data, mode)?.unwrap_or_else(|| HyprlandEvent::Unknown {
+            name: event_name.to_string(),
+            data: data.to_string(),
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ids_leniently() {
+        assert_eq!(parse_int("42").unwrap(), 42);
+        assert_eq!(parse_int(" 7 \n").unwrap(), 7);
+        assert_eq!(parse_int("-98").unwrap(), -98);
+        assert_eq!(parse_int("0x1F").unwrap(), 31);
+        assert_eq!(parse_int("4294967296").unwrap(), 1 << 32);
+        assert!(parse_int("").is_err());
+        assert!(parse_int("abc").is_err());
+    }
+
+    #[test]
+    fn parses_flags() {
+        assert!(parse_flag("1").unwrap());
+        assert!(parse_flag("2").unwrap());
+        assert!(!parse_flag(" 0").unwrap());
+        assert!(parse_flag("true").unwrap());
+        assert!(!parse_flag("false").unwrap());
+        assert!(parse_flag("yes").is_err());
+    }
+
+    #[test]
+    fn normalizes_addresses() {
+        assert_eq!(Address::from("0x55AA").to_string(), "55aa");
+        assert_eq!(Address::from("55aa"), Address::from(" 0X55aa"));
+        assert_eq!(Address::from("55aa").prefixed(), "0x55aa");
+    }
+
+    #[test]
+    fn parses_special_workspace_ids() {
+        match parse_event_line("workspacev2>>-98,special:scratch", ParseMode::Strict).unwrap() {
+            HyprlandEvent::WorkspaceV2 {
+                workspace_id,
+                workspace_name,
+            } => {
+                assert_eq!(workspace_id, -98);
+                assert_eq!(workspace_name, "special:scratch");
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_large_monitor_ids() {
+        match parse_event_line(
+            "monitoraddedv2>>4294967296,DP-3,Some Screen",
+            ParseMode::Strict,
+        )
+        .unwrap()
+        {
+            HyprlandEvent::MonitorAddedV2 { monitor_id, .. } => {
+                assert_eq!(monitor_id, 4294967296)
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_typed_states() {
+        assert!(matches!(
+            parse_event_line("fullscreen>>1", ParseMode::Strict).unwrap(),
+            HyprlandEvent::Fullscreen {
+                status: FullscreenState::On
+            }
+        ));
+        match parse_event_line("changefloatingmode>>0xABC,1", ParseMode::Strict).unwrap() {
+            HyprlandEvent::ChangeFloatingMode {
+                window_address,
+                floating,
+            } => {
+                assert_eq!(window_address, Address::from("abc"));
+                assert_eq!(floating, FloatingState::Floating);
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+        assert!(parse_event_line("pin>>abc,maybe", ParseMode::Strict).is_err());
+    }
+
+    #[test]
+    fn keeps_commas_in_titles() {
+        match parse_event_line(
+            "activewindow>>firefox,Foo, Bar — Mozilla Firefox",
+            ParseMode::Strict,
+        )
+        .unwrap()
+        {
+            HyprlandEvent::ActiveWindow {
+                window_class,
+                window_title,
+            } => {
+                assert_eq!(window_class, "firefox");
+                assert_eq!(window_title, "Foo, Bar — Mozilla Firefox");
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+        match parse_event_line("openwindow>>55aa,2,kitty,vim a,b,c", ParseMode::Strict).unwrap() {
+            HyprlandEvent::OpenWindow {
+                workspace_name,
+                window_class,
+                window_title,
+                ..
+            } => {
+                assert_eq!(workspace_name, "2");
+                assert_eq!(window_class, "kitty");
+                assert_eq!(window_title, "vim a,b,c");
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+        match parse_event_line("windowtitlev2>>55aa,a >> b, c", ParseMode::Strict).unwrap() {
+            HyprlandEvent::WindowTitleV2 { window_title, .. } => {
+                assert_eq!(window_title, "a >> b, c")
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+        match parse_event_line("renameworkspace>>3,code, notes", ParseMode::Strict).unwrap() {
+            HyprlandEvent::RenameWorkspace { new_name, .. } => assert_eq!(new_name, "code, notes"),
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[test]
+    fn fills_missing_fields_leniently() {
+        assert!(parse_event_line("openwindow>>abc,2", ParseMode::Strict).is_err());
+        match parse_event_line("openwindow>>abc,2", ParseMode::Lenient).unwrap() {
+            HyprlandEvent::OpenWindow {
+                workspace_name,
+                window_class,
+                window_title,
+                ..
+            } => {
+                assert_eq!(workspace_name, "2");
+                assert_eq!(window_class, "");
+                assert_eq!(window_title, "");
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+        assert!(parse_event_line("pin>>abc,maybe", ParseMode::Lenient).is_err());
+        assert!(parse_event_line("workspacev2>>", ParseMode::Strict).is_err());
+        match parse_event_line("workspacev2>>", ParseMode::Lenient).unwrap() {
+            HyprlandEvent::WorkspaceV2 {
+                workspace_id,
+                workspace_name,
+            } => {
+                assert_eq!(workspace_id, 0);
+                assert_eq!(workspace_name, "");
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+        match parse_event_line("togglegroup>>0,", ParseMode::Lenient).unwrap() {
+            HyprlandEvent::ToggleGroup {
+                toggle_status,
+                window_addresses,
+            } => {
+                assert!(!toggle_status);
+                assert!(window_addresses.is_empty());
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[test]
+    fn keeps_unknown_events() {
+        match parse_event_line("newthing>>1,2", ParseMode::Strict).unwrap() {
+            HyprlandEvent::Unknown { name, data } => {
+                assert_eq!(name, "newthing");
+                assert_eq!(data, "1,2");
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{client::Connection, hypr::query_socket};
+use log::error;
+use std::{
+    ffi::{CStr, CString, c_char},
+    ptr,
+};
+
+// === C Interface ===
+// Strings returned to C are allocated by Rust and must be released with
+// `hyprman_free_string`. See include/hyprman.h for the matching declarations.
+
+/// Opaque handle handed out to C callers.
+pub struct HyprmanConnection(Connection);
+
+unsafe fn str_arg<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    unsafe { CStr::from_ptr(s) }.to_str().ok()
+}
+
+fn into_c_string(s: String) -> *mut c_char {
+    CString::new(s).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// Connects to the daemon at `socket_path` with the given subscription line
+/// (e.g. `"all"` or `"activewindowv2,closewindow"`). Returns NULL on failure.
+///
+/// # Safety
+/// Both arguments must be NULL or valid NUL-terminated strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hyprman_connect(
+    socket_path: *const c_char,
+    subscription: *const c_char,
+) -> *mut HyprmanConnection {
+    let Some(socket_path) = (unsafe { str_arg(socket_path) }) else {
+        return ptr::null_mut();
+    };
+    let subscription = unsafe { str_arg(subscription) }.unwrap_or("all");
+    match Connection::connect(socket_path, subscription) {
+        Ok(connection) => Box::into_raw(Box::new(HyprmanConnection(connection))),
+        Err(e) => {
+            error!("Failed to connect to {}: {}", socket_path, e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Blocks until the next event arrives and returns it as a JSON string.
+/// Returns NULL once the daemon hangs up or on a read error.
+///
+/// # Safety
+/// `connection` must be NULL or a handle returned by `hyprman_connect` that
+/// has not been passed to `hyprman_disconnect`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hyprman_next_event(connection: *mut HyprmanConnection) -> *mut c_char {
+    let Some(connection) = (unsafe { connection.as_mut() }) else {
+        return ptr::null_mut();
+    };
+    match connection.0.next_line() {
+        Ok(Some(line)) => into_c_string(line),
+        Ok(None) => ptr::null_mut(),
+        Err(e) => {
+            error!("Failed to read event: {}", e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Sends `query` (e.g. `"j/clients"`) to Hyprland's socket1 and returns the
+/// response. Returns NULL on failure.
+///
+/// # Safety
+/// `query` must be NULL or a valid NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hyprman_query(query: *const c_char) -> *mut c_char {
+    let Some(query) = (unsafe { str_arg(query) }) else {
+        return ptr::null_mut();
+    };
+    match query_socket(query) {
+        Ok(response) => into_c_string(response),
+        Err(e) => {
+            error!("Query '{}' failed: {}", query, e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Closes a connection returned by `hyprman_connect`.
+///
+/// # Safety
+/// `connection` must be NULL or a handle returned by `hyprman_connect` that
+/// has not already been disconnected.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hyprman_disconnect(connection: *mut HyprmanConnection) {
+    if !connection.is_null() {
+        drop(unsafe { Box::from_raw(connection) });
+    }
+}
+
+/// Releases a string returned by `hyprman_next_event` or `hyprman_query`.
+///
+/// # Safety
+/// `s` must be NULL or a string returned by this library that has not already
+/// been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hyprman_free_string(s: *mut c_char) {
+    if !s.is_null() {
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
@@ -0,0 +1,318 @@
+use crate::audit::{AuditLog, AuditRecord};
+use hyprman::{
+    error::Result,
+    event::{Address, HyprlandEvent},
+    hypr::{query_clients, query_monitors},
+};
+use log::warn;
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// === Focus History ===
+
+pub fn default_focus_history_size() -> usize {
+    64
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Focus {
+    pub window_address: Address,
+    pub window_class: String,
+    pub window_title: String,
+    pub focused_at_ms: u64,
+    /// How long the window kept the focus; for the focused window, so far.
+    pub duration_ms: u64,
+    pub focused: bool,
+}
+
+/// Recently focused windows, newest first, built from `activewindowv2`
+/// with class and title taken from the `activewindow` event Hyprland sends
+/// just before it.
+pub struct FocusHistory {
+    size: usize,
+    entries: VecDeque<Focus>,
+    /// Class and title of the window about to be focused.
+    pending: Option<(String, String)>,
+}
+
+impl FocusHistory {
+    pub fn new(size: usize) -> FocusHistory {
+        FocusHistory {
+            size,
+            entries: VecDeque::new(),
+            pending: None,
+        }
+    }
+
+    pub fn record(&mut self, event: &HyprlandEvent) {
+        match event {
+            HyprlandEvent::ActiveWindow {
+                window_class,
+                window_title,
+            } => self.pending = Some((window_class.clone(), window_title.clone())),
+            HyprlandEvent::ActiveWindowV2 { window_address } => {
+                let now = now_ms();
+                if self
+                    .entries
+                    .front()
+                    .is_some_and(|last| last.focused && last.window_address == *window_address)
+                {
+                    return;
+                }
+                if let Some(last) = self.entries.front_mut().filter(|last| last.focused) {
+                    last.duration_ms = now.saturating_sub(last.focused_at_ms);
+                    last.focused = false;
+                }
+                let (window_class, window_title) = self.pending.take().unwrap_or_default();
+                // An empty address means no window has the focus.
+                if window_address.is_empty() || self.size == 0 {
+                    return;
+                }
+                if self.entries.len() >= self.size {
+                    self.entries.pop_back();
+                }
+                self.entries.push_front(Focus {
+                    window_address: window_address.clone(),
+                    window_class,
+                    window_title,
+                    focused_at_ms: now,
+                    duration_ms: 0,
+                    focused: true,
+                });
+            }
+            HyprlandEvent::WindowTitleV2 {
+                window_address,
+                window_title,
+            } => {
+                if let Some(current) = self
+                    .entries
+                    .front_mut()
+                    .filter(|f| f.focused && f.window_address == *window_address)
+                {
+                    current.window_title = window_title.clone();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The history, newest first, with the running duration of the focused
+    /// window filled in.
+    pub fn entries(&self) -> Vec<Focus> {
+        let now = now_ms();
+        self.entries
+            .iter()
+            .map(|focus| {
+                let mut focus = focus.clone();
+                if focus.focused {
+                    focus.duration_ms = now.saturating_sub(focus.focused_at_ms);
+                }
+                focus
+            })
+            .collect()
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+// === Focus Restore ===
+
+/// Remembers the window last focused on each workspace and, if enabled,
+/// focuses it again when the workspace becomes active instead of whatever
+/// Hyprland picks by stacking order.
+pub struct FocusRestore {
+    enabled: bool,
+    active_workspace: Option<i64>,
+    last_focused: HashMap<i64, Address>,
+}
+
+impl FocusRestore {
+    pub fn new(enabled: bool) -> FocusRestore {
+        FocusRestore {
+            enabled,
+            active_workspace: None,
+            last_focused: HashMap::new(),
+        }
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn apply(&mut self, event: &HyprlandEvent, dry_run: bool, audit: &AuditLog) {
+        match event {
+            HyprlandEvent::WorkspaceV2 { workspace_id, .. } => {
+                self.active_workspace = Some(*workspace_id);
+                // Hyprland focuses a window of its choice right after this
+                // event; the remembered one is looked up before that lands.
+                if let Some(address) = self.last_focused.get(workspace_id)
+                    && self.enabled
+                {
+                    AuditRecord {
+                        source: "restore_focus",
+                        name: None,
+                        trigger: Some(event),
+                        command: format!("focuswindow address:{}", address.prefixed()),
+                        result: Value::Null,
+                    }
+                    .dispatch(dry_run, audit);
+                }
+            }
+            HyprlandEvent::FocusedMonV2 { workspace_id, .. } => {
+                self.active_workspace = Some(*workspace_id);
+            }
+            HyprlandEvent::ActiveWindowV2 { window_address } if !window_address.is_empty() => {
+                if let Some(workspace_id) = self.active_workspace {
+                    self.last_focused
+                        .insert(workspace_id, window_address.clone());
+                }
+            }
+            HyprlandEvent::MoveWindowV2 {
+                window_address,
+                workspace_id,
+                ..
+            } => {
+                self.last_focused
+                    .retain(|id, address| id == workspace_id || address != window_address);
+            }
+            HyprlandEvent::CloseWindow { window_address } => {
+                self.last_focused
+                    .retain(|_, address| address != window_address);
+            }
+            HyprlandEvent::DestroyWorkspaceV2 { workspace_id, .. } => {
+                self.last_focused.remove(workspace_id);
+            }
+            _ => {}
+        }
+    }
+}
+
+// === Effective Focus ===
+
+/// What has the focus: the window, the monitor and the workspace shown on
+/// it, a special workspace taking precedence over the regular one.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Target {
+    window_address: Address,
+    monitor_name: String,
+    workspace_name: String,
+}
+
+/// The focus as `activewindowv2`, `focusedmon`, `workspacev2` and
+/// `activespecial` change it, reported as one `FocusChanged` whenever any
+/// part of it changes.
+#[derive(Debug, Default)]
+pub struct EffectiveFocus {
+    window_address: Address,
+    monitor_name: String,
+    /// Workspace shown on each monitor, keyed by monitor name.
+    workspaces: HashMap<String, String>,
+    /// Special workspace open on each monitor, keyed by monitor name.
+    specials: HashMap<String, String>,
+}
+
+impl EffectiveFocus {
+    pub fn query() -> Result<EffectiveFocus> {
+        let mut focus = EffectiveFocus::default();
+        for monitor in query_monitors()? {
+            if monitor.focused {
+                focus.monitor_name = monitor.name.clone();
+            }
+            if !monitor.special_workspace.name.is_empty() {
+                focus
+                    .specials
+                    .insert(monitor.name.clone(), monitor.special_workspace.name);
+            }
+            focus
+                .workspaces
+                .insert(monitor.name, monitor.active_workspace.name);
+        }
+        if let Some(client) = query_clients()?
+            .into_values()
+            .find(|c| c.mapped && c.focus_history_id == 0)
+        {
+            focus.window_address = client.address;
+        }
+        Ok(focus)
+    }
+
+    fn target(&self) -> Target {
+        let workspace_name = self
+            .specials
+            .get(&self.monitor_name)
+            .or_else(|| self.workspaces.get(&self.monitor_name))
+            .cloned()
+            .unwrap_or_default();
+        Target {
+            window_address: self.window_address.clone(),
+            monitor_name: self.monitor_name.clone(),
+            workspace_name,
+        }
+    }
+
+    /// Updates the focus and returns a `FocusChanged` event if it changed.
+    pub fn handle(&mut self, event: &HyprlandEvent) -> Option<HyprlandEvent> {
+        let previous = self.target();
+        match event {
+            HyprlandEvent::ActiveWindowV2 { window_address } => {
+                self.window_address = window_address.clone();
+            }
+            HyprlandEvent::FocusedMon {
+                monitor_name,
+                workspace_name,
+            } => {
+                self.monitor_name = monitor_name.clone();
+                self.workspaces
+                    .insert(monitor_name.clone(), workspace_name.clone());
+            }
+            HyprlandEvent::FocusedMonV2 { monitor_name, .. } => {
+                self.monitor_name = monitor_name.clone();
+            }
+            // Workspaces are switched on the focused monitor.
+            HyprlandEvent::WorkspaceV2 { workspace_name, .. } => {
+                self.workspaces
+                    .insert(self.monitor_name.clone(), workspace_name.clone());
+            }
+            HyprlandEvent::ActiveSpecial {
+                workspace_name,
+                monitor_name,
+            } => {
+                if workspace_name.is_empty() {
+                    self.specials.remove(monitor_name);
+                } else {
+                    self.specials
+                        .insert(monitor_name.clone(), workspace_name.clone());
+                }
+            }
+            HyprlandEvent::MonitorRemovedV2 { monitor_name, .. } => {
+                self.workspaces.remove(monitor_name);
+                self.specials.remove(monitor_name);
+            }
+            HyprlandEvent::HyprlandRestarted { .. } => match EffectiveFocus::query() {
+                Ok(focus) => *self = focus,
+                Err(e) => warn!("Could not query the focus: {}", e),
+            },
+            _ => return None,
+        }
+        let current = self.target();
+        if current == previous {
+            return None;
+        }
+        Some(HyprlandEvent::FocusChanged {
+            window_address: current.window_address,
+            monitor_name: current.monitor_name,
+            workspace_name: current.workspace_name,
+            previous_window_address: previous.window_address,
+            previous_monitor_name: previous.monitor_name,
+            previous_workspace_name: previous.workspace_name,
+        })
+    }
+}
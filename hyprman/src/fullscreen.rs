@@ -0,0 +1,106 @@
+use hyprman::{
+    error::Result,
+    event::{Address, HyprlandEvent},
+    hypr::{Client, query_json},
+};
+use log::warn;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+// === Fullscreen Windows per Workspace ===
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FullscreenWindow {
+    pub workspace_id: i64,
+    pub workspace_name: String,
+    pub window_address: Address,
+    /// Hyprland's fullscreen mode: 1 maximized, 2 fullscreen.
+    pub mode: i32,
+}
+
+/// The fullscreen window of each workspace, re-read from socket1 whenever
+/// a window may have entered or left fullscreen.
+#[derive(Debug, Default)]
+pub struct Fullscreen {
+    workspaces: BTreeMap<i64, FullscreenWindow>,
+}
+
+impl Fullscreen {
+    pub fn query() -> Result<Fullscreen> {
+        Ok(Fullscreen {
+            workspaces: query_fullscreen()?,
+        })
+    }
+
+    pub fn windows(&self) -> Vec<&FullscreenWindow> {
+        self.workspaces.values().collect()
+    }
+
+    /// Updates the state and returns a `FullscreenChanged` event for every
+    /// workspace whose fullscreen window changed.
+    pub fn handle(&mut self, event: &HyprlandEvent) -> Vec<HyprlandEvent> {
+        if !matches!(
+            event,
+            HyprlandEvent::Fullscreen { .. }
+                | HyprlandEvent::CloseWindow { .. }
+                | HyprlandEvent::MoveWindowV2 { .. }
+        ) {
+            return Vec::new();
+        }
+        // Closing or moving other windows changes nothing.
+        if let HyprlandEvent::CloseWindow { window_address }
+        | HyprlandEvent::MoveWindowV2 { window_address, .. } = event
+            && !self
+                .workspaces
+                .values()
+                .any(|w| w.window_address == *window_address)
+        {
+            return Vec::new();
+        }
+        let current = match query_fullscreen() {
+            Ok(current) => current,
+            Err(e) => {
+                warn!("Could not query clients for fullscreen state: {}", e);
+                return Vec::new();
+            }
+        };
+        let previous = std::mem::replace(&mut self.workspaces, current);
+        let mut changes = Vec::new();
+        for (id, window) in &previous {
+            if !self.workspaces.contains_key(id) {
+                changes.push(HyprlandEvent::FullscreenChanged {
+                    workspace_id: *id,
+                    workspace_name: window.workspace_name.clone(),
+                    window_address: None,
+                });
+            }
+        }
+        for (id, window) in &self.workspaces {
+            if previous.get(id).map(|w| &w.window_address) != Some(&window.window_address) {
+                changes.push(HyprlandEvent::FullscreenChanged {
+                    workspace_id: *id,
+                    workspace_name: window.workspace_name.clone(),
+                    window_address: Some(window.window_address.clone()),
+                });
+            }
+        }
+        changes
+    }
+}
+
+fn query_fullscreen() -> Result<BTreeMap<i64, FullscreenWindow>> {
+    let clients: Vec<Client> = query_json("j/clients")?;
+    Ok(clients
+        .into_iter()
+        .filter(|c| c.mapped && c.fullscreen != 0)
+        .map(|c| {
+            let window = FullscreenWindow {
+                workspace_id: c.workspace.id,
+                workspace_name: c.workspace.name,
+                window_address: c.address,
+                mode: c.fullscreen,
+            };
+            (window.workspace_id, window)
+        })
+        .collect())
+}
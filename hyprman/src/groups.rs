@@ -0,0 +1,186 @@
+use crate::{Config, connect_unix_socket, output::Output, parse_event, try_load_config};
+use hyprman::{
+    error::Result,
+    event::{Address, HyprlandEvent},
+    hypr::{Client, query_json, set_instance_signature},
+};
+use log::{error, info, warn};
+use serde::Serialize;
+
+// === Window Groups ===
+
+/// A window in a group, in tab order.
+#[derive(Debug, Clone, Serialize)]
+pub struct Tab {
+    pub window_address: Address,
+    pub window_class: String,
+    pub window_title: String,
+    /// The tab shown by the group.
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Group {
+    pub tabs: Vec<Tab>,
+}
+
+/// Members and active tab of every window group. Membership comes from the
+/// `grouped` lists Hyprland reports per client, re-read whenever a group
+/// changes; switching tabs and title changes are followed from events.
+#[derive(Debug, Default)]
+pub struct Groups {
+    groups: Vec<Group>,
+}
+
+impl Groups {
+    pub fn query() -> Result<Groups> {
+        let mut groups = Groups::default();
+        groups.refresh()?;
+        Ok(groups)
+    }
+
+    pub fn groups(&self) -> &[Group] {
+        &self.groups
+    }
+
+    fn refresh(&mut self) -> Result<()> {
+        let clients: Vec<Client> = query_json("j/clients")?;
+        let client = |address: &Address| clients.iter().find(|c| c.address == *address);
+        let mut groups: Vec<Group> = Vec::new();
+        for grouped in clients.iter().map(|c| &c.grouped).filter(|g| !g.is_empty()) {
+            let members = grouped.clone();
+            if groups
+                .iter()
+                .any(|g| g.tabs.iter().any(|t| t.window_address == members[0]))
+            {
+                continue;
+            }
+            let tabs = members
+                .into_iter()
+                .map(|window_address| {
+                    let member = client(&window_address);
+                    Tab {
+                        window_class: member.map(|c| c.class.clone()).unwrap_or_default(),
+                        window_title: member.map(|c| c.title.clone()).unwrap_or_default(),
+                        // Tabs in the background are hidden.
+                        active: member.is_some_and(|c| !c.hidden),
+                        window_address,
+                    }
+                })
+                .collect();
+            groups.push(Group { tabs });
+        }
+        self.groups = groups;
+        Ok(())
+    }
+
+    fn tab_mut(&mut self, address: &Address) -> Option<&mut Tab> {
+        self.groups
+            .iter_mut()
+            .flat_map(|g| g.tabs.iter_mut())
+            .find(|t| t.window_address == *address)
+    }
+
+    /// Updates the groups and returns whether anything changed.
+    pub fn handle(&mut self, event: &HyprlandEvent) -> bool {
+        match event {
+            HyprlandEvent::ToggleGroup { .. }
+            | HyprlandEvent::MoveIntoGroup { .. }
+            | HyprlandEvent::MoveOutOfGroup { .. }
+            | HyprlandEvent::OpenWindow { .. }
+            | HyprlandEvent::CloseWindow { .. } => {
+                // New windows join a focused group; closed ones leave theirs.
+                if let HyprlandEvent::OpenWindow { .. } | HyprlandEvent::CloseWindow { .. } = event
+                    && self.groups.is_empty()
+                {
+                    return false;
+                }
+                match self.refresh() {
+                    Ok(()) => true,
+                    Err(e) => {
+                        warn!("Could not query clients for window groups: {}", e);
+                        false
+                    }
+                }
+            }
+            HyprlandEvent::ActiveWindowV2 { window_address } => {
+                let Some(group) = self
+                    .groups
+                    .iter_mut()
+                    .find(|g| g.tabs.iter().any(|t| t.window_address == *window_address))
+                else {
+                    return false;
+                };
+                let mut changed = false;
+                for tab in group.tabs.iter_mut() {
+                    let active = tab.window_address == *window_address;
+                    changed |= tab.active != active;
+                    tab.active = active;
+                }
+                changed
+            }
+            HyprlandEvent::WindowTitleV2 {
+                window_address,
+                window_title,
+            } => match self.tab_mut(window_address) {
+                Some(tab) if tab.window_title != *window_title => {
+                    tab.window_title = window_title.clone();
+                    true
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    fn print(&self, config: &Config, output: &mut Output) {
+        let groups: Vec<Group> = self
+            .groups
+            .iter()
+            .map(|group| Group {
+                tabs: group
+                    .tabs
+                    .iter()
+                    .map(|tab| Tab {
+                        window_title: config.escape.apply(&tab.window_title),
+                        window_class: config.escape.apply(&tab.window_class),
+                        ..tab.clone()
+                    })
+                    .collect(),
+            })
+            .collect();
+        output.write(&config.key_case.to_json(&groups));
+    }
+}
+
+/// Prints the window groups with their tabs in order whenever a group
+/// changes, for bars rendering tab strips.
+pub fn run_groups_client(mut config: Config, mut output: Output) -> Result<()> {
+    let subscription_line = String::from(
+        "togglegroup,moveintogroup,moveoutofgroup,openwindow,closewindow,activewindowv2,windowtitlev2,hyprmanreloaded,hyprlandrestarted\n",
+    );
+    info!("Using subscription line: {}", subscription_line);
+    let mut connection = connect_unix_socket(&config, subscription_line)?;
+    let mut groups = Groups::query()?;
+    groups.print(&config, &mut output);
+    while let Some(event_line) = connection.next_line()? {
+        let event = parse_event(&event_line)?;
+        if let HyprlandEvent::HyprmanReloaded { config_path } = event {
+            match try_load_config(&config_path, config.config_profile.as_deref()) {
+                Ok(reloaded) => {
+                    config.escape = reloaded.escape;
+                    config.key_case = reloaded.key_case;
+                }
+                Err(e) => error!("Failed to reload config: {}", e),
+            }
+            groups.print(&config, &mut output);
+        } else if let HyprlandEvent::HyprlandRestarted { instance_signature } = event {
+            set_instance_signature(instance_signature);
+            groups = Groups::query()?;
+            groups.print(&config, &mut output);
+        } else if groups.handle(&event) {
+            groups.print(&config, &mut output);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,748 @@
+use crate::{
+    audit::{AuditLog, AuditRecord},
+    check::Findings,
+};
+use hyprman::{
+    event::{HyprlandEvent, event_type},
+    hypr::{Monitor, instance_signature, query_json, query_monitors, query_socket},
+};
+use log::{error, info, warn};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::{
+    collections::HashMap,
+    path::Path,
+    process::{Child, Command},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+// === Event Hooks ===
+
+/// A reaction to events configured as `[[hooks]]` in the config file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Hook {
+    /// Identifies the hook in the audit log.
+    #[serde(default)]
+    name: Option<String>,
+    /// Event names as used in subscription filters, e.g. `urgent`.
+    events: Vec<String>,
+    /// Profiles the hook belongs to; hooks without any are always active.
+    #[serde(default)]
+    profiles: Vec<String>,
+    /// Only log what the hook would run, for trying it out on live events.
+    #[serde(default)]
+    dry_run: bool,
+    #[serde(flatten)]
+    conditions: Conditions,
+    /// Milliseconds after running during which the hook does not run again.
+    #[serde(default)]
+    cooldown: Option<u64>,
+    /// Milliseconds without further matching events to wait before running,
+    /// so a burst of events runs the hook once.
+    #[serde(default)]
+    debounce: Option<u64>,
+    /// Variables filled from Hyprland queries when the hook runs, e.g.
+    /// `capture = { workspace = "activeworkspace.name" }`; the event's own
+    /// fields are always available by name.
+    #[serde(default)]
+    capture: HashMap<String, String>,
+    #[serde(skip)]
+    timing: Arc<Timing>,
+    #[serde(flatten)]
+    action: HookAction,
+}
+
+/// Bookkeeping for `cooldown` and `debounce`, shared by clones of a hook.
+#[derive(Debug, Default)]
+struct Timing {
+    last_run: Mutex<Option<Instant>>,
+    /// Counts matching events so a debounced run can tell it was superseded.
+    pending: AtomicU64,
+}
+
+/// Further conditions that must all hold for a hook to run.
+#[derive(Debug, Clone, Deserialize)]
+struct Conditions {
+    /// Regular expressions matched against the event's fields by name, e.g.
+    /// `match = { window_class = "^(zoom|Slack)$" }`.
+    #[serde(default, rename = "match")]
+    fields: HashMap<String, Pattern>,
+    /// Local time of day, e.g. `"09:00-17:00"`; a range ending before it
+    /// starts wraps around midnight.
+    #[serde(default)]
+    hours: Option<Hours>,
+    /// Monitors that must all be connected.
+    #[serde(default)]
+    monitors: Vec<String>,
+    /// Workspaces one of which must be focused.
+    #[serde(default)]
+    workspaces: Vec<String>,
+}
+
+/// A regular expression compiled when the config is loaded.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Pattern(pub Regex);
+
+impl TryFrom<String> for Pattern {
+    type Error = regex::Error;
+
+    fn try_from(pattern: String) -> Result<Pattern, regex::Error> {
+        Regex::new(&pattern).map(Pattern)
+    }
+}
+
+/// Minutes since midnight, the end excluded.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(try_from = "String")]
+struct Hours {
+    start: u32,
+    end: u32,
+}
+
+impl TryFrom<String> for Hours {
+    type Error = String;
+
+    fn try_from(range: String) -> Result<Hours, String> {
+        let parse = |time: &str| {
+            let (hours, minutes) = time.trim().split_once(':')?;
+            let (hours, minutes) = (hours.parse::<u32>().ok()?, minutes.parse::<u32>().ok()?);
+            (hours <= 24 && minutes < 60 && hours * 60 + minutes <= 24 * 60)
+                .then_some(hours * 60 + minutes)
+        };
+        range
+            .split_once('-')
+            .and_then(|(start, end)| {
+                Some(Hours {
+                    start: parse(start)?,
+                    end: parse(end)?,
+                })
+            })
+            .ok_or_else(|| format!("invalid time range '{}', expected HH:MM-HH:MM", range))
+    }
+}
+
+impl Hours {
+    fn contains(&self, minute: u32) -> bool {
+        if self.start <= self.end {
+            self.start <= minute && minute < self.end
+        } else {
+            minute >= self.start || minute < self.end
+        }
+    }
+}
+
+impl Conditions {
+    /// Whether the fields of `data`, the event's payload, and the time of
+    /// day match.
+    fn match_event(&self, data: &Value) -> bool {
+        self.fields
+            .iter()
+            .all(|(name, Pattern(pattern))| match data.get(name) {
+                Some(Value::String(value)) => pattern.is_match(value),
+                Some(value) => pattern.is_match(&value.to_string()),
+                None => false,
+            })
+            && self
+                .hours
+                .is_none_or(|hours| hours.contains(local_minute()))
+    }
+
+    /// Whether the monitor and workspace conditions hold, asking Hyprland
+    /// for its monitors only if there are any. Waits for socket1, so called
+    /// from the hook's own thread.
+    fn hold_on_monitors(&self) -> bool {
+        if self.monitors.is_empty() && self.workspaces.is_empty() {
+            return true;
+        }
+        match query_monitors() {
+            Ok(monitors) => self.monitors_match(&monitors),
+            Err(e) => {
+                warn!("Could not query monitors for hook conditions: {}", e);
+                false
+            }
+        }
+    }
+
+    fn monitors_match(&self, monitors: &[Monitor]) -> bool {
+        self.monitors
+            .iter()
+            .all(|name| monitors.iter().any(|m| &m.name == name))
+            && (self.workspaces.is_empty()
+                || monitors
+                    .iter()
+                    .filter(|m| m.focused)
+                    .any(|m| self.workspaces.contains(&m.active_workspace.name)))
+    }
+}
+
+/// Minutes since local midnight.
+fn local_minute() -> u32 {
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    unsafe {
+        let now = libc::time(std::ptr::null_mut());
+        libc::localtime_r(&now, &mut tm);
+    }
+    (tm.tm_hour * 60 + tm.tm_min) as u32
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum HookAction {
+    /// Shell command run through `sh -c`. The event is passed as JSON in
+    /// `$HYPRMAN_EVENT`, its name in `$HYPRMAN_EVENT_NAME` and every variable
+    /// as an environment variable of the same name.
+    Exec(String),
+    /// Sound file played with the configured `sound_player`.
+    Sound(String),
+    /// Hyprland dispatcher with its arguments, e.g.
+    /// `"movetoworkspace 5,address:0x{window_address}"`.
+    Dispatch(String),
+    /// Actions run one after another, each waiting for the previous one.
+    Steps(Vec<Step>),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Step {
+    /// Milliseconds to wait before the action.
+    #[serde(default)]
+    delay: u64,
+    #[serde(flatten)]
+    action: HookAction,
+}
+
+impl HookAction {
+    fn check(&self, context: &str, sound_player: &str, findings: &mut Findings) {
+        match self {
+            HookAction::Exec(command) | HookAction::Dispatch(command)
+                if command.trim().is_empty() =>
+            {
+                findings.error(format!("{}: the command is empty", context))
+            }
+            HookAction::Sound(file) if !Path::new(file).is_file() => {
+                findings.warning(format!("{}: sound file {} does not exist", context, file))
+            }
+            HookAction::Sound(_) if !in_path(sound_player) => findings.warning(format!(
+                "{}: sound_player '{}' is not in $PATH",
+                context, sound_player
+            )),
+            HookAction::Steps(steps) => {
+                for (i, step) in steps.iter().enumerate() {
+                    let context = format!("{}, step {}", context, i + 1);
+                    step.action.check(&context, sound_player, findings);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Whether the program of a command line, e.g. `"mpv --no-video"`, can be run.
+fn in_path(command: &str) -> bool {
+    let Some(program) = command.split_whitespace().next() else {
+        return false;
+    };
+    if program.contains('/') {
+        return Path::new(program).is_file();
+    }
+    std::env::var("PATH")
+        .unwrap_or_default()
+        .split(':')
+        .any(|dir| Path::new(dir).join(program).is_file())
+}
+
+/// Reports hooks listening for unknown events or running nothing.
+pub fn check(hooks: &[Hook], sound_player: &str, findings: &mut Findings) {
+    for (i, hook) in hooks.iter().enumerate() {
+        let context = match &hook.name {
+            Some(name) => format!("hook '{}'", name),
+            None => format!("hooks[{}]", i),
+        };
+        if hook.events.is_empty() {
+            findings.warning(format!("{}: listens for no events and never runs", context));
+        }
+        for event in &hook.events {
+            findings.check_event(&context, event);
+        }
+        hook.action.check(&context, sound_player, findings);
+    }
+}
+
+pub fn default_sound_player() -> String {
+    "paplay".to_string()
+}
+
+/// The configured hooks and the settings they share.
+pub struct Hooks {
+    hooks: Vec<Hook>,
+    /// Player command, optionally with arguments; the file is appended.
+    sound_player: String,
+}
+
+impl Hooks {
+    pub fn new(hooks: Vec<Hook>, sound_player: String) -> Hooks {
+        Hooks {
+            hooks,
+            sound_player,
+        }
+    }
+
+    /// Whether any hook is assigned to the named profile.
+    pub fn has_profile(&self, profile: &str) -> bool {
+        self.hooks
+            .iter()
+            .any(|h| h.profiles.iter().any(|p| p == profile))
+    }
+
+    /// Runs every hook listening for `event` that is active under `profile`,
+    /// without waiting for it, and records what was run in `audit`. With
+    /// `dry_run` every hook behaves as if it had `dry_run` set.
+    pub fn fire(
+        &self,
+        event: &HyprlandEvent,
+        profile: Option<&str>,
+        dry_run: bool,
+        audit: &Arc<AuditLog>,
+    ) {
+        let event_name = event_type(event);
+        let data = serde_json::to_value(event).unwrap()["data"].take();
+        for hook in self.hooks.iter().filter(|h| {
+            h.events.iter().any(|e| e == event_name)
+                && (h.profiles.is_empty()
+                    || profile.is_some_and(|p| h.profiles.iter().any(|hp| hp == p)))
+                && h.conditions.match_event(&data)
+        }) {
+            let Some(debounce) = hook.debounce else {
+                run(hook, &self.sound_player, event, dry_run, audit);
+                continue;
+            };
+            // Only the last event of a burst runs the hook, once it settled.
+            let generation = hook.timing.pending.fetch_add(1, Ordering::SeqCst) + 1;
+            let (hook, sound_player) = (hook.clone(), self.sound_player.clone());
+            let (event, audit) = (event.clone(), audit.clone());
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(debounce));
+                if hook.timing.pending.load(Ordering::SeqCst) == generation {
+                    run(&hook, &sound_player, &event, dry_run, &audit);
+                }
+            });
+        }
+    }
+}
+
+/// Starts the hook's action in a thread of its own if its monitor
+/// conditions hold and it is not cooling down.
+fn run(
+    hook: &Hook,
+    sound_player: &str,
+    event: &HyprlandEvent,
+    dry_run: bool,
+    audit: &Arc<AuditLog>,
+) {
+    // Monitor conditions, captures and dispatches wait for socket1, which
+    // must not hold up the events for everyone else.
+    let (hook, sound_player) = (hook.clone(), sound_player.to_string());
+    let (event, audit) = (event.clone(), audit.clone());
+    thread::spawn(move || {
+        if !hook.conditions.hold_on_monitors() || hook.cooling_down(&event) {
+            return;
+        }
+        let variables = hook.variables(&event);
+        let run = Run {
+            hook: &hook,
+            sound_player: &sound_player,
+            event: &event,
+            variables: &variables,
+            dry_run: dry_run || hook.dry_run,
+            audit: &audit,
+        };
+        run.perform(&hook.action);
+    });
+}
+
+/// What the actions of one run of a hook share.
+struct Run<'a> {
+    hook: &'a Hook,
+    sound_player: &'a str,
+    event: &'a HyprlandEvent,
+    variables: &'a HashMap<String, String>,
+    dry_run: bool,
+    audit: &'a Arc<AuditLog>,
+}
+
+impl Run<'_> {
+    /// Runs `action` and returns once it finished.
+    fn perform(&self, action: &HookAction) {
+        let event_name = event_type(self.event);
+        if let HookAction::Steps(steps) = action {
+            for step in steps {
+                thread::sleep(Duration::from_millis(step.delay));
+                self.perform(&step.action);
+            }
+            return;
+        }
+        let mut record = AuditRecord {
+            source: "hook",
+            name: self.hook.name.as_deref(),
+            trigger: Some(self.event),
+            command: action.command_line(self.sound_player, self.variables),
+            result: json!(null),
+        };
+        if self.dry_run {
+            info!("Hook for '{}' would run: {}", event_name, record.command);
+            record.result = json!({ "dry_run": true });
+            self.audit.record(&record);
+            return;
+        }
+        let spawned = match action {
+            HookAction::Exec(command) => Command::new("sh")
+                .arg("-c")
+                .arg(command)
+                .env("HYPRMAN_EVENT", serde_json::to_string(self.event).unwrap())
+                .env("HYPRMAN_EVENT_NAME", event_name)
+                // Follows Hyprland restarts, unlike the daemon's environment.
+                .envs(
+                    instance_signature()
+                        .ok()
+                        .map(|signature| ("HYPRLAND_INSTANCE_SIGNATURE", signature)),
+                )
+                .envs(self.variables)
+                .spawn(),
+            HookAction::Sound(file) => {
+                sound_command(self.sound_player, &substitute(file, self.variables)).spawn()
+            }
+            HookAction::Dispatch(_) => {
+                record.result = match query_socket(&format!("dispatch {}", record.command)) {
+                    Ok(reply) => {
+                        info!("Hook for '{}' dispatched: {}", event_name, record.command);
+                        json!({ "reply": reply.trim() })
+                    }
+                    Err(e) => {
+                        error!("Failed to dispatch {}: {}", record.command, e);
+                        json!({ "error": e.to_string() })
+                    }
+                };
+                self.audit.record(&record);
+                return;
+            }
+            HookAction::Steps(_) => unreachable!(),
+        };
+        match spawned {
+            Ok(mut child) => {
+                info!("Hook for '{}' started: {}", event_name, record.command);
+                record.result = json!({ "spawned": child.id() });
+                self.audit.record(&record);
+                record.result = exit_result(&mut child);
+                self.audit.record(&record);
+            }
+            Err(e) => {
+                error!("Failed to run hook {}: {}", record.command, e);
+                record.result = json!({ "error": e.to_string() });
+                self.audit.record(&record);
+            }
+        }
+    }
+}
+
+impl Hook {
+    /// Whether the hook ran less than `cooldown` ago; if not, notes that it
+    /// runs now.
+    fn cooling_down(&self, event: &HyprlandEvent) -> bool {
+        let Some(cooldown) = self.cooldown else {
+            return false;
+        };
+        let mut last_run = self.timing.last_run.lock().unwrap();
+        if last_run.is_some_and(|t| t.elapsed() < Duration::from_millis(cooldown)) {
+            info!(
+                "Hook for '{}' is cooling down: {:?}",
+                event_type(event),
+                self.action
+            );
+            return true;
+        }
+        *last_run = Some(Instant::now());
+        false
+    }
+
+    /// The event's fields and the hook's captures, by name.
+    fn variables(&self, event: &HyprlandEvent) -> HashMap<String, String> {
+        let mut variables = HashMap::new();
+        if let Value::Object(data) = serde_json::to_value(event).unwrap()["data"].take() {
+            for (name, value) in data {
+                variables.insert(name, plain(&value));
+            }
+        }
+        for (name, path) in &self.capture {
+            let (query, fields) = path.split_once('.').unwrap_or((path, ""));
+            let response = query_json::<Value>(&format!("j/{}", query));
+            match response.map(|response| walk(response, fields)) {
+                Ok(Some(value)) => {
+                    variables.insert(name.clone(), plain(&value));
+                }
+                Ok(None) => warn!("Capture '{}' found nothing at {}", name, path),
+                Err(e) => warn!("Could not query {} for capture '{}': {}", query, name, e),
+            }
+        }
+        variables
+    }
+}
+
+/// The value at a dotted path of object keys and array indices, e.g.
+/// `1.class`; an empty path is the value itself.
+fn walk(value: Value, path: &str) -> Option<Value> {
+    path.split('.')
+        .filter(|field| !field.is_empty())
+        .try_fold(value, |value, field| match value {
+            Value::Array(mut items) => {
+                let index = field.parse::<usize>().ok()?;
+                (index < items.len()).then(|| items.swap_remove(index))
+            }
+            Value::Object(mut fields) => fields.remove(field),
+            _ => None,
+        })
+}
+
+/// A JSON value as substituted into commands: strings without quotes.
+fn plain(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+impl HookAction {
+    /// The command the action runs, as written to the audit log. Variables
+    /// are substituted for `{name}` in dispatchers and sound files.
+    fn command_line(&self, sound_player: &str, variables: &HashMap<String, String>) -> String {
+        match self {
+            HookAction::Exec(command) => command.clone(),
+            HookAction::Sound(file) => format!("{} {}", sound_player, substitute(file, variables)),
+            HookAction::Dispatch(dispatcher) => substitute(dispatcher, variables),
+            HookAction::Steps(steps) => format!("{} steps", steps.len()),
+        }
+    }
+}
+
+/// `template` with the variables substituted for `{name}`.
+fn substitute(template: &str, variables: &HashMap<String, String>) -> String {
+    variables
+        .iter()
+        .fold(template.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), value)
+        })
+}
+
+/// The player command with its arguments split on whitespace, and the file
+/// as one argument however many spaces its path has.
+fn sound_command(sound_player: &str, file: &str) -> Command {
+    let mut player = sound_player.split_whitespace();
+    let mut command = Command::new(player.next().unwrap_or("paplay"));
+    command.args(player).arg(file);
+    command
+}
+
+fn exit_result(child: &mut Child) -> Value {
+    let pid = child.id();
+    match child.wait() {
+        Ok(status) => json!({ "exited": status.code(), "pid": pid }),
+        Err(e) => {
+            error!("Failed to wait for hook process: {}", e);
+            json!({ "error": e.to_string(), "pid": pid })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyprman::{event::Address, hypr::Workspace};
+    use std::{env, fs};
+
+    fn hook(toml: &str) -> Hook {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn monitor(name: &str, focused: bool, workspace: &str) -> Monitor {
+        Monitor {
+            id: 0,
+            name: name.to_string(),
+            description: String::new(),
+            focused,
+            active_workspace: Workspace {
+                name: workspace.to_string(),
+                ..Workspace::default()
+            },
+            special_workspace: Workspace::default(),
+        }
+    }
+
+    #[test]
+    fn parses_hours() {
+        let hours = Hours::try_from("09:00-17:30".to_string()).unwrap();
+        assert_eq!((hours.start, hours.end), (9 * 60, 17 * 60 + 30));
+        assert!(hours.contains(9 * 60));
+        assert!(hours.contains(17 * 60 + 29));
+        assert!(!hours.contains(17 * 60 + 30));
+        assert!(!hours.contains(8 * 60 + 59));
+
+        // Ending before it starts wraps around midnight.
+        let night = Hours::try_from("22:00-06:00".to_string()).unwrap();
+        assert!(night.contains(23 * 60));
+        assert!(night.contains(0));
+        assert!(night.contains(5 * 60 + 59));
+        assert!(!night.contains(6 * 60));
+        assert!(!night.contains(12 * 60));
+
+        let day = Hours::try_from(" 00:00 - 24:00 ".to_string()).unwrap();
+        assert!(day.contains(0) && day.contains(24 * 60 - 1));
+
+        for range in ["9-17", "09:00", "25:00-26:00", "10:60-11:00", "24:01-01:00"] {
+            assert!(Hours::try_from(range.to_string()).is_err(), "{}", range);
+        }
+    }
+
+    #[test]
+    fn checks_conditions() {
+        let data = json!({ "window_class": "kitty", "workspace_id": 3 });
+        let conditions =
+            |toml: &str| hook(&format!("events = []\nexec = \"\"\n{}", toml)).conditions;
+
+        assert!(conditions("").match_event(&data));
+        assert!(conditions("match = { window_class = \"^(kitty|foot)$\" }").match_event(&data));
+        assert!(conditions("match = { workspace_id = \"^3$\" }").match_event(&data));
+        assert!(!conditions("match = { window_class = \"^foot$\" }").match_event(&data));
+        assert!(!conditions("match = { window_title = \"\" }").match_event(&data));
+        assert!(conditions("hours = \"00:00-24:00\"").match_event(&data));
+
+        let monitors = [monitor("DP-1", true, "1"), monitor("HDMI-A-1", false, "3")];
+        assert!(conditions("").monitors_match(&monitors));
+        assert!(conditions("monitors = [\"DP-1\", \"HDMI-A-1\"]").monitors_match(&monitors));
+        assert!(!conditions("monitors = [\"DP-1\", \"DP-2\"]").monitors_match(&monitors));
+        // Only the focused monitor's workspace counts.
+        assert!(conditions("workspaces = [\"1\", \"2\"]").monitors_match(&monitors));
+        assert!(!conditions("workspaces = [\"3\"]").monitors_match(&monitors));
+    }
+
+    #[test]
+    fn debounces_and_cools_down() {
+        let path = env::temp_dir().join(format!("hyprman-hooks-{}.audit", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let audit = Arc::new(AuditLog::open(path.to_str()));
+        let hooks = Hooks::new(
+            vec![
+                hook(
+                    "name = \"cooldown\"\nevents = [\"urgent\"]\ncooldown = 60000\nexec = \"true\"",
+                ),
+                hook("name = \"debounce\"\nevents = [\"urgent\"]\ndebounce = 100\nexec = \"true\""),
+            ],
+            default_sound_player(),
+        );
+        let event = HyprlandEvent::Urgent {
+            window_address: Address::from("5f3a"),
+        };
+        for _ in 0..3 {
+            hooks.fire(&event, None, true, &audit);
+        }
+        thread::sleep(Duration::from_millis(500));
+        let log = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        let runs = |name: &str| {
+            log.lines()
+                .map(|line| serde_json::from_str::<Value>(line).unwrap())
+                .filter(|record| record["name"] == name)
+                .count()
+        };
+        assert_eq!(runs("cooldown"), 1);
+        assert_eq!(runs("debounce"), 1);
+    }
+
+    #[test]
+    fn substitutes_variables() {
+        let variables = HashMap::from([
+            ("window_address".to_string(), "5f3a".to_string()),
+            ("workspace".to_string(), "web".to_string()),
+        ]);
+        let command_line = |toml: &str| {
+            hook(&format!("events = []\n{}", toml))
+                .action
+                .command_line("mpv --no-video", &variables)
+        };
+        assert_eq!(
+            command_line("dispatch = \"movetoworkspace {workspace},address:0x{window_address}\""),
+            "movetoworkspace web,address:0x5f3a"
+        );
+        assert_eq!(
+            command_line("sound = \"/sounds/{workspace}.wav\""),
+            "mpv --no-video /sounds/web.wav"
+        );
+        assert_eq!(
+            command_line("dispatch = \"exec notify-send {unknown}\""),
+            "exec notify-send {unknown}"
+        );
+        // The shell gets variables from its environment instead.
+        assert_eq!(
+            command_line("exec = \"echo {workspace}\""),
+            "echo {workspace}"
+        );
+        assert_eq!(
+            command_line("steps = [{ exec = \"true\" }, { delay = 10, exec = \"false\" }]"),
+            "2 steps"
+        );
+    }
+
+    #[test]
+    fn walks_capture_paths() {
+        let clients = json!([
+            { "class": "kitty", "workspace": { "id": 1, "name": "1" } },
+            { "class": "firefox", "workspace": { "id": 2, "name": "web" } },
+        ]);
+        assert_eq!(walk(clients.clone(), "1.class"), Some(json!("firefox")));
+        assert_eq!(walk(clients.clone(), "0.workspace.id"), Some(json!(1)));
+        assert_eq!(
+            walk(clients.clone(), "1.workspace"),
+            Some(json!({ "id": 2, "name": "web" }))
+        );
+        assert_eq!(walk(clients.clone(), ""), Some(clients.clone()));
+        assert_eq!(walk(clients.clone(), "5.class"), None);
+        assert_eq!(walk(clients.clone(), "first.class"), None);
+        assert_eq!(walk(clients.clone(), "0.class.name"), None);
+        assert_eq!(walk(clients, "0.title"), None);
+    }
+
+    #[test]
+    fn takes_variables_from_the_event() {
+        let hook = hook(
+            "events = [\"openwindow\"]\ndispatch = \"focuswindow address:0x{window_address}\"",
+        );
+        let event = HyprlandEvent::OpenWindow {
+            window_address: Address::from("5f3a"),
+            workspace_name: "web".to_string(),
+            window_class: "kitty".to_string(),
+            window_title: "~".to_string(),
+        };
+        let variables = hook.variables(&event);
+        assert_eq!(variables["window_address"], "5f3a");
+        assert_eq!(variables["workspace_name"], "web");
+        assert_eq!(
+            hook.action.command_line("paplay", &variables),
+            "focuswindow address:0x5f3a"
+        );
+    }
+
+    #[test]
+    fn passes_sound_files_as_one_argument() {
+        let command = sound_command("mpv --no-video", "/home/me/My Sounds/bell.wav");
+        assert_eq!(command.get_program(), "mpv");
+        assert_eq!(
+            command.get_args().collect::<Vec<_>>(),
+            ["--no-video", "/home/me/My Sounds/bell.wav"]
+        );
+    }
+}
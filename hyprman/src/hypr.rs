@@ -0,0 +1,339 @@
+use crate::{
+    error::{HyprmanError, Result},
+    event::Address,
+    paths,
+};
+use log::warn;
+use serde::{Deserialize, Serialize, Serializer};
+use std::{
+    collections::HashMap,
+    env,
+    io::{Read, Write},
+    os::unix::net::UnixStream,
+    sync::RwLock,
+    time::SystemTime,
+};
+
+// === Structs for Interaction with Socket1
+#[derive(Clone, Default, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Workspace {
+    pub id: i64,
+    pub name: String,
+    /// Shown on some monitor. Filled in by the workspaces client.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visible: Option<bool>,
+    /// Shown on the focused monitor. Filled in by the workspaces client.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focused: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monitor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monitor_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub windows: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub has_fullscreen: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_window: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_window_title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub urgent: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub occupied: Option<bool>,
+    /// Rendered window indicator. Filled in by the workspaces client.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indicator: Option<String>,
+    /// Rendered label. Filled in by the workspaces client.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Windows asked to be minimized. Filled in by the workspaces client.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimized: Option<u32>,
+}
+
+impl Workspace {
+    /// Special workspaces (scratchpads) have negative ids and `special:` names.
+    pub fn is_special(&self) -> bool {
+        self.id < 0 || self.name.starts_with("special:")
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Client {
+    /// Written with the `0x` prefix, as Hyprland reports it and dispatchers
+    /// such as `focuswindow address:` expect it.
+    #[serde(serialize_with = "prefixed")]
+    pub address: Address,
+    pub mapped: bool,
+    pub hidden: bool,
+    pub at: (i32, i32),
+    pub size: (i32, i32),
+    pub workspace: Workspace,
+    pub floating: bool,
+    pub pseudo: bool,
+    pub monitor: i64,
+    pub class: String,
+    pub title: String,
+    pub initial_class: String,
+    pub initial_title: String,
+    pub pid: u32,
+    pub xwayland: bool,
+    pub pinned: bool,
+    pub fullscreen: i32,
+    pub fullscreen_client: i32,
+    #[serde(serialize_with = "prefixed_all")]
+    pub grouped: Vec<Address>,
+    pub tags: Vec<String>,
+    pub swallowing: String,
+    #[serde(rename = "focusHistoryID")]
+    pub focus_history_id: i32,
+    pub inhibiting_idle: bool,
+    /// Name of `monitor`. Filled in by the activewindow client.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monitor_name: Option<String>,
+    /// Asked to be minimized. Filled in by the activewindow client.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimized: Option<bool>,
+}
+
+fn prefixed<S: Serializer>(
+    address: &Address,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    match address.is_empty() {
+        true => serializer.serialize_str(""),
+        false => serializer.serialize_str(&address.prefixed()),
+    }
+}
+
+fn prefixed_all<S: Serializer>(
+    addresses: &[Address],
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_seq(addresses.iter().map(Address::prefixed))
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Monitor {
+    pub id: i64,
+    pub name: String,
+    pub description: String,
+    pub focused: bool,
+    pub active_workspace: Workspace,
+    pub special_workspace: Workspace,
+}
+
+/// Everything a client needs to rebuild its view without replaying events.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct State {
+    pub monitors: Vec<Monitor>,
+    pub workspaces: Vec<Workspace>,
+    pub clients: Vec<Client>,
+}
+
+// === Socket1 Queries ===
+
+/// Resolves `$XDG_RUNTIME_DIR/hypr/$HYPRLAND_INSTANCE_SIGNATURE`.
+pub fn hypr_rundir_path() -> Result<String> {
+    Ok(format!(
+        "{}/hypr/{}",
+        paths::runtime_dir()?,
+        instance_signature()?
+    ))
+}
+
+/// Instance switched to after the one of `$HYPRLAND_INSTANCE_SIGNATURE` went
+/// away, as the environment cannot be changed once threads run.
+static INSTANCE: RwLock<Option<String>> = RwLock::new(None);
+
+/// Signature of the Hyprland instance talked to.
+pub fn instance_signature() -> Result<String> {
+    if let Some(signature) = INSTANCE.read().unwrap().as_ref() {
+        return Ok(signature.clone());
+    }
+    env::var("HYPRLAND_INSTANCE_SIGNATURE")
+        .map_err(|_| HyprmanError::Environment("HYPRLAND_INSTANCE_SIGNATURE"))
+}
+
+/// Talks to another Hyprland instance for the rest of the process, e.g. the
+/// one that replaced a Hyprland that exited.
+pub fn set_instance_signature(signature: String) {
+    *INSTANCE.write().unwrap() = Some(signature);
+}
+
+/// A Hyprland session found under `$XDG_RUNTIME_DIR/hypr/`.
+#[derive(Debug)]
+pub struct Instance {
+    pub signature: String,
+    /// When its request socket was created.
+    pub started: SystemTime,
+}
+
+/// The Hyprland sessions whose request socket accepts connections, newest
+/// first. Directories left behind by sessions that crashed are skipped.
+pub fn instances() -> Result<Vec<Instance>> {
+    let entries = match std::fs::read_dir(format!("{}/hypr", paths::runtime_dir()?)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut instances: Vec<Instance> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let socket = entry.path().join(".socket.sock");
+            let started = socket.metadata().and_then(|m| m.modified()).ok()?;
+            UnixStream::connect(&socket).ok()?;
+            Some(Instance {
+                signature: entry.file_name().into_string().ok()?,
+                started,
+            })
+        })
+        .collect();
+    instances.sort_by_key(|instance| std::cmp::Reverse(instance.started));
+    Ok(instances)
+}
+
+/// Socket paths set explicitly, e.g. to talk to a mock server; `None`
+/// falls back to the path derived from the environment.
+static SOCKET_PATHS: RwLock<(Option<String>, Option<String>)> = RwLock::new((None, None));
+
+/// Overrides the paths of socket1 and socket2 for the rest of the process.
+pub fn set_socket_paths(socket1: Option<String>, socket2: Option<String>) {
+    *SOCKET_PATHS.write().unwrap() = (socket1, socket2);
+}
+
+/// The socket1 and socket2 paths set explicitly, if any.
+pub fn socket_paths() -> (Option<String>, Option<String>) {
+    SOCKET_PATHS.read().unwrap().clone()
+}
+
+/// Whether socket1 or socket2 was given explicitly, so that switching the
+/// instance would not change which Hyprland is talked to.
+pub fn socket_paths_fixed() -> bool {
+    let paths = SOCKET_PATHS.read().unwrap();
+    paths.0.is_some() || paths.1.is_some()
+}
+
+/// Path of socket1, the request socket.
+pub fn socket1_path() -> Result<String> {
+    match &SOCKET_PATHS.read().unwrap().0 {
+        Some(path) => Ok(path.clone()),
+        None => Ok(format!("{}/.socket.sock", hypr_rundir_path()?)),
+    }
+}
+
+/// Path of socket2, the event socket.
+pub fn socket2_path() -> Result<String> {
+    match &SOCKET_PATHS.read().unwrap().1 {
+        Some(path) => Ok(path.clone()),
+        None => Ok(format!("{}/.socket2.sock", hypr_rundir_path()?)),
+    }
+}
+
+pub fn create_socket(socket_path: &str) -> Result<UnixStream> {
+    UnixStream::connect(socket_path).map_err(|source| HyprmanError::Connect {
+        path: socket_path.to_string(),
+        source,
+    })
+}
+
+/// Sends a single request to socket1 and returns the raw response.
+pub fn query_socket(query: &str) -> Result<String> {
+    let mut stream = create_socket(&socket1_path()?)?;
+    stream.write_all(query.as_bytes())?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+    // Window titles are not guaranteed to be valid UTF-8.
+    Ok(String::from_utf8_lossy(&response).into_owned())
+}
+
+/// Sends a `j/` request to socket1 and parses the JSON response.
+pub fn query_json<T: serde::de::DeserializeOwned>(query: &str) -> Result<T> {
+    serde_json::from_str(&query_socket(query)?).map_err(|source| HyprmanError::Parse {
+        context: format!("the response to {}", query),
+        source,
+    })
+}
+
+/// Asks socket1 for the Hyprland version, logging instead of failing if it
+/// is unreachable.
+pub fn query_hyprland_version() -> Option<String> {
+    let version: serde_json::Value = query_json("j/version")
+        .inspect_err(|e| warn!("Could not query Hyprland version: {}", e))
+        .ok()?;
+    version
+        .get("version")
+        .or_else(|| version.get("tag"))
+        .and_then(|v| v.as_str())
+        .map(str::to_string)
+}
+/// Queries monitors, workspaces and clients.
+pub fn query_state() -> Result<State> {
+    Ok(State {
+        monitors: query_json("j/monitors")?,
+        workspaces: query_json("j/workspaces")?,
+        clients: query_json("j/clients")?,
+    })
+}
+/// Every window, keyed by address.
+pub fn query_clients() -> Result<HashMap<Address, Client>> {
+    let clients: Vec<Client> = query_json("j/clients")?;
+    Ok(clients
+        .into_iter()
+        .map(|c| (c.address.clone(), c))
+        .collect())
+}
+pub fn query_active_workspace() -> Result<Workspace> {
+    query_json("j/activeworkspace")
+}
+pub fn query_workspaces() -> Result<Vec<Workspace>> {
+    query_json("j/workspaces")
+}
+pub fn query_monitors() -> Result<Vec<Monitor>> {
+    query_json("j/monitors")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{os::unix::net::UnixListener, thread};
+
+    #[test]
+    fn keys_clients_by_normalized_address() {
+        let path = env::temp_dir().join(format!("hyprman-socket1-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        // A window as socket1 reports it, with the prefix in upper case.
+        let mut window = serde_json::to_value(Client::default()).unwrap();
+        window["address"] = "0x55AA".into();
+        window["grouped"] = serde_json::json!(["0x55aa", "0x66BB"]);
+        let response = serde_json::json!([window]).to_string();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 64];
+            let _ = stream.read(&mut request).unwrap();
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+        set_socket_paths(Some(path.to_string_lossy().into_owned()), None);
+        let clients = query_clients();
+        let _ = std::fs::remove_file(&path);
+
+        let clients = clients.unwrap();
+        let client = &clients[&Address::from("55aa")];
+        assert_eq!(
+            client.grouped,
+            [Address::from("55aa"), Address::from("66bb")]
+        );
+        let json = serde_json::to_value(client).unwrap();
+        assert_eq!(json["address"], "0x55aa");
+        assert_eq!(json["grouped"], serde_json::json!(["0x55aa", "0x66bb"]));
+        assert_eq!(
+            serde_json::to_value(Client::default()).unwrap()["address"],
+            ""
+        );
+    }
+}
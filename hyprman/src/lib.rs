@@ -0,0 +1,11 @@
+//! Client-side building blocks shared by the `hyprman` binary and the C
+//! bindings: the event model, socket1 queries and daemon connections.
+
+pub mod client;
+pub mod error;
+pub mod event;
+pub mod ffi;
+pub mod hypr;
+pub mod paths;
+pub mod proto;
+pub mod schema;
@@ -0,0 +1,114 @@
+use hyprman::error::{HyprmanError, Result};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, Write},
+    os::{fd::AsRawFd, unix::fs::MetadataExt},
+};
+
+// === Single-Instance Lock ===
+
+/// An exclusive `flock` on the PID file, held for the daemon's lifetime. The
+/// kernel drops it when the daemon exits, however it exits, so unlike the PID
+/// alone it cannot go stale or point at a reused PID. The file is removed
+/// when the lock is dropped on a clean shutdown.
+pub struct InstanceLock {
+    path: String,
+    file: File,
+    /// The process the file names; a parent exiting after a fork leaves
+    /// the file alone.
+    pid: u32,
+}
+
+impl InstanceLock {
+    /// Takes the lock or fails naming the daemon holding it.
+    pub fn acquire(path: &str) -> Result<InstanceLock> {
+        loop {
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(path)?;
+            if !try_lock(&file, libc::LOCK_EX)? {
+                let message = match read_pid(&file) {
+                    Some(pid) => format!("daemon already running with PID {}", pid),
+                    None => "daemon already running".to_string(),
+                };
+                return Err(HyprmanError::Daemon(message));
+            }
+            // A daemon shutting down may have removed the file between the
+            // open and the lock; locking that orphan would not exclude the
+            // next daemon, which creates a new file.
+            if !is_same_file(&file, path) {
+                continue;
+            }
+            let mut lock = InstanceLock {
+                path: path.to_string(),
+                file,
+                pid: 0,
+            };
+            lock.write_pid()?;
+            return Ok(lock);
+        }
+    }
+
+    /// Records the current process in the file; call again after forking.
+    pub fn write_pid(&mut self) -> io::Result<()> {
+        self.pid = std::process::id();
+        self.file.set_len(0)?;
+        self.file.rewind()?;
+        writeln!(self.file, "{}", self.pid)
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        // Removed while still locked, see `acquire`.
+        if self.pid == std::process::id() && is_same_file(&self.file, &self.path) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn is_same_file(file: &File, path: &str) -> bool {
+    match (file.metadata(), fs::metadata(path)) {
+        (Ok(open), Ok(named)) => (open.dev(), open.ino()) == (named.dev(), named.ino()),
+        _ => false,
+    }
+}
+
+/// The PID of the daemon holding the lock on `path`, if one is running.
+pub fn holder(path: &str) -> Result<Option<i32>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    // A shared lock is only refused while a daemon holds the exclusive one.
+    if try_lock(&file, libc::LOCK_SH)? {
+        return Ok(None);
+    }
+    read_pid(&file)
+        .map(Some)
+        .ok_or_else(|| HyprmanError::Daemon(format!("no PID in locked file {}", path)))
+}
+
+/// `flock` without blocking; false if another process holds a conflicting
+/// lock.
+fn try_lock(file: &File, operation: libc::c_int) -> io::Result<bool> {
+    if unsafe { libc::flock(file.as_raw_fd(), operation | libc::LOCK_NB) } == 0 {
+        return Ok(true);
+    }
+    let error = io::Error::last_os_error();
+    match error.raw_os_error() {
+        Some(libc::EWOULDBLOCK) => Ok(false),
+        _ => Err(error),
+    }
+}
+
+fn read_pid(mut file: &File) -> Option<i32> {
+    let mut contents = String::new();
+    file.rewind().ok()?;
+    file.read_to_string(&mut contents).ok()?;
+    contents.trim().parse().ok()
+}
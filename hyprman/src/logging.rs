@@ -0,0 +1,261 @@
+use hyprman::{
+    error::{HyprmanError, Result},
+    paths,
+};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde::Deserialize;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    os::unix::net::UnixDatagram,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+// === Logging ===
+
+/// Where the daemon's log records go.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogTarget {
+    Stderr,
+    /// The file configured in `[logging]`.
+    File,
+    /// The systemd journal's native socket.
+    Journald,
+}
+
+/// Filters used when the config sets no `log_level`.
+pub const DAEMON_LEVEL: &str = "info";
+pub const CLIENT_LEVEL: &str = "error";
+
+const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// The file of the `file` log target, configured as `[logging]`.
+#[derive(Debug, Deserialize)]
+pub struct LoggingConfig {
+    // Log file; if relative, it is interpreted relative to
+    // $XDG_STATE_HOME/hyprman/ ($XDG_STATE_HOME defaulting to ~/.local/state).
+    #[serde(default = "default_file")]
+    file: String,
+    // Size in bytes past which the file is renamed to `<file>.1`, shifting
+    // older ones up to `<file>.<max_files>`, and a new one is started.
+    #[serde(default = "default_max_size")]
+    max_size: u64,
+    #[serde(default = "default_max_files")]
+    max_files: usize,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        LoggingConfig {
+            file: default_file(),
+            max_size: default_max_size(),
+            max_files: default_max_files(),
+        }
+    }
+}
+
+fn default_file() -> String {
+    "hyprman.log".to_string()
+}
+
+fn default_max_size() -> u64 {
+    1024 * 1024
+}
+
+fn default_max_files() -> usize {
+    3
+}
+
+/// A log file that starts over once it grows past `max_size`.
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    max_files: usize,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, max_size: u64, max_files: usize) -> io::Result<RotatingFile> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(RotatingFile {
+            path,
+            file,
+            size,
+            max_size,
+            max_files,
+        })
+    }
+
+    fn rotated(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        path.into()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.max_files == 0 {
+            self.file.set_len(0)?;
+        } else {
+            for index in (1..self.max_files).rev() {
+                let from = self.rotated(index);
+                if from.exists() {
+                    fs::rename(from, self.rotated(index + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated(1))?;
+            self.file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+        }
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// $XDG_STATE_HOME/hyprman, with XDG_STATE_HOME defaulting to
+/// ~/.local/state.
+fn state_dir() -> Result<PathBuf> {
+    Ok(Path::new(&paths::state_home()?).join("hyprman"))
+}
+
+/// Sends every log record to the journal as one datagram.
+struct JournalWriter {
+    socket: UnixDatagram,
+}
+
+impl Write for JournalWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.socket.send(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// syslog priority of a log level.
+fn priority(level: Level) -> u8 {
+    match level {
+        Level::Error => 3,
+        Level::Warn => 4,
+        Level::Info => 6,
+        Level::Debug | Level::Trace => 7,
+    }
+}
+
+/// Appends a field in the journal's native format, which needs the length
+/// spelled out for values spanning lines.
+fn journal_field(buf: &mut impl Write, name: &str, value: &str) -> io::Result<()> {
+    if value.contains('\n') {
+        buf.write_all(name.as_bytes())?;
+        buf.write_all(b"\n")?;
+        buf.write_all(&(value.len() as u64).to_le_bytes())?;
+        buf.write_all(value.as_bytes())?;
+        buf.write_all(b"\n")
+    } else {
+        writeln!(buf, "{}={}", name, value)
+    }
+}
+
+/// Decides which records are logged; replaced by `set_level`.
+static FILTER: RwLock<Option<env_logger::Logger>> = RwLock::new(None);
+
+/// Formats and writes what `FILTER` lets through, so the level can change
+/// while the target stays open.
+struct Logger {
+    output: env_logger::Logger,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        FILTER
+            .read()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|filter| filter.enabled(metadata))
+    }
+
+    fn log(&self, record: &Record) {
+        if FILTER
+            .read()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|filter| filter.matches(record))
+        {
+            self.output.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.output.flush();
+    }
+}
+
+/// Changes what is logged. `level` is an env_logger filter such as `"info"`
+/// or `"hyprman=debug"`; `RUST_LOG` still takes precedence.
+pub fn set_level(level: &str) {
+    let filter =
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(level)).build();
+    log::set_max_level(filter.filter());
+    *FILTER.write().unwrap() = Some(filter);
+}
+
+/// Sets up the logger, see `set_level` for `level`.
+pub fn init(target: LogTarget, level: &str, config: &LoggingConfig) -> Result<()> {
+    let mut builder = env_logger::Builder::new();
+    builder.filter_level(LevelFilter::Trace);
+    match target {
+        LogTarget::Stderr => {}
+        LogTarget::File => {
+            let path = state_dir()?.join(&config.file);
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let file = RotatingFile::open(path, config.max_size, config.max_files)?;
+            builder.target(env_logger::Target::Pipe(Box::new(file)));
+        }
+        LogTarget::Journald => {
+            let socket = UnixDatagram::unbound()?;
+            socket
+                .connect(JOURNAL_SOCKET)
+                .map_err(|source| HyprmanError::Connect {
+                    path: JOURNAL_SOCKET.to_string(),
+                    source,
+                })?;
+            builder
+                .format(|buf, record| {
+                    writeln!(buf, "PRIORITY={}", priority(record.level()))?;
+                    writeln!(buf, "SYSLOG_IDENTIFIER=hyprman")?;
+                    journal_field(buf, "TARGET", record.target())?;
+                    journal_field(buf, "MESSAGE", &record.args().to_string())
+                })
+                .target(env_logger::Target::Pipe(Box::new(JournalWriter { socket })));
+        }
+    }
+    let logger = Logger {
+        output: builder.build(),
+    };
+    log::set_boxed_logger(Box::new(logger)).map_err(io::Error::other)?;
+    set_level(level);
+    Ok(())
+}
@@ -1,1082 +1,2024 @@
+mod activewindow;
+mod audit;
+mod binds;
+mod canonical;
+mod check;
+mod cli;
+mod completions;
+mod control;
+mod focus;
+mod fullscreen;
+mod groups;
+mod hooks;
+mod lock;
+mod logging;
+mod multicast;
+mod occupancy;
+mod output;
+mod overlays;
+mod overrides;
+mod rates;
+mod submap;
+mod systemd;
+mod tee;
+mod titles;
+mod trace;
+mod usage;
+mod watch;
+mod windowrules;
+mod windows;
+mod workspaces;
+
 use daemonize::Daemonize;
-use log::{error, info};
-use serde::{Deserialize, Serialize};
-use signal_hook::{consts::TERM_SIGNALS, iterator::Signals};
-use std::collections::HashMap;
-use std::io::Read;
+use hyprman::client::Connection;
+use hyprman::error::{HyprmanError, Result};
+use hyprman::event::{
+    Envelope, HYPRLAND_EVENT_SET, HyprlandEvent, PROTOCOL_VERSION, ParseMode, event_type,
+    parse_event_line,
+};
+use hyprman::hypr::{
+    create_socket, instance_signature, query_hyprland_version, query_state, set_socket_paths,
+    socket_paths, socket2_path,
+};
+use hyprman::paths;
+use hyprman::proto::encode_envelope_delimited;
+use hyprman::schema;
+use log::{error, info, warn};
+use serde::Deserialize;
+use signal_hook::{
+    consts::{SIGHUP, TERM_SIGNALS},
+    iterator::Signals,
+};
 use std::{
-    collections::HashSet,
-    env,
-    error::Error,
-    fs,
-    io::{BufRead, BufReader, BufWriter, Write},
-    os::unix::net::{UnixListener, UnixStream},
+    collections::{HashMap, HashSet, VecDeque},
+    env, fs,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    net::Shutdown,
+    os::unix::{
+        fs::{FileTypeExt, MetadataExt, PermissionsExt},
+        net::{UnixListener, UnixStream},
+    },
     process::Command,
-    sync::{mpsc, Arc, Mutex},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        mpsc,
+    },
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-/// === Hyprland Event Types ===
+// === Client Subscription Infrastructure ===
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(tag = "event", content = "data")]
-enum HyprlandEvent {
-    Workspace {
-        workspace_name: String,
-    },
-    WorkspaceV2 {
-        workspace_id: u8,
-        workspace_name: String,
-    },
-    FocusedMon {
-        monitor_name: String,
-        workspace_name: String,
-    },
-    FocusedMonV2 {
-        monitor_name: String,
-        workspace_id: u8,
-    },
-    ActiveWindow {
-        window_class: String,
-        window_title: String,
-    },
-    ActiveWindowV2 {
-        window_address: String,
-    },
-    Fullscreen {
-        status: u8,
-    },
-    MonitorRemoved {
-        monitor_name: String,
-    },
-    MonitorAdded {
-        monitor_name: String,
-    },
-    MonitorAddedV2 {
-        monitor_id: u8,
-        monitor_name: String,
-        monitor_description: String,
-    },
-    CreateWorkspace {
-        workspace_name: String,
-    },
-    CreateWorkspaceV2 {
-        workspace_id: u8,
-        workspace_name: String,
-    },
-    DestroyWorkspace {
-        workspace_name: String,
-    },
-    DestroyWorkspaceV2 {
-        workspace_id: u8,
-        workspace_name: String,
-    },
-    MoveWorkspace {
-        workspace_name: String,
-        monitor_name: String,
-    },
-    MoveWorkspaceV2 {
-        workspace_id: u8,
-        workspace_name: String,
-        monitor_name: String,
-    },
-    RenameWorkspace {
-        workspace_id: u8,
-        new_name: String,
-    },
-    ActiveSpecial {
-        workspace_name: String,
-        monitor_name: String,
-    },
-    ActiveLayout {
-        keyboard_name: String,
-        layout_name: String,
-    },
-    OpenWindow {
-        window_address: String,
-        workspace_name: String,
-        window_class: String,
-        window_title: String,
-    },
-    CloseWindow {
-        window_address: String,
-    },
-    MoveWindow {
-        window_address: String,
-        workspace_name: String,
-    },
-    MoveWindowV2 {
-        window_address: String,
-        workspace_id: u8,
-        workspace_name: String,
-    },
-    OpenLayer {
-        namespace: String,
-    },
-    CloseLayer {
-        namespace: String,
-    },
-    Submap {
-        submap_name: String,
-    },
-    ChangeFloatingMode {
-        window_address: String,
-        floating: u8,
-    },
-    Urgent {
-        window_address: String,
-    },
-    Screencast {
-        state: u8,
-        owner: u8,
-    },
-    WindowTitle {
-        window_address: String,
-    },
-    WindowTitleV2 {
-        window_address: String,
-        window_title: String,
-    },
-    ToggleGroup {
-        toggle_status: u8,
-        window_addresses: Vec<String>,
-    },
-    MoveIntoGroup {
-        window_address: String,
-    },
-    MoveOutOfGroup {
-        window_address: String,
-    },
-    IgnoreGroupLock {
-        value: u8,
-    },
-    LockGroups {
-        value: u8,
-    },
-    ConfigReloaded,
-    Pin {
-        window_address: String,
-        pin_state: u8,
-    },
+#[derive(Debug, Clone)]
+enum Subscription {
+    All,
+    Filtered(HashSet<String>),
+    /// Either of the above without the events `canonical` merges or
+    /// replaces.
+    Canonical(Box<Subscription>, Arc<canonical::Canonical>),
 }
 
-/// === Utility: Extract event type string for filtering ===
-
-fn event_type(event: &HyprlandEvent) -> &'static str {
-    match event {
-        HyprlandEvent::Workspace { .. } => "workspace",
-        HyprlandEvent::WorkspaceV2 { .. } => "workspacev2",
-        HyprlandEvent::FocusedMon { .. } => "focusedmon",
-        HyprlandEvent::FocusedMonV2 { .. } => "focusedmonv2",
-        HyprlandEvent::ActiveWindow { .. } => "activewindow",
-        HyprlandEvent::ActiveWindowV2 { .. } => "activewindowv2",
-        HyprlandEvent::Fullscreen { .. } => "fullscreen",
-        HyprlandEvent::MonitorRemoved { .. } => "monitorremoved",
-        HyprlandEvent::MonitorAdded { .. } => "monitoradded",
-        HyprlandEvent::MonitorAddedV2 { .. } => "monitoraddedv2",
-        HyprlandEvent::CreateWorkspace { .. } => "createworkspace",
-        HyprlandEvent::CreateWorkspaceV2 { .. } => "createworkspacev2",
-        HyprlandEvent::DestroyWorkspace { .. } => "destroyworkspace",
-        HyprlandEvent::DestroyWorkspaceV2 { .. } => "destroyworkspacev2",
-        HyprlandEvent::MoveWorkspace { .. } => "moveworkspace",
-        HyprlandEvent::MoveWorkspaceV2 { .. } => "moveworkspacev2",
-        HyprlandEvent::RenameWorkspace { .. } => "renameworkspace",
-        HyprlandEvent::ActiveSpecial { .. } => "activespecial",
-        HyprlandEvent::ActiveLayout { .. } => "activelayout",
-        HyprlandEvent::OpenWindow { .. } => "openwindow",
-        HyprlandEvent::CloseWindow { .. } => "closewindow",
-        HyprlandEvent::MoveWindow { .. } => "movewindow",
-        HyprlandEvent::MoveWindowV2 { .. } => "movewindowv2",
-        HyprlandEvent::OpenLayer { .. } => "openlayer",
-        HyprlandEvent::CloseLayer { .. } => "closelayer",
-        HyprlandEvent::Submap { .. } => "submap",
-        HyprlandEvent::ChangeFloatingMode { .. } => "changefloatingmode",
-        HyprlandEvent::Urgent { .. } => "urgent",
-        HyprlandEvent::Screencast { .. } => "screencast",
-        HyprlandEvent::WindowTitle { .. } => "windowtitle",
-        HyprlandEvent::WindowTitleV2 { .. } => "windowtitlev2",
-        HyprlandEvent::ToggleGroup { .. } => "togglegroup",
-        HyprlandEvent::MoveIntoGroup { .. } => "moveintogroup",
-        HyprlandEvent::MoveOutOfGroup { .. } => "moveoutofgroup",
-        HyprlandEvent::IgnoreGroupLock { .. } => "ignoregrouplock",
-        HyprlandEvent::LockGroups { .. } => "lockgroups",
-        HyprlandEvent::ConfigReloaded => "configreloaded",
-        HyprlandEvent::Pin { .. } => "pin",
-    }
-}
-
-/// === Hyprland Events parsing ===
-
-fn parse_event_line(line: &str) -> Result<HyprlandEvent, Box<dyn Error>> {
-    let line = line.trim();
-    let mut parts = line.split(">>");
-    let event_name = parts.next().ok_or("Missing event name")?;
-    let data = parts.next().unwrap_or("").trim();
-
-    match event_name {
-        "workspace" => Ok(HyprlandEvent::Workspace {
-            workspace_name: data.to_string(),
-        }),
-        "workspacev2" => {
-            let mut fields = data.split(',');
-            let workspace_id = fields.next().ok_or("Missing workspace_id")?.parse::<u8>()?;
-            let workspace_name = fields.next().ok_or("Missing workspace_name")?.to_string();
-            Ok(HyprlandEvent::WorkspaceV2 {
-                workspace_id,
-                workspace_name,
-            })
-        }
-        "focusedmon" => {
-            let mut fields = data.split(',');
-            let monitor_name = fields.next().ok_or("Missing monitor_name")?.to_string();
-            let workspace_name = fields.next().ok_or("Missing workspace_name")?.to_string();
-            Ok(HyprlandEvent::FocusedMon {
-                monitor_name,
-                workspace_name,
-            })
-        }
-        "focusedmonv2" => {
-            let mut fields = data.split(',');
-            let monitor_name = fields.next().ok_or("Missing monitor_name")?.to_string();
-            let workspace_id = fields.next().ok_or("Missing workspace_id")?.parse::<u8>()?;
-            Ok(HyprlandEvent::FocusedMonV2 {
-                monitor_name,
-                workspace_id,
-            })
-        }
-        "activewindow" => {
-            let mut fields = data.split(',');
-            let window_class = fields.next().ok_or("Missing window_class")?.to_string();
-            let window_title = fields.next().ok_or("Missing window_title")?.to_string();
-            Ok(HyprlandEvent::ActiveWindow {
-                window_class,
-                window_title,
-            })
-        }
-        "activewindowv2" => Ok(HyprlandEvent::ActiveWindowV2 {
-            window_address: data.to_string(),
-        }),
-        "fullscreen" => {
-            let status = data.parse::<u8>()?;
-            Ok(HyprlandEvent::Fullscreen { status })
-        }
-        "monitorremoved" => Ok(HyprlandEvent::MonitorRemoved {
-            monitor_name: data.to_string(),
-        }),
-        "monitoradded" => Ok(HyprlandEvent::MonitorAdded {
-            monitor_name: data.to_string(),
-        }),
-        "monitoraddedv2" => {
-            let mut fields = data.split(',');
-            let monitor_id = fields.next().ok_or("Missing monitor_id")?.parse::<u8>()?;
-            let monitor_name = fields.next().ok_or("Missing monitor_name")?.to_string();
-            let monitor_description = fields
-                .next()
-                .ok_or("Missing monitor_description")?
-                .to_string();
-            Ok(HyprlandEvent::MonitorAddedV2 {
-                monitor_id,
-                monitor_name,
-                monitor_description,
-            })
-        }
-        "createworkspace" => Ok(HyprlandEvent::CreateWorkspace {
-            workspace_name: data.to_string(),
-        }),
-        "createworkspacev2" => {
-            let mut fields = data.split(',');
-            let workspace_id = fields.next().ok_or("Missing workspace_id")?.parse::<u8>()?;
-            let workspace_name = fields.next().ok_or("Missing workspace_name")?.to_string();
-            Ok(HyprlandEvent::CreateWorkspaceV2 {
-                workspace_id,
-                workspace_name,
-            })
-        }
-        "destroyworkspace" => Ok(HyprlandEvent::DestroyWorkspace {
-            workspace_name: data.to_string(),
-        }),
-        "destroyworkspacev2" => {
-            let mut fields = data.split(',');
-            let workspace_id = fields.next().ok_or("Missing workspace_id")?.parse::<u8>()?;
-            let workspace_name = fields.next().ok_or("Missing workspace_name")?.to_string();
-            Ok(HyprlandEvent::DestroyWorkspaceV2 {
-                workspace_id,
-                workspace_name,
-            })
-        }
-        "moveworkspace" => {
-            let mut fields = data.split(',');
-            let workspace_name = fields.next().ok_or("Missing workspace_name")?.to_string();
-            let monitor_name = fields.next().ok_or("Missing monitor_name")?.to_string();
-            Ok(HyprlandEvent::MoveWorkspace {
-                workspace_name,
-                monitor_name,
-            })
-        }
-        "moveworkspacev2" => {
-            let mut fields = data.split(',');
-            let workspace_id = fields.next().ok_or("Missing workspace_id")?.parse::<u8>()?;
-            let workspace_name = fields.next().ok_or("Missing workspace_name")?.to_string();
-            let monitor_name = fields.next().ok_or("Missing monitor_name")?.to_string();
-            Ok(HyprlandEvent::MoveWorkspaceV2 {
-                workspace_id,
-                workspace_name,
-                monitor_name,
-            })
-        }
-        "renameworkspace" => {
-            let mut fields = data.split(',');
-            let workspace_id = fields.next().ok_or("Missing workspace_id")?.parse::<u8>()?;
-            let new_name = fields.next().ok_or("Missing new_name")?.to_string();
-            Ok(HyprlandEvent::RenameWorkspace {
-                workspace_id,
-                new_name,
-            })
-        }
-        "activespecial" => {
-            let mut fields = data.split(',');
-            let workspace_name = fields.next().ok_or("Missing workspace_name")?.to_string();
-            let monitor_name = fields.next().ok_or("Missing monitor_name")?.to_string();
-            Ok(HyprlandEvent::ActiveSpecial {
-                workspace_name,
-                monitor_name,
-            })
-        }
-        "activelayout" => {
-            let mut fields = data.split(',');
-            let keyboard_name = fields.next().ok_or("Missing keyboard_name")?.to_string();
-            let layout_name = fields.next().ok_or("Missing layout_name")?.to_string();
-            Ok(HyprlandEvent::ActiveLayout {
-                keyboard_name,
-                layout_name,
-            })
-        }
-        "openwindow" => {
-            let mut fields = data.split(',');
-            let window_address = fields.next().ok_or("Missing window_address")?.to_string();
-            let workspace_name = fields.next().ok_or("Missing workspace_name")?.to_string();
-            let window_class = fields.next().ok_or("Missing window_class")?.to_string();
-            let window_title = fields.next().ok_or("Missing window_title")?.to_string();
-            Ok(HyprlandEvent::OpenWindow {
-                window_address,
-                workspace_name,
-                window_class,
-                window_title,
-            })
-        }
-        "closewindow" => Ok(HyprlandEvent::CloseWindow {
-            window_address: data.to_string(),
-        }),
-        "movewindow" => {
-            let mut fields = data.split(',');
-            let window_address = fields.next().ok_or("Missing window_address")?.to_string();
-            let workspace_name = fields.next().ok_or("Missing workspace_name")?.to_string();
-            Ok(HyprlandEvent::MoveWindow {
-                window_address,
-                workspace_name,
-            })
-        }
-        "movewindowv2" => {
-            let mut fields = data.split(',');
-            let window_address = fields.next().ok_or("Missing window_address")?.to_string();
-            let workspace_id = fields.next().ok_or("Missing workspace_id")?.parse::<u8>()?;
-            let workspace_name = fields.next().ok_or("Missing workspace_name")?.to_string();
-            Ok(HyprlandEvent::MoveWindowV2 {
-                window_address,
-                workspace_id,
-                workspace_name,
-            })
-        }
-        "openlayer" => Ok(HyprlandEvent::OpenLayer {
-            namespace: data.to_string(),
-        }),
-        "closelayer" => Ok(HyprlandEvent::CloseLayer {
-            namespace: data.to_string(),
-        }),
-        "submap" => Ok(HyprlandEvent::Submap {
-            submap_name: data.to_string(),
-        }),
-        "changefloatingmode" => {
-            let mut fields = data.split(',');
-            let window_address = fields.next().ok_or("Missing window_address")?.to_string();
-            let floating = fields.next().ok_or("Missing floating")?.parse::<u8>()?;
-            Ok(HyprlandEvent::ChangeFloatingMode {
-                window_address,
-                floating,
-            })
-        }
-        "urgent" => Ok(HyprlandEvent::Urgent {
-            window_address: data.to_string(),
-        }),
-        "screencast" => {
-            let mut fields = data.split(',');
-            let state = fields.next().ok_or("Missing state")?.parse::<u8>()?;
-            let owner = fields.next().ok_or("Missing owner")?.parse::<u8>()?;
-            Ok(HyprlandEvent::Screencast { state, owner })
-        }
-        "windowtitle" => Ok(HyprlandEvent::WindowTitle {
-            window_address: data.to_string(),
-        }),
-        "windowtitlev2" => {
-            let mut fields = data.split(',');
-            let window_address = fields.next().ok_or("Missing window_address")?.to_string();
-            let window_title = fields.next().ok_or("Missing window_title")?.to_string();
-            Ok(HyprlandEvent::WindowTitleV2 {
-                window_address,
-                window_title,
-            })
-        }
-        "togglegroup" => {
-            let mut fields = data.split(',');
-            let toggle_status = fields
-                .next()
-                .ok_or("Missing toggle_status")?
-                .parse::<u8>()?;
-            let window_addresses: Vec<String> = fields.map(|s| s.to_string()).collect();
-            Ok(HyprlandEvent::ToggleGroup {
-                toggle_status,
-                window_addresses,
-            })
-        }
-        "moveintogroup" => Ok(HyprlandEvent::MoveIntoGroup {
-            window_address: data.to_string(),
-        }),
-        "moveoutofgroup" => Ok(HyprlandEvent::MoveOutOfGroup {
-            window_address: data.to_string(),
-        }),
-        "ignoregrouplock" => {
-            let value = data.parse::<u8>()?;
-            Ok(HyprlandEvent::IgnoreGroupLock { value })
-        }
-        "lockgroups" => {
-            let value = data.parse::<u8>()?;
-            Ok(HyprlandEvent::LockGroups { value })
+impl Subscription {
+    /// The subscription as it would be written in the handshake.
+    fn describe(&self) -> String {
+        match self {
+            Subscription::All => "all".to_string(),
+            Subscription::Filtered(filters) => {
+                let mut filters: Vec<&str> = filters.iter().map(String::as_str).collect();
+                filters.sort_unstable();
+                filters.join(",")
+            }
+            Subscription::Canonical(inner, _) => format!("{},canonical", inner.describe()),
         }
-        "configreloaded" => Ok(HyprlandEvent::ConfigReloaded),
-        "pin" => {
-            let mut fields = data.split(',');
-            let window_address = fields.next().ok_or("Missing window_address")?.to_string();
-            let pin_state = fields.next().ok_or("Missing pin_state")?.parse::<u8>()?;
-            Ok(HyprlandEvent::Pin {
-                window_address,
-                pin_state,
-            })
+    }
+
+    fn wants(&self, event: &HyprlandEvent) -> bool {
+        match self {
+            Subscription::All => !is_opt_in(event),
+            Subscription::Filtered(filters) => filters.contains(event_type(event)),
+            Subscription::Canonical(inner, canonical) => {
+                if canonical.supersedes(event) {
+                    return false;
+                }
+                match **inner {
+                    Subscription::All => !is_opt_in(event) || is_canonical(event),
+                    _ => inner.wants(event),
+                }
+            }
         }
-        _ => Err(format!("Unknown event type: {}", event_name).into()),
-    }
-}
-
-/// === Structs for Interaction with Socket1
-#[derive(Clone, Serialize, Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-struct Workspace {
-    id: u8,
-    name: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    active: Option<bool>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    monitor: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    monitor_id: Option<u32>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    windows: Option<u32>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    has_fullscreen: Option<bool>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    last_window: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    last_window_title: Option<String>,
-}
-
-#[derive(Clone, Serialize, Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-struct Client {
-    address: String,
-    mapped: bool,
-    hidden: bool,
-    at: (i32, i32),
-    size: (i32, i32),
-    workspace: Workspace,
-    floating: bool,
-    pseudo: bool,
-    monitor: u8,
-    class: String,
-    title: String,
-    initial_class: String,
-    initial_title: String,
-    pid: u32,
-    xwayland: bool,
-    pinned: bool,
-    fullscreen: i32,
-    fullscreen_client: i32,
-    grouped: Vec<String>,
-    tags: Vec<String>,
-    swallowing: String,
-    #[serde(rename = "focusHistoryID")]
-    focus_history_id: i32,
-    inhibiting_idle: bool,
-}
-
-/// === Client Subscription Infrastructure ===
+    }
+}
 
-#[derive(Debug, Clone)]
-enum Subscription {
-    All,
-    Filtered(HashSet<String>),
+/// What the writer side of a client connection acts on.
+enum ClientMessage {
+    Event(Envelope),
+    /// Sent by the client: hold back events until `Resume`.
+    Pause,
+    Resume,
+    /// Sent by the client in ack mode: everything up to this seq arrived.
+    Ack(u64),
+    /// Sent by the client: forward `dispatch <args>` to Hyprland.
+    Dispatch(String),
+    /// The client closed its end of the socket.
+    Hangup,
+    /// The daemon is exiting: write `DaemonShutdown` after what is queued,
+    /// report back and stop.
+    Shutdown(mpsc::Sender<()>),
+}
+
+/// Sending half of a client's channel, counting the events its writer has
+/// not picked up yet.
+struct ClientQueue {
+    sender: mpsc::Sender<ClientMessage>,
+    depth: Arc<AtomicUsize>,
+}
+
+impl ClientQueue {
+    fn push(&self, envelope: Envelope) -> bool {
+        self.depth.fetch_add(1, Ordering::SeqCst);
+        self.sender.send(ClientMessage::Event(envelope)).is_ok()
+    }
 }
 
 struct ClientHandle {
-    sender: mpsc::Sender<HyprlandEvent>,
+    queue: ClientQueue,
     subscription: Subscription,
+    /// Subscribed with `enrich`: window and workspace events carry what
+    /// they are about.
+    enrich: bool,
+    /// Shut down to disconnect a client whose queue overflowed.
+    socket: UnixStream,
 }
 
-/// === Configuration Loading ===
+/// Events held back while a client is paused.
+#[derive(Default)]
+struct PausedEvents {
+    events: Vec<Envelope>,
+    missed: u64,
+}
+
+/// Delivery state of a client that acknowledges events with `ack <seq>`.
+struct AckWindow {
+    /// Maximum number of unacknowledged events written to the client.
+    window: usize,
+    in_flight: VecDeque<Envelope>,
+    /// Events waiting for room in the window.
+    queued: VecDeque<Envelope>,
+}
+
+/// Events kept for a subscription registered with `durable=<name>` while
+/// its client is disconnected.
+struct DurableSubscription {
+    subscription: Subscription,
+    connected: bool,
+    events: VecDeque<Envelope>,
+    /// Events lost to the overflow policy since the client disconnected.
+    dropped: u64,
+    /// Sequence number at the time the client disconnected.
+    since: u64,
+}
+
+/// What happens when a durable subscription's buffer is full.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum OverflowPolicy {
+    #[default]
+    DropOldest,
+    DropNewest,
+    /// Stop buffering and send `ResyncRequired` on reconnect.
+    Resync,
+}
+
+impl DurableSubscription {
+    fn push(&mut self, envelope: Envelope, limit: usize, policy: OverflowPolicy) {
+        if self.dropped == 0 && self.events.len() < limit {
+            self.events.push_back(envelope);
+            return;
+        }
+        match policy {
+            OverflowPolicy::DropOldest => {
+                self.events.pop_front();
+                self.events.push_back(envelope);
+                self.dropped += 1;
+            }
+            OverflowPolicy::DropNewest => self.dropped += 1,
+            OverflowPolicy::Resync => {
+                self.dropped += self.events.len() as u64 + 1;
+                self.events.clear();
+            }
+        }
+    }
+}
+
+/// Encoding of the messages written to a client, chosen with `format=`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    /// One JSON object per line, with keys cased as chosen with `keys=`.
+    Json(output::KeyCase),
+    /// Length-delimited `Envelope` messages from proto/hyprman.proto.
+    Protobuf,
+}
+
+/// Writes events to one client, holding them back while it is paused or
+/// while its ack window is full.
+struct ClientWriter<W: Write> {
+    writer: W,
+    format: Format,
+    paused: Option<PausedEvents>,
+    ack: Option<AckWindow>,
+    /// Who is connected, checked before forwarding dispatches.
+    peer: Option<libc::ucred>,
+    /// Connected through the sandbox socket or from inside a Flatpak; such
+    /// clients only receive events.
+    sandboxed: bool,
+    /// Keeps the window and workspace of events, which are dropped
+    /// otherwise.
+    enrich: bool,
+}
+
+/// Features listed in the metadata header for clients to detect.
+const CAPABILITIES: &[&str] = &[
+    "format=json",
+    "format=protobuf",
+    "keys",
+    "resume_from",
+    "ack",
+    "durable",
+    "pause",
+    "snapshot",
+    "dispatch",
+    "timestamp",
+    "canonical",
+    "enrich",
+];
+
+/// State shared between the daemon threads.
+struct Daemon {
+    subscriptions: Mutex<Vec<ClientHandle>>,
+    /// Sequence number of the last dispatched event. Only advanced while
+    /// `subscriptions` is locked, so new clients see a consistent value.
+    seq: AtomicU64,
+    /// Both change when Hyprland restarts.
+    hyprland_version: Mutex<Option<String>>,
+    instance_signature: Mutex<String>,
+    /// $XDG_RUNTIME_DIR/hyprman/<instance>, holding the sockets.
+    runtime_dir: String,
+    started: Instant,
+    /// Whether the event thread is reading Hyprland's socket2.
+    hyprland_connected: AtomicBool,
+    /// Record of the processes spawned on behalf of events.
+    audit: Arc<audit::AuditLog>,
+    /// Replaced as a whole when the config is reloaded.
+    hooks: Mutex<hooks::Hooks>,
+    /// Rules are re-read on reload; what they applied is kept.
+    window_rules: Mutex<windowrules::WindowRules>,
+    /// Default for clients that do not choose with `keys=`.
+    key_case: output::KeyCase,
+    /// Set by `--rules-dry-run`: hooks and window rules only log what they
+    /// would run.
+    dry_run: bool,
+    /// Binds registered in Hyprland, removed again on reload and shutdown.
+    binds: Mutex<Vec<binds::Bind>>,
+    config_path: String,
+    /// `[profile.NAME]` section applied on top of the config file.
+    config_profile: Option<String>,
+    /// Do-not-disturb: hooks are skipped while set, events still stream.
+    dnd: AtomicBool,
+    suppress_legacy_events: AtomicBool,
+    parse_mode: ParseMode,
+    /// Active hook profile, switched with `hyprman profile <name>`.
+    profile: Mutex<Option<String>>,
+    /// Who may run which control commands; replaced on reload.
+    control_permissions: Mutex<Vec<control::ControlPermission>>,
+    /// Events buffered per paused client before falling back to a snapshot.
+    pause_buffer_limit: usize,
+    /// Events queued behind a full ack window before the client is dropped.
+    ack_queue_limit: usize,
+    /// Events waiting to be written to a client before it is dropped.
+    client_queue_limit: usize,
+    /// Capacity of the buffers the event and client sockets are read through.
+    read_buffer_size: usize,
+    /// Named subscriptions that keep collecting events while disconnected.
+    durable: Mutex<HashMap<String, DurableSubscription>>,
+    durable_buffer_size: usize,
+    durable_overflow: OverflowPolicy,
+    multicast: Option<multicast::MulticastSink>,
+    /// The most recent events, replayed to clients resuming with `resume_from`.
+    history: Mutex<VecDeque<Envelope>>,
+    history_size: usize,
+    /// Recent events per type, reported by `status`.
+    rates: Mutex<rates::EventRates>,
+    /// Recent titles per window, reported by `get title-history`.
+    titles: Mutex<titles::TitleHistory>,
+    /// Recently focused windows, reported by `get focus-history`.
+    focus: Mutex<focus::FocusHistory>,
+    focus_restore: Mutex<focus::FocusRestore>,
+    effective_focus: Mutex<focus::EffectiveFocus>,
+    /// Window groups, reported by `get groups`.
+    groups: Mutex<groups::Groups>,
+    /// Fullscreen window per workspace, reported by `get fullscreen`.
+    fullscreen: Mutex<fullscreen::Fullscreen>,
+    overlays: Mutex<overlays::Overlays>,
+    canonical: Arc<canonical::Canonical>,
+    /// Windows attached to the events of `enrich` clients.
+    windows: Mutex<windows::WindowCache>,
+    /// Workspaces attached to the events of `enrich` clients.
+    occupancy: Mutex<occupancy::Occupancy>,
+    /// Workspace of every window, for `WorkspaceEmpty` and
+    /// `WorkspaceOccupied`.
+    workspace_windows: Mutex<occupancy::WorkspaceWindows>,
+    submap_timeouts: Mutex<submap::SubmapTimeouts>,
+    /// Clients of the raw socket, fed socket2 unmodified.
+    raw_tee: tee::RawTee,
+}
+
+// === Configuration Loading ===
 
 #[derive(Debug, Deserialize)]
 struct Config {
     // Socket path where clients connect to receive events.
-    // If relative, it will be interpreted relative to $XDG_RUNTIME_DIR/hyprman/
+    // If relative, it will be interpreted relative to
+    // $XDG_RUNTIME_DIR/hyprman/$HYPRLAND_INSTANCE_SIGNATURE/
+    // Client modes use $HYPRMAN_SOCKET instead when it is set.
+    #[serde(default = "default_client_socket_path")]
     client_socket_path: String,
+    // Client modes start the daemon if it is not running, instead of
+    // failing to connect.
+    #[serde(default)]
+    auto_spawn_daemon: bool,
+    // Permissions and group of the client, sandbox and raw sockets, e.g.
+    // 0o660 and "video" for a bar running as another user in that group.
+    // Without them the sockets get the daemon's umask and primary group.
+    #[serde(default)]
+    socket_mode: Option<u32>,
+    #[serde(default)]
+    socket_group: Option<String>,
+    // File mode creation mask of the daemon, also covering the runtime
+    // directory and the log file.
+    #[serde(default = "default_umask")]
+    umask: u32,
+    // Second client socket meant to be exposed to sandboxed apps, e.g.
+    // "hyprman.sock". If relative, it is interpreted relative to
+    // $XDG_RUNTIME_DIR itself, so a Flatpak can be given just this file with
+    // `--filesystem=xdg-run/hyprman.sock`. Clients on it may not dispatch.
+    #[serde(default)]
+    sandbox_socket_path: Option<String>,
+    // Socket passing Hyprland's socket2 stream through unmodified, for
+    // scripts written against its `event>>data` lines. Relative like the
+    // client socket.
+    #[serde(default)]
+    raw_socket_path: Option<String>,
+    // Hyprland's request and event sockets, used as given instead of being
+    // looked up under $XDG_RUNTIME_DIR/hypr/$HYPRLAND_INSTANCE_SIGNATURE.
+    // Overridden by --socket1 and --socket2.
+    #[serde(default)]
+    hyprland_socket1_path: Option<String>,
+    #[serde(default)]
+    hyprland_socket2_path: Option<String>,
+    // Socket for admin commands (dnd, profile, reload), relative like the
+    // client socket, created with `control_socket_mode` permissions.
+    #[serde(default = "control::default_control_socket_path")]
+    control_socket_path: String,
+    #[serde(default = "control::default_control_socket_mode")]
+    control_socket_mode: u32,
+    // Which users and groups may run which control commands. Without any
+    // entry, everyone able to open the control socket may run all of them.
+    #[serde(default)]
+    control_permissions: Vec<control::ControlPermission>,
+    // Events kept for a paused client; past this it gets a snapshot on resume.
+    #[serde(default = "default_pause_buffer_limit")]
+    pause_buffer_limit: usize,
+    // Events queued for a client in ack mode that stopped acknowledging;
+    // past this it is disconnected and has to reconnect with `resume_from`.
+    #[serde(default = "default_ack_queue_limit")]
+    ack_queue_limit: usize,
+    // Events waiting to be written to any client that is not keeping up;
+    // past this it is disconnected.
+    #[serde(default = "default_client_queue_limit")]
+    client_queue_limit: usize,
+    // Bytes read at once from Hyprland's event socket and from clients.
+    #[serde(default = "default_read_buffer_size")]
+    read_buffer_size: usize,
+    // Leave out events such as `workspace` and `focusedmon` that Hyprland
+    // follows with a newer one (`workspacev2`, `focusedmonv2`), for every
+    // client. Hooks still see them.
+    #[serde(default)]
+    suppress_legacy_events: bool,
+    // Socket2 lines missing trailing fields: "lenient" fills in defaults
+    // and logs a warning, "strict" turns them into ParseError events.
+    #[serde(default)]
+    parse_mode: ParseMode,
+    // Events buffered for a disconnected `durable=<name>` subscription, and
+    // what to do once that is exceeded: "drop_oldest", "drop_newest" or
+    // "resync".
+    #[serde(default = "default_durable_buffer_size")]
+    durable_buffer_size: usize,
+    #[serde(default)]
+    durable_overflow: OverflowPolicy,
+    // Optional sink broadcasting events as UDP datagrams.
+    #[serde(default)]
+    multicast: Option<multicast::MulticastConfig>,
+    // Reload the config whenever the file changes, as `hyprman reload` does.
+    #[serde(default = "default_watch_config")]
+    watch_config: bool,
+    // JSON lines file recording every command hooks run, with the event that
+    // triggered it. Relative paths are resolved like `client_socket_path`.
+    #[serde(default)]
+    audit_log: Option<String>,
+    // Niceness of the daemon (-20 to 19); negative values need privileges.
+    #[serde(default)]
+    nice: Option<i32>,
+    // CPU the thread reading and dispatching Hyprland events is pinned to.
+    #[serde(default)]
+    dispatch_cpu: Option<usize>,
+    // Events kept for clients reconnecting with `resume_from=<seq>`.
+    #[serde(default = "default_history_size")]
+    history_size: usize,
+    // Titles remembered per window for `hyprman get title-history`.
+    #[serde(default = "titles::default_title_history_size")]
+    title_history_size: usize,
+    // Focus changes remembered for `hyprman get focus-history`.
+    #[serde(default = "focus::default_focus_history_size")]
+    focus_history_size: usize,
+    // Focus the window last focused on a workspace when switching to it.
+    #[serde(default)]
+    restore_focus: bool,
+    // Layer namespaces reported as OverlayShown/OverlayHidden events, e.g.
+    // ["rofi", "wofi", "notifications"].
+    #[serde(default)]
+    overlay_namespaces: Vec<String>,
+    // Seconds without events after which a submap is reset, e.g.
+    // { resize = 5 }.
+    #[serde(default)]
+    submap_timeouts: HashMap<String, u64>,
+    // Casing of JSON keys sent to clients and printed in client mode:
+    // "mixed" (as declared), "snake_case" or "camelCase".
+    #[serde(default)]
+    key_case: output::KeyCase,
+    // Markup escaping applied to titles and classes in client-mode output.
+    #[serde(default)]
+    escape: output::Escape,
+    #[serde(default)]
+    activewindow: activewindow::ActiveWindowConfig,
+    #[serde(default)]
+    workspaces: workspaces::WorkspacesConfig,
+    // Commands and sounds triggered by events.
+    #[serde(default)]
+    hooks: Vec<hooks::Hook>,
+    // Window properties set while windows match, e.g. dimming unfocused ones.
+    #[serde(default)]
+    window_rules: Vec<windowrules::WindowRule>,
+    // Key binds registered in Hyprland that run `hyprman trigger <name>`.
+    #[serde(default)]
+    binds: Vec<binds::Bind>,
+    // Player used by sound hooks, e.g. "paplay" or "mpv --no-video".
+    #[serde(default = "hooks::default_sound_player")]
+    sound_player: String,
+    // Hook profile active when the daemon starts. Spelled `hook_profile` in
+    // files that also hold [profile.NAME] sections, which TOML would
+    // otherwise reject; see `overrides::apply_profile`.
+    #[serde(default, alias = "hook_profile")]
+    profile: Option<String>,
+    // What is logged, as an env_logger filter such as "debug" or
+    // "hyprman::hooks=debug,info"; RUST_LOG takes precedence. Defaults to
+    // "info" for the daemon and "error" for the other modes.
+    #[serde(default)]
+    log_level: Option<String>,
+    // Where the daemon logs: "stderr", "file" or "journald". Defaults to
+    // "file" when daemonized and "stderr" with --foreground.
+    #[serde(default)]
+    log_target: Option<logging::LogTarget>,
+    // Log file used by the "file" target.
+    #[serde(default)]
+    logging: logging::LoggingConfig,
+    // Name of the [profile.NAME] section applied with --profile, kept to
+    // apply it again on reload.
+    #[serde(skip)]
+    config_profile: Option<String>,
+}
+
+fn default_umask() -> u32 {
+    0o022
 }
 
-fn load_config(path: &str) -> Config {
-    let content = fs::read_to_string(path).expect("Failed to read config file");
-    toml::from_str(&content).expect("Failed to parse config file")
+fn default_client_socket_path() -> String {
+    "hyprman.sock".to_string()
 }
 
-fn get_hypr_rundir_path() -> String {
-    let xdg_runtime_dir = env::var("XDG_RUNTIME_DIR")
-        .unwrap_or_else(|_| panic!("Environment variable XDG_RUNTIME_DIR is not set"));
-    let hypr_instance_signature = env::var("HYPRLAND_INSTANCE_SIGNATURE")
-        .unwrap_or_else(|_| panic!("Environment variable HYPRLAND_INSTANCE_SIGNATURE is not set"));
-    format!("{}/hypr/{}", xdg_runtime_dir, hypr_instance_signature)
+fn default_pause_buffer_limit() -> usize {
+    256
 }
 
-/// === Daemon Mode Functions ===
+fn default_ack_queue_limit() -> usize {
+    4096
+}
 
-fn client_handler(stream: UnixStream, subscriptions: Arc<Mutex<Vec<ClientHandle>>>) {
-    let mut reader = BufReader::new(stream.try_clone().expect("Failed to clone stream"));
-    let mut writer = BufWriter::new(stream);
+fn default_client_queue_limit() -> usize {
+    65536
+}
+
+fn default_read_buffer_size() -> usize {
+    8192
+}
+
+fn default_durable_buffer_size() -> usize {
+    1024
+}
+
+fn default_history_size() -> usize {
+    1024
+}
+
+fn default_watch_config() -> bool {
+    true
+}
+
+/// $XDG_CONFIG_HOME/hyprman/config.toml, with XDG_CONFIG_HOME defaulting
+/// to ~/.config.
+fn config_path() -> Result<String> {
+    Ok(format!("{}/hyprman/config.toml", paths::config_home()?))
+}
+
+impl Default for Config {
+    /// Every key has a default, so an empty file is the default config.
+    fn default() -> Config {
+        toml::from_str("").expect("Defaults form a valid config")
+    }
+}
+
+/// Reads the config file, falling back to the defaults if there is none,
+/// with the `[profile.NAME]` section and then the keys set by `HYPRMAN_*`
+/// variables applied on top.
+fn try_load_config(path: &str, profile: Option<&str>) -> Result<Config> {
+    let error = |reason: String| HyprmanError::Config {
+        path: path.to_string(),
+        reason,
+    };
+    let mut table: toml::Table = match fs::read_to_string(path) {
+        Ok(content) => toml::from_str(&content).map_err(|e| error(e.to_string()))?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => toml::Table::new(),
+        Err(e) => return Err(error(e.to_string())),
+    };
+    overrides::apply_profile(&mut table, profile).map_err(error)?;
+    overrides::apply(&mut table);
+    let mut config: Config = toml::Value::Table(table)
+        .try_into()
+        .map_err(|e: toml::de::Error| error(e.to_string()))?;
+    config.config_profile = profile.map(str::to_string);
+    Ok(config)
+}
+
+// === Daemon Mode Functions ===
+
+fn client_handler(stream: UnixStream, daemon: Arc<Daemon>, sandboxed: bool) {
+    let mut reader = BufReader::with_capacity(
+        daemon.read_buffer_size,
+        stream.try_clone().expect("Failed to clone stream"),
+    );
+    let socket = stream.try_clone().expect("Failed to clone stream");
+    let writer = BufWriter::new(stream);
     // Read a line from the client to get subscription preferences.
     let mut subscription_line = String::new();
     if let Err(e) = reader.read_line(&mut subscription_line) {
         error!("Failed to read subscription from client: {}", e);
         return;
     }
-    let subscription_line = subscription_line.trim();
-    let subscription = if subscription_line.is_empty() || subscription_line.to_lowercase() == "all"
-    {
+    // Event names, with options such as `resume_from=<seq>` mixed in.
+    let mut filters = HashSet::new();
+    let mut resume_from = None;
+    let mut ack_window = None;
+    let mut durable = None;
+    let mut format = Format::Json(daemon.key_case);
+    let mut keys = daemon.key_case;
+    let mut canonical = false;
+    let mut enrich = false;
+    for token in subscription_line.split(',').map(str::trim) {
+        match token.split_once('=') {
+            Some(("resume_from", seq)) => match seq.parse::<u64>() {
+                Ok(seq) => resume_from = Some(seq),
+                Err(e) => error!("Invalid resume_from '{}': {}", seq, e),
+            },
+            Some(("ack", window)) => match window.parse::<usize>() {
+                Ok(window) if window > 0 => ack_window = Some(window),
+                _ => error!("Invalid ack window '{}'", window),
+            },
+            Some(("durable", name)) if !name.is_empty() => durable = Some(name.to_string()),
+            Some(("format", "json")) => format = Format::Json(keys),
+            Some(("format", "protobuf")) => format = Format::Protobuf,
+            Some(("keys", name)) => match output::KeyCase::parse(name) {
+                Some(key_case) => keys = key_case,
+                None => error!("Invalid key case '{}'", name),
+            },
+            Some((option, _)) => error!("Unknown subscription option: {}", option),
+            None if token.is_empty() => {}
+            None if token.eq_ignore_ascii_case("canonical") => canonical = true,
+            None if token.eq_ignore_ascii_case("enrich") => enrich = true,
+            None => {
+                filters.insert(token.to_lowercase());
+            }
+        }
+    }
+    if let Format::Json(key_case) = &mut format {
+        *key_case = keys;
+    }
+    // `all` next to other filters still means everything.
+    let mut subscription = if filters.is_empty() || filters.contains("all") {
         Subscription::All
+    } else if canonical {
+        Subscription::Filtered(
+            filters
+                .iter()
+                .flat_map(|filter| canonical::filter_names(filter))
+                .collect(),
+        )
     } else {
-        let filters: HashSet<String> = subscription_line
-            .split(',')
-            .map(|s| s.trim().to_lowercase())
-            .collect();
         Subscription::Filtered(filters)
     };
+    if canonical {
+        subscription = Subscription::Canonical(Box::new(subscription), daemon.canonical.clone());
+    }
     info!("Client subscribed to: {:?}", subscription);
 
     // Create a channel for sending events to this client.
-    let (tx, rx) = mpsc::channel::<HyprlandEvent>();
+    let (tx, rx) = mpsc::channel::<ClientMessage>();
 
-    {
-        let mut subs = subscriptions.lock().unwrap();
+    // Lines sent after the subscription pause and resume delivery or
+    // acknowledge events.
+    let queue = ClientQueue {
+        sender: tx,
+        depth: Arc::new(AtomicUsize::new(0)),
+    };
+    let depth = queue.depth.clone();
+    let commands = queue.sender.clone();
+    thread::spawn(move || {
+        for line in reader.lines() {
+            let message = match line.as_deref().map(str::trim) {
+                Ok("pause") => ClientMessage::Pause,
+                Ok("resume") => ClientMessage::Resume,
+                Ok(other) if let Some(Ok(seq)) = other.strip_prefix("ack ").map(str::parse) => {
+                    ClientMessage::Ack(seq)
+                }
+                Ok(other) if let Some(args) = other.strip_prefix("dispatch ") => {
+                    ClientMessage::Dispatch(args.trim().to_string())
+                }
+                Ok(other) => {
+                    error!("Unknown client command: {}", other);
+                    continue;
+                }
+                Err(_) => break,
+            };
+            if commands.send(message).is_err() {
+                return;
+            }
+        }
+        let _ = commands.send(ClientMessage::Hangup);
+    });
+
+    let seq = {
+        let mut subs = daemon.subscriptions.lock().unwrap();
+        if let Some(resume_from) = resume_from {
+            replay(&daemon, resume_from, &subscription, &queue);
+        }
+        if let Some(name) = &durable
+            && !attach_durable(&daemon, name, &subscription, &queue)
+        {
+            durable = None;
+        }
         subs.push(ClientHandle {
-            sender: tx,
+            queue,
             subscription,
+            enrich,
+            socket,
         });
+        daemon.seq.load(Ordering::SeqCst)
+    };
+
+    // Tell the client who it is talking to and where its stream starts.
+    let metadata = HyprlandEvent::Metadata {
+        hyprland_version: daemon.hyprland_version.lock().unwrap().clone(),
+        instance_signature: daemon.instance_signature.lock().unwrap().clone(),
+        hyprman_version: env!("CARGO_PKG_VERSION").to_string(),
+        protocol_version: PROTOCOL_VERSION,
+        seq,
+        capabilities: CAPABILITIES.iter().map(|c| c.to_string()).collect(),
+    };
+    let peer = control::peer_credentials(writer.get_ref())
+        .inspect_err(|e| error!("Failed to read client peer credentials: {}", e))
+        .ok();
+    let mut client = ClientWriter {
+        sandboxed: sandboxed || peer.is_some_and(|peer| control::is_sandboxed(&peer)),
+        peer,
+        writer,
+        format,
+        enrich,
+        paused: None,
+        ack: ack_window.map(|window| AckWindow {
+            window,
+            in_flight: VecDeque::new(),
+            queued: VecDeque::new(),
+        }),
+    };
+    match write_bare(&mut client.writer, format, seq, metadata) {
+        Ok(()) => serve(&daemon, &mut client, &rx, &depth),
+        Err(e) => error!("Failed to send metadata to client: {}", e),
+    }
+
+    let undelivered = client.into_undelivered();
+    if let Some(name) = durable {
+        detach_durable(&daemon, &name, undelivered, &rx);
+    } else if !undelivered.is_empty() && ack_window.is_some() {
+        info!(
+            "Client left with {} unacknowledged events",
+            undelivered.len()
+        );
     }
+}
 
-    // Loop and write events to the client.
+/// Loops and writes events to the client until it hangs up.
+fn serve<W: Write>(
+    daemon: &Daemon,
+    client: &mut ClientWriter<W>,
+    rx: &mpsc::Receiver<ClientMessage>,
+    depth: &AtomicUsize,
+) {
     loop {
-        match rx.recv() {
-            Ok(event) => {
-                let json = serde_json::to_string(&event).unwrap();
-                if let Err(e) = writeln!(writer, "{}", json) {
-                    error!("Failed to write to client: {}", e);
-                    break;
-                }
-                if let Err(e) = writer.flush() {
-                    error!("Failed to flush writer: {}", e);
-                    break;
+        let result = match rx.recv() {
+            Ok(ClientMessage::Event(envelope)) => {
+                depth.fetch_sub(1, Ordering::SeqCst);
+                client.event(daemon, envelope)
+            }
+            Ok(ClientMessage::Pause) => {
+                info!("Client paused");
+                client.paused.get_or_insert_default();
+                Ok(())
+            }
+            Ok(ClientMessage::Resume) => client.resume(daemon),
+            Ok(ClientMessage::Ack(seq)) => client.acknowledge(seq),
+            Ok(ClientMessage::Dispatch(command)) => client.dispatch(daemon, command),
+            Ok(ClientMessage::Hangup) => break,
+            Ok(ClientMessage::Shutdown(done)) => {
+                let seq = daemon.seq.load(Ordering::SeqCst);
+                if let Err(e) = write_bare(
+                    &mut client.writer,
+                    client.format,
+                    seq,
+                    HyprlandEvent::DaemonShutdown,
+                ) {
+                    error!("Failed to send shutdown to client: {}", e);
                 }
+                let _ = done.send(());
+                break;
             }
             Err(e) => {
                 error!("Channel error: {}", e);
                 break;
             }
+        };
+        if let Err(e) = result {
+            error!("Failed to write to client: {}", e);
+            break;
         }
     }
 }
 
-fn hyprland_event_thread(subscriptions: Arc<Mutex<Vec<ClientHandle>>>) {
-    let hypr_rundir_path = get_hypr_rundir_path();
-    info!("Using hypr runtime directory: {}", hypr_rundir_path);
+/// Registers or reconnects a durable subscription and queues the events it
+/// collected. Returns false if another client is using the name.
+fn attach_durable(
+    daemon: &Daemon,
+    name: &str,
+    subscription: &Subscription,
+    queue: &ClientQueue,
+) -> bool {
+    let mut durable = daemon.durable.lock().unwrap();
+    let entry = durable
+        .entry(name.to_string())
+        .or_insert_with(|| DurableSubscription {
+            subscription: subscription.clone(),
+            connected: false,
+            events: VecDeque::new(),
+            dropped: 0,
+            since: 0,
+        });
+    if entry.connected {
+        error!("Durable subscription '{}' is already connected", name);
+        return false;
+    }
+    info!(
+        "Durable subscription '{}' attached with {} buffered events",
+        name,
+        entry.events.len()
+    );
+    if entry.dropped > 0 {
+        info!(
+            "Durable subscription '{}' dropped {} events",
+            name, entry.dropped
+        );
+        if daemon.durable_overflow == OverflowPolicy::Resync {
+            queue.push(Envelope::new(
+                daemon.seq.load(Ordering::SeqCst),
+                HyprlandEvent::ResyncRequired {
+                    resume_from: entry.since,
+                },
+            ));
+        }
+    }
+    for envelope in entry.events.drain(..) {
+        queue.push(envelope);
+    }
+    entry.subscription = subscription.clone();
+    entry.connected = true;
+    entry.dropped = 0;
+    true
+}
 
-    let socket2_path = format!("{}/.socket2.sock", hypr_rundir_path);
-    info!("Using hypr socket2 path: {}", socket2_path);
-    let socket2 = create_socket(&socket2_path);
-    let reader = BufReader::new(socket2);
+/// Marks a durable subscription as disconnected, keeping the events its
+/// client did not receive or acknowledge for the next connection.
+fn detach_durable(
+    daemon: &Daemon,
+    name: &str,
+    mut undelivered: Vec<Envelope>,
+    rx: &mpsc::Receiver<ClientMessage>,
+) {
+    // Hold the registry lock so no event slips between draining the
+    // channel and buffering in the durable subscription.
+    let _subs = daemon.subscriptions.lock().unwrap();
+    undelivered.extend(rx.try_iter().filter_map(|message| match message {
+        ClientMessage::Event(envelope) => Some(envelope),
+        _ => None,
+    }));
+    let mut durable = daemon.durable.lock().unwrap();
+    if let Some(entry) = durable.get_mut(name) {
+        info!(
+            "Durable subscription '{}' detached with {} undelivered events",
+            name,
+            undelivered.len()
+        );
+        for envelope in undelivered.into_iter().rev() {
+            entry.events.push_front(envelope);
+        }
+        entry.connected = false;
+        entry.since = daemon.seq.load(Ordering::SeqCst);
+    }
+}
 
-    for line in reader.lines() {
-        match line {
-            Ok(line_content) => {
-                match parse_event_line(&line_content) {
-                    Ok(event) => {
-                        let event_name = event_type(&event);
-                        let json = serde_json::to_string(&event).unwrap();
-                        info!("Received event: {}", json);
-                        let mut subs = subscriptions.lock().unwrap();
-                        // Dispatch events to matching clients.
-                        subs.retain(|client| {
-                            let send_result = match &client.subscription {
-                                Subscription::All => client.sender.send(event.clone()),
-                                Subscription::Filtered(filters) => {
-                                    if filters.contains(&event_name.to_string()) {
-                                        client.sender.send(event.clone())
-                                    } else {
-                                        Ok(())
-                                    }
-                                }
-                            };
-                            send_result.is_ok()
-                        });
-                    }
-                    Err(e) => error!("Error parsing event '{}': {}", line_content, e),
+impl<W: Write> ClientWriter<W> {
+    fn event(&mut self, daemon: &Daemon, mut envelope: Envelope) -> io::Result<()> {
+        if !self.enrich {
+            envelope.window = None;
+            envelope.workspace = None;
+        }
+        match &mut self.paused {
+            Some(held) if held.missed > 0 || held.events.len() >= daemon.pause_buffer_limit => {
+                // Past the limit only count what is skipped; resume
+                // sends a snapshot instead.
+                held.missed += held.events.len() as u64 + 1;
+                held.events.clear();
+                Ok(())
+            }
+            Some(held) => {
+                held.events.push(envelope);
+                Ok(())
+            }
+            None => self.deliver(daemon, envelope),
+        }
+    }
+
+    /// Writes the envelope unless the ack window is full.
+    fn deliver(&mut self, daemon: &Daemon, envelope: Envelope) -> io::Result<()> {
+        match &mut self.ack {
+            Some(ack) if ack.in_flight.len() >= ack.window => {
+                if ack.queued.len() >= daemon.ack_queue_limit {
+                    return Err(io::Error::other("too many unacknowledged events"));
+                }
+                ack.queued.push_back(envelope);
+                Ok(())
+            }
+            Some(ack) => {
+                write_envelope(&mut self.writer, self.format, &envelope)?;
+                ack.in_flight.push_back(envelope);
+                Ok(())
+            }
+            None => write_envelope(&mut self.writer, self.format, &envelope),
+        }
+    }
+
+    /// Drops the events acknowledged by the client and fills the window
+    /// from the queue.
+    fn acknowledge(&mut self, seq: u64) -> io::Result<()> {
+        let Some(ack) = &mut self.ack else {
+            return Ok(());
+        };
+        while ack
+            .in_flight
+            .front()
+            .is_some_and(|envelope| envelope.seq <= seq)
+        {
+            ack.in_flight.pop_front();
+        }
+        while ack.in_flight.len() < ack.window
+            && let Some(envelope) = ack.queued.pop_front()
+        {
+            write_envelope(&mut self.writer, self.format, &envelope)?;
+            ack.in_flight.push_back(envelope);
+        }
+        Ok(())
+    }
+
+    /// Forwards a dispatch sent by the client to Hyprland if its user may
+    /// run `dispatch`, and answers with a `DispatchReply`.
+    fn dispatch(&mut self, daemon: &Daemon, command: String) -> io::Result<()> {
+        let allowed = !self.sandboxed
+            && self
+                .peer
+                .is_some_and(|peer| control::is_allowed(daemon, "dispatch", &peer));
+        let (reply, error) = if allowed {
+            let name = self.peer.map(|peer| format!("pid {}", peer.pid));
+            let result = audit::AuditRecord {
+                source: "client",
+                name: name.as_deref(),
+                trigger: None,
+                command: command.clone(),
+                result: serde_json::Value::Null,
+            }
+            .dispatch(false, &daemon.audit);
+            let field = |key: &str| result.get(key).and_then(|v| v.as_str()).map(str::to_string);
+            (field("reply"), field("error"))
+        } else {
+            info!(
+                "Denied dispatch '{}' for client {:?}",
+                command,
+                self.peer.map(|p| p.uid)
+            );
+            (None, Some("permission denied for 'dispatch'".to_string()))
+        };
+        let reply = HyprlandEvent::DispatchReply {
+            command,
+            reply,
+            error,
+        };
+        write_bare(
+            &mut self.writer,
+            self.format,
+            daemon.seq.load(Ordering::SeqCst),
+            reply,
+        )
+    }
+
+    /// Events the client has not been sent or, in ack mode, has not
+    /// acknowledged yet, oldest first.
+    fn into_undelivered(self) -> Vec<Envelope> {
+        let mut events = Vec::new();
+        if let Some(ack) = self.ack {
+            events.extend(ack.in_flight);
+            events.extend(ack.queued);
+        }
+        if let Some(held) = self.paused {
+            events.extend(held.events);
+        }
+        events
+    }
+
+    /// Replays the events held back during a pause, or sends a fresh
+    /// snapshot when the buffer overflowed.
+    fn resume(&mut self, daemon: &Daemon) -> io::Result<()> {
+        let Some(held) = self.paused.take() else {
+            return Ok(());
+        };
+        info!("Client resumed");
+        if held.missed == 0 {
+            return held
+                .events
+                .into_iter()
+                .try_for_each(|envelope| self.deliver(daemon, envelope));
+        }
+        match query_state() {
+            Ok(state) => self.deliver(
+                daemon,
+                Envelope::new(
+                    daemon.seq.load(Ordering::SeqCst),
+                    HyprlandEvent::Snapshot {
+                        missed: held.missed,
+                        state,
+                    },
+                ),
+            ),
+            Err(e) => {
+                error!("Failed to query state for snapshot: {}", e);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Writes an event meant for this client only, outside the numbered stream.
+/// In JSON that is a bare event; protobuf frames are always envelopes, so
+/// there it carries the current `seq`.
+fn write_bare(
+    writer: &mut impl Write,
+    format: Format,
+    seq: u64,
+    event: HyprlandEvent,
+) -> io::Result<()> {
+    match format {
+        Format::Json(keys) => {
+            writeln!(writer, "{}", keys.to_json(&event))?;
+            writer.flush()
+        }
+        Format::Protobuf => write_envelope(writer, format, &Envelope::new(seq, event)),
+    }
+}
+
+fn write_envelope(writer: &mut impl Write, format: Format, envelope: &Envelope) -> io::Result<()> {
+    match format {
+        Format::Json(keys) => writeln!(writer, "{}", keys.to_json(envelope))?,
+        Format::Protobuf => {
+            let frame = encode_envelope_delimited(envelope).map_err(io::Error::other)?;
+            writer.write_all(&frame)?;
+        }
+    }
+    writer.flush()
+}
+
+/// Queues the events after `resume_from` for a reconnecting client, or asks
+/// it to resync when they already dropped out of the history. Called with
+/// `subscriptions` locked so nothing is dispatched in between.
+fn replay(daemon: &Daemon, resume_from: u64, subscription: &Subscription, queue: &ClientQueue) {
+    let seq = daemon.seq.load(Ordering::SeqCst);
+    let history = daemon.history.lock().unwrap();
+    let oldest = history.front().map_or(seq + 1, |envelope| envelope.seq);
+    if resume_from > seq || resume_from + 1 < oldest {
+        info!("Cannot replay from {}, client has to resync", resume_from);
+        queue.push(Envelope::new(
+            seq,
+            HyprlandEvent::ResyncRequired { resume_from },
+        ));
+        return;
+    }
+    for envelope in history
+        .iter()
+        .filter(|envelope| envelope.seq > resume_from && subscription.wants(&envelope.event))
+    {
+        queue.push(envelope.clone());
+    }
+}
+
+/// Delay before retrying to connect to Hyprland, doubled after every failed
+/// attempt up to `MAX_RECONNECT_DELAY`.
+const RECONNECT_DELAY: Duration = Duration::from_millis(100);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+/// Reads Hyprland's event socket for the lifetime of the daemon, connecting
+/// again whenever the connection fails or Hyprland is not up yet, e.g. when
+/// started by `exec-once` before Hyprland listens. When Hyprland restarts
+/// the daemon follows the new instance and tells clients with
+/// `HyprlandRestarted`.
+fn hyprland_event_thread(daemon: Arc<Daemon>) {
+    loop {
+        let (socket2, socket2_path) = connect_socket2();
+        info!("Connected to Hyprland event socket {}", socket2_path);
+        let signature = instance_signature().unwrap_or_default();
+        let previous = std::mem::replace(
+            &mut *daemon.instance_signature.lock().unwrap(),
+            signature.clone(),
+        );
+        daemon.hyprland_connected.store(true, Ordering::SeqCst);
+        // Replaces the binds of an earlier connection.
+        binds::register(&daemon.binds.lock().unwrap());
+        if previous != signature {
+            *daemon.hyprland_version.lock().unwrap() = query_hyprland_version();
+            link_runtime_dir(&daemon.runtime_dir, &previous, &signature);
+            dispatch(
+                &daemon,
+                HyprlandEvent::HyprlandRestarted {
+                    instance_signature: signature,
+                },
+            );
+        }
+        read_events(&daemon, socket2);
+        daemon.hyprland_connected.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Connects to socket2, retrying with exponential backoff until it accepts.
+fn connect_socket2() -> (UnixStream, String) {
+    let mut delay = RECONNECT_DELAY;
+    loop {
+        match socket2_path().and_then(|path| Ok((create_socket(&path)?, path))) {
+            Ok(connected) => return connected,
+            Err(_) if follow_new_instance() => {}
+            Err(e) => {
+                warn!("Cannot reach Hyprland, retrying in {:?}: {}", delay, e);
+                thread::sleep(delay);
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+            }
+        }
+    }
+}
+
+/// Switches to the newest Hyprland instance running once the current one
+/// is gone, returning whether it did. Explicit socket paths are kept.
+fn follow_new_instance() -> bool {
+    if hyprman::hypr::socket_paths_fixed() {
+        return false;
+    }
+    let instances = match hyprman::hypr::instances() {
+        Ok(instances) => instances,
+        Err(e) => {
+            warn!("Cannot look for Hyprland instances: {}", e);
+            return false;
+        }
+    };
+    let current = instance_signature().ok();
+    if instances
+        .iter()
+        .any(|instance| Some(&instance.signature) == current.as_ref())
+    {
+        return false;
+    }
+    let Some(newest) = instances.into_iter().next() else {
+        return false;
+    };
+    info!("Switching to Hyprland instance {}", newest.signature);
+    hyprman::hypr::set_instance_signature(newest.signature);
+    true
+}
+
+/// Makes the runtime directory of a new Hyprland instance a link to the
+/// daemon's, where clients started in that instance look for it.
+fn link_runtime_dir(runtime_dir: &str, previous: &str, signature: &str) {
+    let Some(base) = runtime_dir.strip_suffix(&format!("/{}", previous)) else {
+        return;
+    };
+    let link = format!("{}/{}", base, signature);
+    match std::os::unix::fs::symlink(previous, &link) {
+        Ok(()) => info!("Linked {} to {}", link, runtime_dir),
+        Err(e) => warn!("Cannot link {} to {}: {}", link, runtime_dir, e),
+    }
+}
+
+/// Dispatches the events read from socket2 until it closes or fails.
+fn read_events(daemon: &Daemon, socket2: UnixStream) {
+    let mut reader = BufReader::with_capacity(daemon.read_buffer_size, socket2);
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) => {
+                warn!("Hyprland closed the event socket");
+                return;
+            }
+            Ok(_) => {
+                daemon.raw_tee.send(&buf);
+                // Window titles may carry invalid UTF-8; keep the event
+                // instead of dropping the whole line.
+                let line_content = String::from_utf8_lossy(&buf).trim_end().to_string();
+                handle_event_line(daemon, line_content);
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => {
+                error!("Error reading from the event socket: {}", e);
+                return;
+            }
+        }
+    }
+}
+
+fn handle_event_line(daemon: &Daemon, line_content: String) {
+    match parse_event_line(&line_content, daemon.parse_mode) {
+        Ok(event) => {
+            let json = serde_json::to_string(&event).unwrap();
+            info!("Received event: {}", json);
+            dispatch(daemon, event);
+        }
+        Err(e) => {
+            error!("Error parsing event '{}': {}", line_content, e);
+            dispatch(
+                daemon,
+                HyprlandEvent::ParseError {
+                    line: line_content,
+                    error: e.to_string(),
+                },
+            );
+        }
+    }
+}
+
+/// Events that are only delivered to clients naming them in their filter.
+fn is_opt_in(event: &HyprlandEvent) -> bool {
+    matches!(
+        event,
+        HyprlandEvent::ParseError { .. } | HyprlandEvent::Unknown { .. }
+    ) || is_canonical(event)
+}
+
+/// Events merged for `canonical` subscribers, who get them without asking.
+fn is_canonical(event: &HyprlandEvent) -> bool {
+    matches!(
+        event,
+        HyprlandEvent::ActiveWindowChanged { .. } | HyprlandEvent::FocusedMonChanged { .. }
+    )
+}
+
+/// Sends an event to every matching client, dropping clients that hung up,
+/// runs the hooks listening for it unless do-not-disturb is on and updates
+/// the windows affected by window rules.
+fn dispatch(daemon: &Daemon, event: HyprlandEvent) {
+    // Before sending, so the first event of a pair already counts as seen.
+    let merged = daemon.canonical.handle(&event);
+    send_to_clients(daemon, &event);
+    daemon.rates.lock().unwrap().record(&event);
+    daemon.titles.lock().unwrap().record(&event);
+    daemon.focus.lock().unwrap().record(&event);
+    daemon.groups.lock().unwrap().handle(&event);
+    if !daemon.dnd.load(Ordering::SeqCst) {
+        let profile = daemon.profile.lock().unwrap().clone();
+        daemon.hooks.lock().unwrap().fire(
+            &event,
+            profile.as_deref(),
+            daemon.dry_run,
+            &daemon.audit,
+        );
+    }
+    daemon
+        .window_rules
+        .lock()
+        .unwrap()
+        .apply(&event, daemon.dry_run, &daemon.audit);
+    daemon
+        .focus_restore
+        .lock()
+        .unwrap()
+        .apply(&event, daemon.dry_run, &daemon.audit);
+    daemon
+        .submap_timeouts
+        .lock()
+        .unwrap()
+        .handle(&event, daemon.dry_run, &daemon.audit);
+    let changes = daemon.fullscreen.lock().unwrap().handle(&event);
+    for change in changes {
+        dispatch(daemon, change);
+    }
+    let changes = daemon.workspace_windows.lock().unwrap().handle(&event);
+    for change in changes {
+        dispatch(daemon, change);
+    }
+    let overlay = daemon.overlays.lock().unwrap().handle(&event);
+    if let Some(overlay) = overlay {
+        dispatch(daemon, overlay);
+    }
+    let focus_change = daemon.effective_focus.lock().unwrap().handle(&event);
+    if let Some(focus_change) = focus_change {
+        dispatch(daemon, focus_change);
+    }
+    if let Some(merged) = merged {
+        dispatch(daemon, merged);
+    }
+}
+
+fn send_to_clients(daemon: &Daemon, event: &HyprlandEvent) {
+    // Before numbering, so suppressed events leave no gaps.
+    if daemon.suppress_legacy_events.load(Ordering::SeqCst) && daemon.canonical.is_legacy(event) {
+        return;
+    }
+    let mut subs = daemon.subscriptions.lock().unwrap();
+    let mut envelope = Envelope::new(daemon.seq.fetch_add(1, Ordering::SeqCst) + 1, event.clone());
+    if let Some(multicast) = &daemon.multicast {
+        multicast.send(&envelope);
+    }
+    let enrich = subs.iter().any(|client| client.enrich);
+    envelope.window = daemon.windows.lock().unwrap().handle(event, enrich);
+    envelope.workspace = daemon.occupancy.lock().unwrap().handle(event, enrich);
+    subs.retain(|client| {
+        if !client.subscription.wants(event) {
+            return true;
+        }
+        if client.queue.depth.load(Ordering::SeqCst) >= daemon.client_queue_limit {
+            error!(
+                "Disconnecting client with {} queued events",
+                daemon.client_queue_limit
+            );
+            let _ = client.socket.shutdown(Shutdown::Both);
+            return false;
+        }
+        client.queue.push(envelope.clone())
+    });
+    let mut durable = daemon.durable.lock().unwrap();
+    for entry in durable
+        .values_mut()
+        .filter(|entry| !entry.connected && entry.subscription.wants(event))
+    {
+        entry.push(
+            envelope.clone(),
+            daemon.durable_buffer_size,
+            daemon.durable_overflow,
+        );
+    }
+    if daemon.history_size > 0 {
+        let mut history = daemon.history.lock().unwrap();
+        if history.len() == daemon.history_size {
+            history.pop_front();
+        }
+        history.push_back(envelope);
+    }
+}
+
+/// A socket file bound by this daemon, removed again when dropped unless
+/// something else has replaced it in the meantime.
+struct SocketFile {
+    path: String,
+    inode: (u64, u64),
+}
+
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        if fs::symlink_metadata(&self.path).is_ok_and(|m| (m.dev(), m.ino()) == self.inode) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Applies `socket_mode` and `socket_group` to a socket the daemon bound,
+/// logging instead of failing like the control socket's mode.
+fn set_socket_access(path: &str, mode: Option<u32>, group: Option<&str>) {
+    if let Some(group) = group {
+        match control::group_id(group) {
+            Some(gid) => {
+                if let Err(e) = std::os::unix::fs::chown(path, None, Some(gid)) {
+                    error!("Failed to set group {} on socket {}: {}", group, path, e);
                 }
             }
-            Err(e) => error!("Error reading line: {}", e),
+            None => error!("Unknown socket_group {}", group),
         }
     }
+    if let Some(mode) = mode
+        && let Err(e) = fs::set_permissions(path, fs::Permissions::from_mode(mode))
+    {
+        error!("Failed to set mode {:o} on socket {}: {}", mode, path, e);
+    }
 }
 
-fn client_server_thread(client_socket_path: String, subscriptions: Arc<Mutex<Vec<ClientHandle>>>) {
-    // Remove existing socket file if present.
-    let _ = fs::remove_file(&client_socket_path);
-    let listener = UnixListener::bind(&client_socket_path)
-        .unwrap_or_else(|e| panic!("Failed to bind client socket {}: {}", client_socket_path, e));
-    info!("Client server listening on {}", client_socket_path);
+/// Binds a daemon socket. A socket file left behind by a daemon that died is
+/// replaced, but one that another daemon still accepts connections on is not.
+fn bind_socket(path: &str) -> Result<(UnixListener, SocketFile)> {
+    let context = |e: io::Error| io::Error::new(e.kind(), format!("cannot bind {}: {}", path, e));
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if !metadata.file_type().is_socket() => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} exists and is not a socket", path),
+            )
+            .into());
+        }
+        Ok(_) => {
+            if UnixStream::connect(path).is_ok() {
+                return Err(HyprmanError::Daemon(format!(
+                    "another daemon is listening on {}",
+                    path
+                )));
+            }
+            info!("Removing stale socket {}", path);
+            fs::remove_file(path).map_err(context)?;
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(context(e).into()),
+    }
+    let listener = UnixListener::bind(path).map_err(context)?;
+    let metadata = fs::symlink_metadata(path).map_err(context)?;
+    let socket_file = SocketFile {
+        path: path.to_string(),
+        inode: (metadata.dev(), metadata.ino()),
+    };
+    Ok((listener, socket_file))
+}
 
+fn client_server_thread(listener: UnixListener, daemon: Arc<Daemon>, sandboxed: bool) {
     for stream in listener.incoming() {
         match stream {
             Ok(stream) => {
-                let subs = subscriptions.clone();
-                thread::spawn(move || client_handler(stream, subs));
+                let daemon = daemon.clone();
+                thread::spawn(move || client_handler(stream, daemon, sandboxed));
             }
             Err(e) => error!("Failed to accept client connection: {}", e),
         }
     }
 }
 
-fn create_socket(socket_path: &str) -> UnixStream {
-    UnixStream::connect(socket_path)
-        .unwrap_or_else(|err| panic!("Could not connect to socket {}: {}", socket_path, err))
+fn set_niceness(nice: i32) {
+    // On Linux this only affects the calling thread, which is why it runs
+    // before the daemon spawns its threads.
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } != 0 {
+        error!(
+            "Failed to set niceness {}: {}",
+            nice,
+            io::Error::last_os_error()
+        );
+    } else {
+        info!("Running with niceness {}", nice);
+    }
+}
+
+fn pin_current_thread(cpu: usize) {
+    let result = unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_SET(cpu, &mut set);
+        libc::sched_setaffinity(0, size_of::<libc::cpu_set_t>(), &set)
+    };
+    if result != 0 {
+        error!(
+            "Failed to pin dispatch thread to CPU {}: {}",
+            cpu,
+            io::Error::last_os_error()
+        );
+    } else {
+        info!("Dispatch thread pinned to CPU {}", cpu);
+    }
 }
 
 /// The main daemon functionality: spawn threads, handle signals, etc.
-fn run_daemon(config: Config) {
-    // Global subscription registry.
-    let subscriptions = Arc::new(Mutex::new(Vec::<ClientHandle>::new()));
+fn run_daemon(
+    config: Config,
+    config_path: String,
+    runtime_dir: String,
+    dry_run: bool,
+) -> Result<()> {
+    // Set before any thread is spawned so all of them inherit it.
+    if let Some(nice) = config.nice {
+        set_niceness(nice);
+    }
+
+    // Global subscription registry and connection metadata.
+    let daemon = Arc::new(Daemon {
+        subscriptions: Mutex::new(Vec::new()),
+        seq: AtomicU64::new(0),
+        hyprland_version: Mutex::new(query_hyprland_version()),
+        instance_signature: Mutex::new(instance_signature().unwrap_or_default()),
+        runtime_dir,
+        started: Instant::now(),
+        hyprland_connected: AtomicBool::new(false),
+        audit: Arc::new(audit::AuditLog::open(config.audit_log.as_deref())),
+        hooks: Mutex::new(hooks::Hooks::new(config.hooks, config.sound_player)),
+        window_rules: Mutex::new(windowrules::WindowRules::new(config.window_rules)),
+        key_case: config.key_case,
+        dry_run,
+        binds: Mutex::new(config.binds),
+        config_path,
+        config_profile: config.config_profile,
+        dnd: AtomicBool::new(false),
+        suppress_legacy_events: AtomicBool::new(config.suppress_legacy_events),
+        parse_mode: config.parse_mode,
+        profile: Mutex::new(config.profile),
+        control_permissions: Mutex::new(config.control_permissions),
+        pause_buffer_limit: config.pause_buffer_limit,
+        ack_queue_limit: config.ack_queue_limit,
+        client_queue_limit: config.client_queue_limit,
+        read_buffer_size: config.read_buffer_size,
+        durable: Mutex::new(HashMap::new()),
+        durable_buffer_size: config.durable_buffer_size,
+        durable_overflow: config.durable_overflow,
+        multicast: config.multicast.and_then(|multicast| {
+            multicast::MulticastSink::new(multicast, config.key_case)
+                .inspect_err(|e| error!("Failed to set up multicast sink: {}", e))
+                .ok()
+        }),
+        history: Mutex::new(VecDeque::with_capacity(config.history_size)),
+        history_size: config.history_size,
+        rates: Mutex::new(rates::EventRates::new()),
+        titles: Mutex::new(titles::TitleHistory::new(config.title_history_size)),
+        focus: Mutex::new(focus::FocusHistory::new(config.focus_history_size)),
+        focus_restore: Mutex::new(focus::FocusRestore::new(config.restore_focus)),
+        effective_focus: Mutex::new(focus::EffectiveFocus::query().unwrap_or_else(|e| {
+            warn!("Could not query the focus: {}", e);
+            focus::EffectiveFocus::default()
+        })),
+        groups: Mutex::new(groups::Groups::query().unwrap_or_else(|e| {
+            warn!("Could not query window groups: {}", e);
+            groups::Groups::default()
+        })),
+        fullscreen: Mutex::new(fullscreen::Fullscreen::query().unwrap_or_else(|e| {
+            warn!("Could not query fullscreen windows: {}", e);
+            fullscreen::Fullscreen::default()
+        })),
+        overlays: Mutex::new(overlays::Overlays::new(config.overlay_namespaces)),
+        canonical: Arc::default(),
+        windows: Mutex::default(),
+        occupancy: Mutex::default(),
+        workspace_windows: Mutex::new(occupancy::WorkspaceWindows::query().unwrap_or_else(|e| {
+            warn!("Could not query workspace windows: {}", e);
+            occupancy::WorkspaceWindows::default()
+        })),
+        submap_timeouts: Mutex::new(submap::SubmapTimeouts::new(config.submap_timeouts)),
+        raw_tee: tee::RawTee::default(),
+    });
+
+    // Reports readiness to systemd when it started the daemon.
+    let mut notifier = systemd::Notifier::from_env();
 
     // Setup signal handling for graceful shutdown.
-    let mut signals = Signals::new(TERM_SIGNALS).expect("Unable to setup signal handling");
+    let mut signals = Signals::new(TERM_SIGNALS)?;
     let signals_handle = signals.handle();
     let shutdown_flag = Arc::new(Mutex::new(false));
     {
         let shutdown_flag = shutdown_flag.clone();
         thread::spawn(move || {
-            for signal in signals.forever() {
+            if let Some(signal) = signals.forever().next() {
                 info!("Received termination signal: {}", signal);
                 *shutdown_flag.lock().unwrap() = true;
-                break;
             }
         });
     }
 
+    // Bind the sockets up front so a second daemon gives up before it
+    // touches anything.
+    let (client_listener, _client_socket) = bind_socket(&config.client_socket_path)?;
+    set_socket_access(
+        &config.client_socket_path,
+        config.socket_mode,
+        config.socket_group.as_deref(),
+    );
+    info!("Client server listening on {}", config.client_socket_path);
+    let (control_listener, _control_socket) =
+        control::bind_control_socket(&config.control_socket_path, config.control_socket_mode)?;
+    let (sandbox_listener, _sandbox_socket) = match &config.sandbox_socket_path {
+        Some(path) => {
+            let (listener, socket) = bind_socket(path)?;
+            set_socket_access(path, config.socket_mode, config.socket_group.as_deref());
+            info!("Sandbox client server listening on {}", path);
+            (Some(listener), Some(socket))
+        }
+        None => (None, None),
+    };
+    let (raw_listener, _raw_socket) = match &config.raw_socket_path {
+        Some(path) => {
+            let (listener, socket) = bind_socket(path)?;
+            set_socket_access(path, config.socket_mode, config.socket_group.as_deref());
+            info!("Raw event server listening on {}", path);
+            (Some(listener), Some(socket))
+        }
+        None => (None, None),
+    };
+    let socket2_path = socket2_path()?;
+    info!("Using hypr socket2 path: {}", socket2_path);
+
     // Spawn thread to read and dispatch Hyprland events.
-    let subs_clone = subscriptions.clone();
-    thread::spawn(move || {
-        hyprland_event_thread(subs_clone);
+    let daemon_clone = daemon.clone();
+    let dispatch_cpu = config.dispatch_cpu;
+    let event_thread = thread::spawn(move || {
+        if let Some(cpu) = dispatch_cpu {
+            pin_current_thread(cpu);
+        }
+        hyprland_event_thread(daemon_clone);
     });
 
     // Spawn thread to accept client connections.
-    let client_socket_path = config.client_socket_path;
-    let subs_clone = subscriptions.clone();
+    let daemon_clone = daemon.clone();
     thread::spawn(move || {
-        client_server_thread(client_socket_path, subs_clone);
+        client_server_thread(client_listener, daemon_clone, false);
     });
+    if let Some(sandbox_listener) = sandbox_listener {
+        let daemon_clone = daemon.clone();
+        thread::spawn(move || {
+            client_server_thread(sandbox_listener, daemon_clone, true);
+        });
+    }
+    if let Some(raw_listener) = raw_listener {
+        let daemon_clone = daemon.clone();
+        thread::spawn(move || {
+            tee::raw_server_thread(
+                raw_listener,
+                &daemon_clone.raw_tee,
+                daemon_clone.client_queue_limit,
+            );
+        });
+    }
 
-    // Main thread waits for shutdown.
+    // Spawn thread to reload the config on SIGHUP, keeping clients connected.
+    let mut hangups = Signals::new([SIGHUP])?;
+    let daemon_clone = daemon.clone();
+    thread::spawn(move || {
+        for _ in hangups.forever() {
+            info!("Received SIGHUP, reloading config");
+            control::execute(&daemon_clone, control::ControlCommand::Reload);
+        }
+    });
+
+    // Spawn thread to reload the config when it changes.
+    if config.watch_config {
+        let daemon_clone = daemon.clone();
+        thread::spawn(move || watch::config_watch_thread(daemon_clone));
+    }
+
+    // Spawn thread to accept control commands.
+    let daemon_clone = daemon.clone();
+    thread::spawn(move || {
+        control::control_server_thread(control_listener, daemon_clone);
+    });
+
+    // Main thread waits for shutdown, vouching to systemd for the event
+    // thread while it runs.
+    notifier.ready();
+    let tick = notifier
+        .watchdog_interval()
+        .map_or(Duration::from_secs(1), |interval| {
+            interval.min(Duration::from_secs(1))
+        });
     loop {
         if *shutdown_flag.lock().unwrap() {
             info!("Shutting down daemon");
+            notifier.stopping();
+            shutdown_clients(&daemon);
+            binds::unregister(&daemon.binds.lock().unwrap());
             signals_handle.close();
-            break;
+            return Ok(());
         }
-        thread::sleep(Duration::from_secs(1));
+        if !event_thread.is_finished() {
+            notifier.watchdog();
+        }
+        thread::sleep(tick);
     }
 }
 
-/// === Client Mode Function ===
-/// Accepts a subscription filter (e.g. "all" or "activewindow")
-fn run_client(config: &Config, subscription: &str) {
-    match UnixStream::connect(&config.client_socket_path) {
-        Ok(mut stream) => {
-            // Send subscription preferences.
-            let subscription_line = format!("{}\n", subscription);
-            if let Err(e) = stream.write_all(subscription_line.as_bytes()) {
-                eprintln!("Failed to send subscription: {}", e);
-                std::process::exit(1);
-            }
-            info!(
-                "Subscribed to '{}' events. Waiting for events...",
-                subscription
-            );
-
-            let reader = BufReader::new(stream);
-            for line in reader.lines() {
-                match line {
-                    Ok(msg) => println!("{}", msg),
-                    Err(e) => {
-                        eprintln!("Error reading from daemon: {}", e);
-                        break;
-                    }
-                }
-            }
-        }
-        Err(e) => {
-            eprintln!("Failed to connect to daemon. Is it running? Error: {}", e);
-            std::process::exit(1);
+/// Tells every client the daemon is going away and waits a moment for their
+/// writers to flush it, then closes the connections.
+fn shutdown_clients(daemon: &Daemon) {
+    let clients: Vec<ClientHandle> = daemon.subscriptions.lock().unwrap().drain(..).collect();
+    let (done, flushed) = mpsc::channel();
+    let pending = clients
+        .iter()
+        .filter(|client| {
+            client
+                .queue
+                .sender
+                .send(ClientMessage::Shutdown(done.clone()))
+                .is_ok()
+        })
+        .count();
+    let deadline = Instant::now() + Duration::from_secs(1);
+    for _ in 0..pending {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        if flushed.recv_timeout(timeout).is_err() {
+            warn!("Gave up waiting for clients to receive the shutdown");
+            break;
         }
     }
+    for client in clients {
+        let _ = client.socket.shutdown(Shutdown::Both);
+    }
 }
 
-/// Prints the active window as json
-
-fn run_activewindow_client(config: &Config) {
-    let subscription_line = String::from(
-        "activewindowv2,fullscreen,closewindow,movewindow,changefloatingmode,moveintogroup,moveoutofgroup,togglegroup,pin,windowtitle\n",
+// === Client Mode Function ===
+/// Accepts a subscription filter (e.g. "all" or "activewindow")
+fn run_client(config: &Config, subscription: &str, mut output: output::Output) -> Result<()> {
+    let mut stream = create_socket(&config.client_socket_path)?;
+    // Send subscription preferences.
+    let subscription_line = format!("{}\n", subscription);
+    stream.write_all(subscription_line.as_bytes())?;
+    info!(
+        "Subscribed to '{}' events. Waiting for events...",
+        subscription
     );
-    info!("Using subscription line: {}", subscription_line);
-    let event_reader = connect_unix_socket(config, subscription_line);
-    let mut clients = query_clients();
-    print_empty_client();
-    for event_line in event_reader.lines() {
-        let event_line = event_line.unwrap();
-        info!("Read eventline: {}", event_line.clone());
-        let event: HyprlandEvent =
-            serde_json::from_str(&event_line).expect("Failed to parse event");
-        info!("Parsed event: {}", serde_json::to_string(&event.clone()).unwrap());
-        match event {
-            HyprlandEvent::ActiveWindowV2 { window_address } => {
-                if window_address != "" {
-                    if let Some(client) = clients.get(&format!("0x{}", window_address)) {
-                        println!("{}", serde_json::to_string(&client).unwrap());
-                    } else {
-                        clients = query_clients();
-                        if let Some(client) = clients.get(&format!("0x{}", window_address)) {
-                            println!("{}", serde_json::to_string(&client).unwrap());
-                        } else {
-                            eprintln!("Failed to find window address {}", window_address);
-                            std::process::exit(1);
-                        }
-                    }
-                } else {
-                    info!("No active window.");
-                    print_empty_client();
-                }
-            }
-            _ => {
-                clients = query_clients();
-                let active_client = query_active_client();
-                println!("{}", serde_json::to_string(&active_client).unwrap());
-            }
-        }
+
+    let reader = BufReader::new(stream);
+    for line in reader.lines() {
+        output.write(&line?);
     }
+    Ok(())
 }
 
-/// Prints the workspaces as json highlighting the active one
+// === Helper functions for clients that also query socket1 ===
 
-fn run_workspaces_client(config: &Config) {
-    let subscription_line = String::from(
-        "workspacev2,focusedmonv2,createworkspacev2,destoryworkspacev2,moveworkspacev2,renameworkspace,activespecial,openwindow,closewindow\n",
-    );
-    info!("Using subscription line: {}", subscription_line);
-    let event_reader = connect_unix_socket(config, subscription_line);
-    let mut workspaces = query_workspaces();
-    let serialized = serde_json::to_string(&workspaces).expect("Failed to serialize workspaces");
-    println!("{}", serialized);
-    for event_line in event_reader.lines() {
-        let event: HyprlandEvent =
-            serde_json::from_str(&event_line.unwrap()).expect("Failed to parse event");
-        let mut active_id: u8 = 0;
-        match event {
-            HyprlandEvent::WorkspaceV2 { workspace_id, .. }
-            | HyprlandEvent::FocusedMonV2 { workspace_id, .. } => {
-                active_id = workspace_id;
-            }
-            _ => {
-                workspaces = query_workspaces();
-                if active_id < 1 {
-                    active_id = query_active_workspace().id;
-                }
-            }
-        }
-        let mut workspaces_tmp = workspaces.clone();
-        workspaces_tmp.sort_by(|a, b| a.id.cmp(&b.id));
-        let workspace = workspaces_tmp
-            .iter_mut()
-            .find(|w| w.id == active_id)
-            .unwrap();
-        workspace.active = Some(true);
-        let serialized =
-            serde_json::to_string(&workspaces_tmp).expect("Failed to serialize workspaces");
-        println!("{}", serialized);
-    }
-}
-/// === Helper functions for clients that also query socket1 ===
-
-fn connect_unix_socket(config: &Config, subscription_line: String) -> BufReader<UnixStream> {
-    match UnixStream::connect(&config.client_socket_path) {
-        Ok(mut stream) => {
-            if let Err(e) = stream.write_all(subscription_line.as_bytes()) {
-                eprintln!("Failed to send subscription: {}", e);
-                std::process::exit(1);
+fn connect_unix_socket(config: &Config, subscription_line: String) -> Result<Connection> {
+    let connection =
+        Connection::connect(&config.client_socket_path, &subscription_line).map_err(|source| {
+            HyprmanError::Connect {
+                path: config.client_socket_path.clone(),
+                source,
             }
-            info!("Successfully connected to daemon.");
-            BufReader::new(stream)
-        }
-        Err(e) => {
-            eprintln!("Failed to connect to daemon. Is it running? Error: {}", e);
-            std::process::exit(1);
-        }
-    }
+        })?;
+    info!("Successfully connected to daemon.");
+    Ok(connection)
 }
 
-fn create_empty_client() -> Client {
-    Client {
-        address: "".to_string(),
-        mapped: false,
-        hidden: false,
-        at: (0, 0),
-        size: (0, 0),
-        workspace: Workspace {
-            id: 0,
-            name: "".to_string(),
-            active: None,
-            monitor: None,
-            monitor_id: None,
-            windows: None,
-            has_fullscreen: None,
-            last_window: None,
-            last_window_title: None,
-        },
-        floating: false,
-        pseudo: false,
-        monitor: 0,
-        class: "".to_string(),
-        title: "".to_string(),
-        initial_class: "".to_string(),
-        initial_title: "".to_string(),
-        pid: 0,
-        xwayland: false,
-        pinned: false,
-        fullscreen: 0,
-        fullscreen_client: 0,
-        grouped: vec![],
-        tags: vec![],
-        swallowing: "".to_string(),
-        focus_history_id: 0,
-        inhibiting_idle: false,
-    }
-}
-fn print_empty_client(){
-    let client = create_empty_client();
-    println!("{}", serde_json::to_string(&client).unwrap());
-}
-
-fn query_socket(query: &str) -> String {
-    info!("Using query: {}", query);
-    let hypr_rundir_path = get_hypr_rundir_path();
-    info!("Using hypr runtime directory: {}", hypr_rundir_path);
-    let socket_path = format!("{}/.socket.sock", hypr_rundir_path);
-    info!("Using hypr socket1 path: {}", socket_path);
-    let mut stream = create_socket(&socket_path);
-    stream.write_all(query.as_bytes()).unwrap();
-    let mut response = String::new();
-    stream.read_to_string(&mut response).unwrap();
-    stream.flush().expect("Failed to flush stream");
-    response
-}
-fn query_active_client() -> Client {
-    let query = "j/activewindow";
-    let response = query_socket(query);
-    if response != "{}" {
-        serde_json::from_str(&response).unwrap_or_else(|e| {
-            eprintln!("Failed to parse active window: {}", e);
-            std::process::exit(1);
-        })
-    } else {
-        info!("Active window is empty.");
-        create_empty_client()
-    }
-}
-fn query_clients() -> HashMap<String, Client> {
-    let query = "j/clients";
-    let response = query_socket(query);
-    let clients: Vec<Client> =
-        serde_json::from_str(&response).expect("Failed to parse clients response");
-    clients
-        .into_iter()
-        .map(|c| (c.address.clone(), c))
-        .collect()
-}
-fn query_active_workspace() -> Workspace {
-    let query = "j/activeworkspace";
-    let response = query_socket(query);
-    serde_json::from_str(&response).expect("Failed to parse active window response")
-}
-fn query_workspaces() -> Vec<Workspace> {
-    let query = "j/workspaces";
-    let response = query_socket(query);
-    serde_json::from_str(&response).expect("Failed to parse response")
+/// Parses an event line the daemon sent.
+fn parse_event(line: &str) -> Result<HyprlandEvent> {
+    serde_json::from_str(line).map_err(|source| HyprmanError::Parse {
+        context: format!("event '{}'", line),
+        source,
+    })
 }
 
-/// === Daemon Control Functions ===
+// === Daemon Control Functions ===
 
-fn stop_daemon() -> Result<(), Box<dyn Error>> {
-    // Compute pid file path from $XDG_RUNTIME_DIR/hyprman/
-    let xdg_runtime_dir = env::var("XDG_RUNTIME_DIR").expect("XDG_RUNTIME_DIR not set");
-    let hyprman_dir = format!("{}/hyprman", xdg_runtime_dir);
-    let pid_file_path = format!("{}/hyprman.pid", hyprman_dir);
-    let pid_str = fs::read_to_string(&pid_file_path)?;
-    let pid: i32 = pid_str.trim().parse()?;
+fn stop_daemon(pid_file_path: &str) -> Result<()> {
+    let pid = lock::holder(pid_file_path)?
+        .ok_or_else(|| HyprmanError::Daemon("the daemon is not running".to_string()))?;
     unsafe {
         if libc::kill(pid, libc::SIGTERM) != 0 {
-            return Err(format!("Failed to kill process {}", pid).into());
+            return Err(HyprmanError::Daemon(format!(
+                "failed to stop process {}: {}",
+                pid,
+                io::Error::last_os_error()
+            )));
         }
     }
-    fs::remove_file(&pid_file_path)?;
-    println!("Daemon stopped.");
-    Ok(())
+    // Wait for it to release the lock and its sockets, so a restart can
+    // take them.
+    for _ in 0..50 {
+        if lock::holder(pid_file_path)?.is_none() {
+            println!("Daemon stopped.");
+            return Ok(());
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    Err(HyprmanError::Daemon(format!(
+        "process {} did not exit within 5 seconds",
+        pid
+    )))
 }
 
-fn restart_daemon() -> Result<(), Box<dyn Error>> {
-    stop_daemon()?;
-    thread::sleep(Duration::from_secs(1));
+fn restart_daemon(pid_file_path: &str, config_path: &str, profile: Option<&str>) -> Result<()> {
+    stop_daemon(pid_file_path)?;
     let current_exe = env::current_exe()?;
-    Command::new(current_exe).arg("-d").spawn()?;
+    let mut command = Command::new(current_exe);
+    command.args(["daemon", "--config", config_path]);
+    if let Some(profile) = profile {
+        command.args(["--profile", profile]);
+    }
+    command.spawn()?;
     println!("Daemon restarted.");
     Ok(())
 }
 
-/// Print usage help text.
-fn print_help() {
-    println!("Usage: hyprman [OPTIONS]");
-    println!();
-    println!("Options:");
-    println!("  -d, --daemon          Run Hyprman as a daemon.");
-    println!("  -r, --restart         Restart the running daemon.");
-    println!("  -k, --kill            Stop the running daemon.");
-    println!("  -f, --filter [FILTER] Run client mode with a subscription filter (default: all).");
-    println!("  -a, --activewindow    Run client mode to track active window changes.");
-    println!("  -w, --workspaces      Run client mode to track workspace events.");
-    println!("  -h, --help            Show this help message.");
-    println!();
+/// Starts the daemon for a client mode unless it is running, and waits for
+/// it to listen on the client socket.
+fn spawn_daemon(
+    config: &Config,
+    pid_file_path: &str,
+    config_path: &str,
+    profile: Option<&str>,
+) -> Result<()> {
+    if lock::holder(pid_file_path)?.is_some() {
+        return Ok(());
+    }
+    info!("Daemon not running, starting it");
+    let spawned = std::time::SystemTime::now();
+    let mut command = Command::new(env::current_exe()?);
+    command.args(["daemon", "--config", config_path]);
+    if let Some(profile) = profile {
+        command.args(["--profile", profile]);
+    }
+    // The daemon has to serve the socket this client waits on and talk to
+    // the same Hyprland.
+    let (socket1, socket2) = socket_paths();
+    if let Some(socket1) = socket1 {
+        command.args(["--socket1", &socket1]);
+    }
+    if let Some(socket2) = socket2 {
+        command.args(["--socket2", &socket2]);
+    }
+    if env::var("HYPRMAN_SOCKET").is_ok_and(|socket| !socket.is_empty()) {
+        command.env("HYPRMAN_CLIENT_SOCKET_PATH", &config.client_socket_path);
+    }
+    // Returns once the daemon has forked; a client started at the same time
+    // may have won the race, in which case that daemon is waited for.
+    command.status()?;
+    // A socket left behind by a daemon that crashed predates the spawn.
+    let listening = || {
+        fs::metadata(&config.client_socket_path)
+            .is_ok_and(|m| m.file_type().is_socket() && m.modified().is_ok_and(|t| t >= spawned))
+    };
+    for _ in 0..50 {
+        if listening() {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    Err(HyprmanError::Daemon(format!(
+        "started the daemon, but {} did not appear within 5 seconds",
+        config.client_socket_path
+    )))
+}
+
+/// Picks the Hyprland instance to use: the one given with `--instance`, that
+/// of `$HYPRLAND_INSTANCE_SIGNATURE`, or the newest one running. The choice
+/// is exported in `$HYPRLAND_INSTANCE_SIGNATURE`, where the socket lookup and
+/// the commands the daemon runs find it. None if there is no instance to
+/// pick, which only matters if socket1 or socket2 were not given explicitly.
+fn resolve_instance(requested: Option<String>) -> Result<Option<String>> {
+    let signature = match requested {
+        Some(signature) => signature,
+        None => match env::var("HYPRLAND_INSTANCE_SIGNATURE") {
+            Ok(signature) if !signature.is_empty() => return Ok(Some(signature)),
+            _ => {
+                let instances = hyprman::hypr::instances()?;
+                let Some(newest) = instances.first() else {
+                    return Ok(None);
+                };
+                if instances.len() > 1 {
+                    warn!(
+                        "Found {} Hyprland instances, using the newest, {}; \
+                         pick another with --instance",
+                        instances.len(),
+                        newest.signature
+                    );
+                }
+                newest.signature.clone()
+            }
+        },
+    };
+    if signature.is_empty() || signature.contains('/') {
+        return Err(HyprmanError::Usage(format!(
+            "invalid instance signature '{}'",
+            signature
+        )));
+    }
+    // SAFETY: called from main before any thread is spawned.
+    unsafe { env::set_var("HYPRLAND_INSTANCE_SIGNATURE", &signature) };
+    Ok(Some(signature))
+}
+
+/// Prints the build, protocol version and Hyprland event set, identifying
+/// the build in bug reports.
+fn print_version() {
     println!(
-        "If no options are provided, Hyprman runs in client mode with the 'all' subscription."
+        "hyprman {} ({}, built {})",
+        env!("CARGO_PKG_VERSION"),
+        env!("HYPRMAN_GIT_COMMIT"),
+        env!("HYPRMAN_BUILD_DATE")
     );
+    println!("Protocol version: {}", PROTOCOL_VERSION);
+    println!("Hyprland events: up to {}", HYPRLAND_EVENT_SET);
 }
 
-/// === Main Entry Point: Mode Selection Based on Command‑Line Arguments ===
+// === Main Entry Point: Mode Selection Based on Command‑Line Arguments ===
 
 fn main() {
-    // Load configuration from $XDG_CONFIG_HOME/hyprman/config.toml
-    let config_dir = env::var("XDG_CONFIG_HOME")
-        .unwrap_or_else(|_| panic!("Environment variable XDG_CONFIG_HOME is not set"));
-    let config_path = format!("{}/hyprman/config.toml", config_dir);
-    let mut config = load_config(&config_path);
-    env_logger::init();
-
-    // Ensure $XDG_RUNTIME_DIR/hyprman/ exists.
-    let xdg_runtime_dir = env::var("XDG_RUNTIME_DIR").expect("XDG_RUNTIME_DIR not set");
-    let hyprman_dir = format!("{}/hyprman", xdg_runtime_dir);
+    if let Err(e) = run() {
+        eprintln!("hyprman: {}", e);
+        if let HyprmanError::Usage(_) = e {
+            eprintln!("Run 'hyprman help' for usage.");
+        }
+        std::process::exit(e.exit_code());
+    }
+}
+
+fn run() -> Result<()> {
+    let cli = cli::parse(env::args().skip(1))?;
+    // Documentation and identifying the build must work even without a
+    // session or config.
+    match cli.command {
+        cli::Command::Version => print_version(),
+        cli::Command::Schema => println!(
+            "{}",
+            serde_json::to_string_pretty(&schema::envelope_schema()).unwrap()
+        ),
+        cli::Command::Completions(shell) => completions::print_completions(shell),
+        cli::Command::Manpage => usage::print_manpage(),
+        cli::Command::Help(None) => usage::print_help(),
+        cli::Command::Help(Some(command)) => usage::print_command_help(command),
+        _ => return run_command(cli),
+    }
+    Ok(())
+}
+
+fn run_command(mut cli: cli::Cli) -> Result<()> {
+    let mut output = output::Output::default();
+    if let Some(file) = &cli.output {
+        output.set_file(file.clone());
+    }
+    if cli.dedupe_output {
+        output.dedupe();
+    }
+
+    // Load configuration from --config or $XDG_CONFIG_HOME/hyprman/config.toml.
+    // The daemon changes into / and reloads later, so keep the path absolute.
+    let config_path = match &cli.config {
+        Some(path) if !path.starts_with('/') => env::current_dir()
+            .map(|dir| dir.join(path).to_string_lossy().into_owned())
+            .unwrap_or_else(|_| path.clone()),
+        Some(path) => path.clone(),
+        None => config_path()?,
+    };
+    let mut config = try_load_config(&config_path, cli.profile.as_deref())?;
+    // The daemon logs to a file once daemonized, which points stderr at
+    // /dev/null; the logger is set up before that so it can report failures.
+    let (log_target, log_level) = match cli.command {
+        cli::Command::Daemon { foreground, .. } => (
+            config.log_target.unwrap_or(match foreground {
+                true => logging::LogTarget::Stderr,
+                false => logging::LogTarget::File,
+            }),
+            logging::DAEMON_LEVEL,
+        ),
+        _ => (logging::LogTarget::Stderr, logging::CLIENT_LEVEL),
+    };
+    logging::init(
+        log_target,
+        config.log_level.as_deref().unwrap_or(log_level),
+        &config.logging,
+    )?;
+    if cli.is_client_mode()
+        && let Ok(socket) = env::var("HYPRMAN_SOCKET")
+        && !socket.is_empty()
+    {
+        config.client_socket_path = socket;
+    }
+    set_socket_paths(
+        cli.socket1.take().or(config.hyprland_socket1_path.take()),
+        cli.socket2.take().or(config.hyprland_socket2_path.take()),
+    );
+
+    // Ensure $XDG_RUNTIME_DIR/hyprman/<instance>/ exists.
+    let xdg_runtime_dir = paths::runtime_dir()?;
+    let hyprman_dir = match resolve_instance(cli.instance.take())? {
+        Some(signature) => format!("{}/hyprman/{}", xdg_runtime_dir, signature),
+        None => format!("{}/hyprman", xdg_runtime_dir),
+    };
     if fs::metadata(&hyprman_dir).is_err() {
-        fs::create_dir_all(&hyprman_dir).expect("Failed to create hyprman runtime directory");
+        fs::create_dir_all(&hyprman_dir)?;
     }
     // If the socket path from the config is relative, interpret it relative to hyprman_dir.
     if !config.client_socket_path.starts_with("/") {
         config.client_socket_path = format!("{}/{}", hyprman_dir, config.client_socket_path);
     }
+    if let Some(raw_socket_path) = &mut config.raw_socket_path
+        && !raw_socket_path.starts_with("/")
+    {
+        *raw_socket_path = format!("{}/{}", hyprman_dir, raw_socket_path);
+    }
+    if let Some(sandbox_socket_path) = &mut config.sandbox_socket_path
+        && !sandbox_socket_path.starts_with("/")
+    {
+        *sandbox_socket_path = format!("{}/{}", xdg_runtime_dir, sandbox_socket_path);
+    }
+    if let Some(audit_log) = &mut config.audit_log
+        && !audit_log.starts_with("/")
+    {
+        *audit_log = format!("{}/{}", hyprman_dir, audit_log);
+    }
+    if !config.control_socket_path.starts_with("/") {
+        config.control_socket_path = format!("{}/{}", hyprman_dir, config.control_socket_path);
+    }
 
     // Also, compute the PID file path to be used.
     let pid_file_path = format!("{}/hyprman.pid", hyprman_dir);
+    if cli.is_client_mode() && config.auto_spawn_daemon {
+        spawn_daemon(
+            &config,
+            &pid_file_path,
+            &config_path,
+            cli.profile.as_deref(),
+        )?;
+    }
 
-    let args: Vec<String> = env::args().collect();
-    if args.len() > 1 {
-        match args[1].as_str() {
-            "-d" | "--daemon" => {
-                // Check if daemon is already running.
-                if let Ok(pid_str) = fs::read_to_string(&pid_file_path) {
-                    if let Ok(pid) = pid_str.trim().parse::<i32>() {
-                        if unsafe { libc::kill(pid, 0) } == 0 {
-                            eprintln!("Daemon already running with PID {}.", pid);
-                            std::process::exit(1);
-                        }
-                    }
-                }
-                let daemonize = Daemonize::new()
-                    .pid_file(&pid_file_path)
-                    .working_directory("/")
-                    .umask(0o022)
-                    .privileged_action(|| {
-                        info!("Daemon started successfully");
-                    });
-                if let Err(e) = daemonize.start() {
-                    eprintln!("Error daemonizing: {}", e);
-                    std::process::exit(1);
-                }
-                run_daemon(config);
-            }
-            "-r" | "--restart" => {
-                if let Err(e) = restart_daemon() {
-                    eprintln!("Error restarting daemon: {}", e);
-                    std::process::exit(1);
-                }
-            }
-            "-k" | "--kill" => {
-                if let Err(e) = stop_daemon() {
-                    eprintln!("Error stopping daemon: {}", e);
-                    std::process::exit(1);
-                }
-            }
-            "-f" | "--filter" => {
-                // Client mode with a subscription filter.
-                let filter = if args.len() > 2 {
-                    args[2].clone()
-                } else {
-                    "all".to_string()
-                };
-                run_client(&config, &filter);
-            }
-            "-a" | "--activewindow" => {
-                run_activewindow_client(&config);
-            }
-            "-w" | "--workspaces" => {
-                run_workspaces_client(&config);
-            }
-            "-h" | "--help" => {
-                print_help();
-            }
-            _ => {
-                eprintln!("Unknown option.");
-                print_help();
-                std::process::exit(1);
+    match cli.command {
+        cli::Command::Daemon {
+            rules_dry_run,
+            foreground: true,
+        } => {
+            let _lock = lock::InstanceLock::acquire(&pid_file_path)?;
+            unsafe { libc::umask(config.umask as libc::mode_t) };
+            info!("Running in the foreground");
+            run_daemon(config, config_path, hyprman_dir, rules_dry_run)
+        }
+        cli::Command::Daemon { rules_dry_run, .. } => {
+            // Locked before forking so a second daemon fails on the terminal;
+            // the child inherits the lock and keeps it after the parent exits.
+            let mut lock = lock::InstanceLock::acquire(&pid_file_path)?;
+            let daemonize = Daemonize::new()
+                .working_directory("/")
+                .umask(config.umask)
+                .privileged_action(|| {
+                    info!("Daemon started successfully");
+                });
+            daemonize
+                .start()
+                .map_err(|e| HyprmanError::Daemon(format!("failed to daemonize: {}", e)))?;
+            lock.write_pid()?;
+            run_daemon(config, config_path, hyprman_dir, rules_dry_run)
+        }
+        cli::Command::Restart => {
+            restart_daemon(&pid_file_path, &config_path, cli.profile.as_deref())
+        }
+        cli::Command::Kill => stop_daemon(&pid_file_path),
+        cli::Command::Client { filter } => run_client(&config, &filter, output),
+        cli::Command::ActiveWindow { fields } => {
+            if fields.is_some() {
+                config.activewindow.fields = fields;
             }
+            activewindow::run_activewindow_client(&config, output)
+        }
+        cli::Command::Workspaces => workspaces::run_workspaces_client(config, output),
+        cli::Command::Groups => groups::run_groups_client(config, output),
+        cli::Command::Control(line) => control::run_control_command(&config, &line),
+        cli::Command::Check => check::run_check(&config, &config_path),
+        cli::Command::Trace => trace::run_trace(config.key_case),
+        cli::Command::Version
+        | cli::Command::Schema
+        | cli::Command::Completions(_)
+        | cli::Command::Manpage
+        | cli::Command::Help(_) => {
+            unreachable!("handled before loading the config")
         }
-    } else {
-        // No arguments provided: run as client with "all" subscription.
-        run_client(&config, "all");
     }
 }
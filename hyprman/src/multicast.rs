@@ -0,0 +1,99 @@
+use crate::{check::Findings, is_opt_in, output::KeyCase};
+use hyprman::event::{Envelope, event_type};
+use log::{error, info};
+use serde::Deserialize;
+use std::{
+    io,
+    net::{Ipv4Addr, SocketAddrV4, UdpSocket},
+};
+
+// === UDP Multicast Sink ===
+
+/// Broadcasts events as JSON datagrams, configured as `[multicast]`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MulticastConfig {
+    // Multicast group and port, e.g. "239.255.42.99:4242".
+    group: SocketAddrV4,
+    // Hops the datagrams may travel; 1 keeps them on the local network.
+    #[serde(default = "default_ttl")]
+    ttl: u32,
+    // Address of the interface to send from; the default route if unset.
+    #[serde(default)]
+    interface: Option<Ipv4Addr>,
+    // Event names to broadcast; every event a plain `all` client gets if empty.
+    #[serde(default)]
+    events: Vec<String>,
+}
+
+impl MulticastConfig {
+    pub fn check(&self, findings: &mut Findings) {
+        for event in &self.events {
+            findings.check_event("multicast.events", event);
+        }
+    }
+}
+
+fn default_ttl() -> u32 {
+    1
+}
+
+pub struct MulticastSink {
+    socket: UdpSocket,
+    group: SocketAddrV4,
+    events: Vec<String>,
+    keys: KeyCase,
+}
+
+impl MulticastSink {
+    pub fn new(config: MulticastConfig, keys: KeyCase) -> io::Result<MulticastSink> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+        socket.set_multicast_ttl_v4(config.ttl)?;
+        socket.set_multicast_loop_v4(true)?;
+        if let Some(interface) = config.interface {
+            set_multicast_interface(&socket, interface)?;
+        }
+        info!("Broadcasting events to {}", config.group);
+        Ok(MulticastSink {
+            socket,
+            group: config.group,
+            events: config.events,
+            keys,
+        })
+    }
+
+    pub fn send(&self, envelope: &Envelope) {
+        let wanted = if self.events.is_empty() {
+            !is_opt_in(&envelope.event)
+        } else {
+            let name = event_type(&envelope.event);
+            self.events.iter().any(|e| e == name)
+        };
+        if !wanted {
+            return;
+        }
+        let json = self.keys.to_json(envelope);
+        if let Err(e) = self.socket.send_to(json.as_bytes(), self.group) {
+            error!("Failed to send event to {}: {}", self.group, e);
+        }
+    }
+}
+
+fn set_multicast_interface(socket: &UdpSocket, interface: Ipv4Addr) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+    let addr = libc::in_addr {
+        s_addr: u32::from(interface).to_be(),
+    };
+    let ret = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::IPPROTO_IP,
+            libc::IP_MULTICAST_IF,
+            &addr as *const libc::in_addr as *const libc::c_void,
+            size_of::<libc::in_addr>() as libc::socklen_t,
+        )
+    };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
@@ -0,0 +1,192 @@
+use hyprman::{
+    error::Result,
+    event::{Address, HyprlandEvent},
+    hypr::{Workspace, query_clients, query_workspaces},
+};
+use log::warn;
+use std::collections::{BTreeSet, HashMap};
+
+// === Workspace Details for Enriched Events ===
+
+/// Every workspace with its monitor and window count as `j/workspaces`
+/// reports them, so workspace events can carry them to clients that
+/// subscribed with `enrich`. Re-read from socket1 on the first lookup after
+/// windows or workspaces changed.
+#[derive(Debug, Default)]
+pub struct Occupancy {
+    workspaces: HashMap<i64, Workspace>,
+    stale: bool,
+}
+
+impl Occupancy {
+    /// Notes whether `event` may have changed any workspace and, if `lookup`
+    /// is set, returns the workspace it is about.
+    pub fn handle(&mut self, event: &HyprlandEvent, lookup: bool) -> Option<Box<Workspace>> {
+        if matches!(
+            event,
+            HyprlandEvent::OpenWindow { .. }
+                | HyprlandEvent::CloseWindow { .. }
+                | HyprlandEvent::MoveWindowV2 { .. }
+                | HyprlandEvent::CreateWorkspaceV2 { .. }
+                | HyprlandEvent::DestroyWorkspaceV2 { .. }
+                | HyprlandEvent::MoveWorkspaceV2 { .. }
+                | HyprlandEvent::RenameWorkspace { .. }
+                | HyprlandEvent::MonitorAddedV2 { .. }
+                | HyprlandEvent::MonitorRemovedV2 { .. }
+                | HyprlandEvent::HyprlandRestarted { .. }
+        ) {
+            self.stale = true;
+        }
+        let workspace_id = match event {
+            HyprlandEvent::WorkspaceV2 { workspace_id, .. }
+            | HyprlandEvent::CreateWorkspaceV2 { workspace_id, .. }
+            | HyprlandEvent::MoveWorkspaceV2 { workspace_id, .. }
+            | HyprlandEvent::FocusedMonV2 { workspace_id, .. }
+            | HyprlandEvent::FocusedMonChanged { workspace_id, .. } => *workspace_id,
+            _ => return None,
+        };
+        if !lookup {
+            return None;
+        }
+        if self.stale || !self.workspaces.contains_key(&workspace_id) {
+            match query_workspaces() {
+                Ok(workspaces) => {
+                    self.workspaces = workspaces.into_iter().map(|w| (w.id, w)).collect();
+                    self.stale = false;
+                }
+                Err(e) => warn!("Could not query workspaces to enrich events: {}", e),
+            }
+        }
+        self.workspaces.get(&workspace_id).cloned().map(Box::new)
+    }
+}
+
+// === Empty and Occupied Workspaces ===
+
+/// The workspace of every mapped window, followed through events to report
+/// workspaces gaining their first or losing their last window.
+#[derive(Debug, Default)]
+pub struct WorkspaceWindows {
+    windows: HashMap<Address, i64>,
+    /// Workspace names by id.
+    names: HashMap<i64, String>,
+}
+
+impl WorkspaceWindows {
+    pub fn query() -> Result<WorkspaceWindows> {
+        let names = query_workspaces()?
+            .into_iter()
+            .map(|w| (w.id, w.name))
+            .collect();
+        let windows = query_clients()?
+            .into_values()
+            .filter(|c| c.mapped)
+            .map(|c| (c.address, c.workspace.id))
+            .collect();
+        Ok(WorkspaceWindows { windows, names })
+    }
+
+    /// Updates the windows and returns a `WorkspaceEmpty` or
+    /// `WorkspaceOccupied` event for every workspace that lost its last or
+    /// gained its first window.
+    pub fn handle(&mut self, event: &HyprlandEvent) -> Vec<HyprlandEvent> {
+        let before = self.occupied();
+        match event {
+            HyprlandEvent::OpenWindow {
+                window_address,
+                workspace_name,
+                ..
+            } => match self.workspace_id(workspace_name) {
+                Some(workspace_id) => {
+                    self.windows.insert(window_address.clone(), workspace_id);
+                }
+                None => warn!("Window opened on unknown workspace {}", workspace_name),
+            },
+            HyprlandEvent::CloseWindow { window_address } => {
+                self.windows.remove(window_address);
+            }
+            HyprlandEvent::MoveWindowV2 {
+                window_address,
+                workspace_id,
+                workspace_name,
+            } => {
+                self.names.insert(*workspace_id, workspace_name.clone());
+                self.windows.insert(window_address.clone(), *workspace_id);
+            }
+            HyprlandEvent::CreateWorkspaceV2 {
+                workspace_id,
+                workspace_name,
+            }
+            | HyprlandEvent::WorkspaceV2 {
+                workspace_id,
+                workspace_name,
+            } => {
+                self.names.insert(*workspace_id, workspace_name.clone());
+            }
+            HyprlandEvent::RenameWorkspace {
+                workspace_id,
+                new_name,
+            } => {
+                self.names.insert(*workspace_id, new_name.clone());
+            }
+            HyprlandEvent::DestroyWorkspaceV2 { workspace_id, .. } => {
+                self.names.remove(workspace_id);
+                self.windows.retain(|_, id| id != workspace_id);
+                return Vec::new();
+            }
+            // The new instance starts over; nothing changed in between.
+            HyprlandEvent::HyprlandRestarted { .. } => {
+                match WorkspaceWindows::query() {
+                    Ok(current) => *self = current,
+                    Err(e) => warn!("Could not query workspace windows: {}", e),
+                }
+                return Vec::new();
+            }
+            _ => return Vec::new(),
+        }
+        let after = self.occupied();
+        let change = |workspace_id: &i64, occupied: bool| {
+            let workspace_id = *workspace_id;
+            let workspace_name = self.names.get(&workspace_id).cloned().unwrap_or_default();
+            if occupied {
+                HyprlandEvent::WorkspaceOccupied {
+                    workspace_id,
+                    workspace_name,
+                }
+            } else {
+                HyprlandEvent::WorkspaceEmpty {
+                    workspace_id,
+                    workspace_name,
+                }
+            }
+        };
+        before
+            .difference(&after)
+            .map(|id| change(id, false))
+            .chain(after.difference(&before).map(|id| change(id, true)))
+            .collect()
+    }
+
+    fn occupied(&self) -> BTreeSet<i64> {
+        self.windows.values().copied().collect()
+    }
+
+    /// The id of a workspace by name, asking socket1 for workspaces not
+    /// seen in an event yet.
+    fn workspace_id(&mut self, name: &str) -> Option<i64> {
+        let find = |names: &HashMap<i64, String>| {
+            names
+                .iter()
+                .find(|(_, n)| n.as_str() == name)
+                .map(|(id, _)| *id)
+        };
+        if let Some(id) = find(&self.names) {
+            return Some(id);
+        }
+        match query_workspaces() {
+            Ok(workspaces) => self.names = workspaces.into_iter().map(|w| (w.id, w.name)).collect(),
+            Err(e) => warn!("Could not query workspaces: {}", e),
+        }
+        find(&self.names)
+    }
+}
@@ -0,0 +1,228 @@
+use log::error;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{fs, io, path::PathBuf, process};
+
+// === Client Mode Output Helpers ===
+
+/// Where client modes write what they render.
+#[derive(Debug, Default)]
+pub struct Output {
+    /// Set with `--output`: replaced on every update, for bars and scripts
+    /// that poll a file, instead of printing lines to stdout.
+    file: Option<PathBuf>,
+    /// Set with `--dedupe-output`: skip updates equal to the previous one.
+    dedupe: bool,
+    last: Option<String>,
+}
+
+impl Output {
+    pub fn set_file(&mut self, file: String) {
+        self.file = Some(PathBuf::from(file));
+    }
+
+    pub fn dedupe(&mut self) {
+        self.dedupe = true;
+    }
+
+    /// Prints `line`, or replaces the output file with it so readers never
+    /// see a partial document.
+    pub fn write(&mut self, line: &str) {
+        if self.dedupe {
+            if self.last.as_deref() == Some(line) {
+                return;
+            }
+            self.last = Some(line.to_string());
+        }
+        let Some(file) = &self.file else {
+            println!("{}", line);
+            return;
+        };
+        if let Err(e) = replace_file(file, line) {
+            error!("Failed to write {}: {}", file.display(), e);
+        }
+    }
+}
+
+/// Writes a temporary file next to `file` and renames it into place.
+fn replace_file(file: &PathBuf, line: &str) -> io::Result<()> {
+    let name = file
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file path"))?;
+    let temp = file.with_file_name(format!(".{}.{}.tmp", name.to_string_lossy(), process::id()));
+    fs::write(&temp, format!("{}\n", line))?;
+    fs::rename(&temp, file).inspect_err(|_| {
+        let _ = fs::remove_file(&temp);
+    })
+}
+
+/// Casing of the keys in the JSON hyprman writes, configured as `key_case`
+/// and per client with `keys=` in the subscription line.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+pub enum KeyCase {
+    /// As declared: event fields in snake_case, Hyprland's own objects such
+    /// as clients and workspaces in camelCase.
+    #[default]
+    #[serde(rename = "mixed")]
+    Mixed,
+    #[serde(rename = "snake_case")]
+    Snake,
+    #[serde(rename = "camelCase")]
+    Camel,
+}
+
+impl KeyCase {
+    pub fn parse(name: &str) -> Option<KeyCase> {
+        match name {
+            "mixed" => Some(KeyCase::Mixed),
+            "snake_case" => Some(KeyCase::Snake),
+            "camelCase" => Some(KeyCase::Camel),
+            _ => None,
+        }
+    }
+
+    /// Serializes `value` with every object key renamed.
+    pub fn to_json<T: Serialize>(self, value: &T) -> String {
+        if self == KeyCase::Mixed {
+            return serde_json::to_string(value).unwrap();
+        }
+        self.apply(serde_json::to_value(value).unwrap()).to_string()
+    }
+
+    pub fn apply(self, value: Value) -> Value {
+        match value {
+            Value::Object(object) if self != KeyCase::Mixed => Value::Object(
+                object
+                    .into_iter()
+                    .map(|(key, value)| (self.rename(&key), self.apply(value)))
+                    .collect(),
+            ),
+            Value::Array(items) => {
+                Value::Array(items.into_iter().map(|item| self.apply(item)).collect())
+            }
+            value => value,
+        }
+    }
+
+    fn rename(self, key: &str) -> String {
+        let chars: Vec<char> = key.chars().collect();
+        let mut renamed = String::with_capacity(key.len() + 4);
+        for (i, &c) in chars.iter().enumerate() {
+            match self {
+                KeyCase::Mixed => renamed.push(c),
+                // Runs of capitals such as `ID` count as one word.
+                KeyCase::Snake if c.is_uppercase() => {
+                    let previous = i.checked_sub(1).map(|i| chars[i]);
+                    let next = chars.get(i + 1);
+                    if previous.is_some_and(|p| p.is_lowercase() || p.is_ascii_digit())
+                        || (previous.is_some_and(char::is_uppercase)
+                            && next.is_some_and(|n| n.is_lowercase()))
+                    {
+                        renamed.push('_');
+                    }
+                    renamed.extend(c.to_lowercase());
+                }
+                KeyCase::Camel if c == '_' && i > 0 => {}
+                KeyCase::Camel if i > 0 && chars[i - 1] == '_' => renamed.extend(c.to_uppercase()),
+                _ => renamed.push(c),
+            }
+        }
+        renamed
+    }
+}
+
+/// Markup escaping for text fields that bars may render as markup.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Escape {
+    #[default]
+    None,
+    /// Pango markup, as done by `g_markup_escape_text`.
+    Pango,
+    Html,
+}
+
+impl Escape {
+    pub fn apply(self, text: &str) -> String {
+        if self == Escape::None {
+            return text.to_string();
+        }
+        let mut escaped = String::with_capacity(text.len());
+        for c in text.chars() {
+            match (self, c) {
+                (_, '&') => escaped.push_str("&amp;"),
+                (_, '<') => escaped.push_str("&lt;"),
+                (_, '>') => escaped.push_str("&gt;"),
+                (_, '"') => escaped.push_str("&quot;"),
+                (Escape::Pango, '\'') => escaped.push_str("&#39;"),
+                (_, '\'') => escaped.push_str("&#x27;"),
+                (_, c) => escaped.push(c),
+            }
+        }
+        escaped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyprman::{
+        client::Connection,
+        event::{Address, Envelope, HyprlandEvent},
+    };
+    use std::{
+        env,
+        io::{BufRead, BufReader, Write},
+        os::unix::net::UnixListener,
+        thread,
+    };
+
+    #[test]
+    fn connection_reads_events_under_any_key_case() {
+        let path = env::temp_dir().join(format!("hyprman-keys-{}.sock", process::id()));
+        let _ = fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        // A daemon configured with `key_case = "camelCase"`.
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut handshake = String::new();
+            BufReader::new(stream.try_clone().unwrap())
+                .read_line(&mut handshake)
+                .unwrap();
+            let keys = handshake
+                .split(',')
+                .rev()
+                .find_map(|token| token.trim().strip_prefix("keys=").and_then(KeyCase::parse))
+                .unwrap_or(KeyCase::Camel);
+            let metadata = HyprlandEvent::Metadata {
+                hyprland_version: None,
+                instance_signature: "test".to_string(),
+                hyprman_version: "0.1.0".to_string(),
+                protocol_version: 2,
+                seq: 0,
+                capabilities: vec!["dispatch".to_string()],
+            };
+            let event = HyprlandEvent::ActiveWindowV2 {
+                window_address: Address::from("5f3a"),
+            };
+            writeln!(stream, "{}", keys.to_json(&metadata)).unwrap();
+            writeln!(stream, "{}", keys.to_json(&Envelope::new(1, event))).unwrap();
+        });
+        let mut connection = Connection::connect(path.to_str().unwrap(), "all").unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert!(connection.supports("dispatch"));
+        match connection.next_event().unwrap() {
+            Some(HyprlandEvent::ActiveWindowV2 { window_address }) => {
+                assert_eq!(window_address, Address::from("5f3a"))
+            }
+            other => panic!("expected an ActiveWindowV2, got {:?}", other),
+        }
+        assert_eq!(
+            KeyCase::Camel.to_json(&HyprlandEvent::ActiveWindowV2 {
+                window_address: Address::from("5f3a"),
+            }),
+            r#"{"data":{"windowAddress":"5f3a"},"event":"ActiveWindowV2"}"#
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use hyprman::event::HyprlandEvent;
+use std::collections::HashMap;
+
+// === Overlay Layers ===
+
+/// Open layer-shell surfaces of the configured namespaces, e.g. launchers
+/// and OSDs, turned into `OverlayShown`/`OverlayHidden` when the first one
+/// opens and the last one closes.
+pub struct Overlays {
+    namespaces: Vec<String>,
+    open: HashMap<String, usize>,
+}
+
+impl Overlays {
+    pub fn new(namespaces: Vec<String>) -> Overlays {
+        Overlays {
+            namespaces,
+            open: HashMap::new(),
+        }
+    }
+
+    /// Swaps in reloaded namespaces; overlays of dropped ones are forgotten
+    /// without an `OverlayHidden`.
+    pub fn set_namespaces(&mut self, namespaces: Vec<String>) {
+        self.open
+            .retain(|namespace, _| namespaces.contains(namespace));
+        self.namespaces = namespaces;
+    }
+
+    /// Returns the overlay event `event` causes, if any.
+    pub fn handle(&mut self, event: &HyprlandEvent) -> Option<HyprlandEvent> {
+        match event {
+            HyprlandEvent::OpenLayer { namespace } if self.namespaces.contains(namespace) => {
+                let open = self.open.entry(namespace.clone()).or_default();
+                *open += 1;
+                (*open == 1).then(|| HyprlandEvent::OverlayShown {
+                    namespace: namespace.clone(),
+                })
+            }
+            HyprlandEvent::CloseLayer { namespace } => {
+                let open = self.open.get_mut(namespace)?;
+                *open -= 1;
+                if *open > 0 {
+                    return None;
+                }
+                self.open.remove(namespace);
+                Some(HyprlandEvent::OverlayHidden {
+                    namespace: namespace.clone(),
+                })
+            }
+            _ => None,
+        }
+    }
+}
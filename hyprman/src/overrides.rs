@@ -0,0 +1,98 @@
+use log::info;
+use std::env;
+use toml::{Table, Value};
+
+// === Config Profiles ===
+
+/// Takes the `[profile.NAME]` sections out of the table and merges the one
+/// selected with `--profile` over the rest. Sections are merged key by key;
+/// arrays such as `[[hooks]]` replace those of the file. A plain `profile =
+/// "NAME"` is the initial hook profile and is left alone.
+pub fn apply_profile(table: &mut Table, name: Option<&str>) -> Result<(), String> {
+    let profiles = match table.get("profile") {
+        Some(Value::Table(_)) => table.remove("profile"),
+        _ => None,
+    };
+    let Some(name) = name else {
+        return Ok(());
+    };
+    let profile = match profiles {
+        Some(Value::Table(mut profiles)) => profiles.remove(name),
+        _ => None,
+    };
+    match profile {
+        Some(Value::Table(profile)) => {
+            merge(table, profile);
+            Ok(())
+        }
+        _ => Err(format!("no [profile.{}] section", name)),
+    }
+}
+
+fn merge(base: &mut Table, layer: Table) {
+    for (key, value) in layer {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base)), Value::Table(layer)) => merge(base, layer),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+// === Environment Overrides of Config Keys ===
+
+/// Prefix of the variables overriding config keys, e.g. `HYPRMAN_LOG_LEVEL`.
+const PREFIX: &str = "HYPRMAN_";
+
+/// Variables with the prefix that mean something else.
+const RESERVED: &[&str] = &["HYPRMAN_SOCKET"];
+
+/// Sets the keys named by `HYPRMAN_*` variables on top of those read from the
+/// config file. A double underscore separates a section from its keys, as in
+/// `HYPRMAN_LOGGING__MAX_SIZE`. Values are read as TOML, falling back to a
+/// plain string, so `HYPRMAN_NICE=5` is a number and `HYPRMAN_LOG_LEVEL=debug`
+/// a string.
+pub fn apply(table: &mut Table) {
+    for (name, value) in env::vars() {
+        let Some(key) = name.strip_prefix(PREFIX) else {
+            continue;
+        };
+        if key.is_empty() || RESERVED.contains(&name.as_str()) {
+            continue;
+        }
+        let path: Vec<String> = key.to_lowercase().split("__").map(String::from).collect();
+        info!("Config key {} set by ${}", path.join("."), name);
+        set(table, &path, parse_value(&value));
+    }
+}
+
+fn parse_value(value: &str) -> Value {
+    format!("value = {}", value)
+        .parse::<Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::String(value.to_string()))
+}
+
+/// Inserts a value at a dotted path, creating or replacing the sections on
+/// the way.
+fn set(table: &mut Table, path: &[String], value: Value) {
+    let Some((key, sections)) = path.split_last() else {
+        return;
+    };
+    let mut table = table;
+    for section in sections {
+        let entry = table
+            .entry(section.clone())
+            .or_insert_with(|| Value::Table(Table::new()));
+        if !entry.is_table() {
+            *entry = Value::Table(Table::new());
+        }
+        table = match entry {
+            Value::Table(inner) => inner,
+            _ => unreachable!("replaced by a table above"),
+        };
+    }
+    table.insert(key.clone(), value);
+}
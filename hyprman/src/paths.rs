@@ -0,0 +1,47 @@
+//! XDG base directories, resolved as the XDG Base Directory Specification
+//! asks: a variable that is unset, empty or holds a relative path is
+//! ignored in favour of the default.
+
+use crate::error::{HyprmanError, Result};
+use log::warn;
+use std::{env, path::Path};
+
+/// A variable holding an absolute path.
+fn absolute_var(name: &str) -> Option<String> {
+    env::var(name)
+        .ok()
+        .filter(|value| Path::new(value).is_absolute())
+}
+
+/// `$name`, or `subdir` of the home directory.
+fn base_dir(name: &str, subdir: &str) -> Result<String> {
+    if let Some(dir) = absolute_var(name) {
+        return Ok(dir);
+    }
+    let home = absolute_var("HOME").ok_or(HyprmanError::Environment("HOME"))?;
+    Ok(format!("{}/{}", home, subdir))
+}
+
+/// `$XDG_CONFIG_HOME`, defaulting to `~/.config`.
+pub fn config_home() -> Result<String> {
+    base_dir("XDG_CONFIG_HOME", ".config")
+}
+
+/// `$XDG_STATE_HOME`, defaulting to `~/.local/state`.
+pub fn state_home() -> Result<String> {
+    base_dir("XDG_STATE_HOME", ".local/state")
+}
+
+/// `$XDG_RUNTIME_DIR`. The specification has no default, so without it the
+/// directory systemd-logind creates for the user is used if it exists.
+pub fn runtime_dir() -> Result<String> {
+    if let Some(dir) = absolute_var("XDG_RUNTIME_DIR") {
+        return Ok(dir);
+    }
+    let fallback = format!("/run/user/{}", unsafe { libc::getuid() });
+    if Path::new(&fallback).is_dir() {
+        warn!("XDG_RUNTIME_DIR is not set, using {}", fallback);
+        return Ok(fallback);
+    }
+    Err(HyprmanError::Environment("XDG_RUNTIME_DIR"))
+}
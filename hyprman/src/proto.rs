@@ -0,0 +1,525 @@
+//! Protocol Buffers encoding of events, matching `proto/hyprman.proto`.
+//!
+//! Messages are produced from the serde representation: struct fields are
+//! numbered in declaration order starting at 1, and the variants of
+//! `HyprlandEvent` map to the `oneof` fields of `Event` in declaration order.
+//! New fields and variants therefore have to be appended at the end.
+
+use crate::event::Envelope;
+use serde::{
+    Serialize,
+    ser::{self, Impossible},
+};
+use std::fmt;
+
+// === Wire Format ===
+
+const VARINT: u32 = 0;
+const FIXED64: u32 = 1;
+const LEN: u32 = 2;
+const FIXED32: u32 = 5;
+
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn put_tag(out: &mut Vec<u8>, field: u32, wire_type: u32) {
+    put_varint(out, u64::from(field << 3 | wire_type));
+}
+
+fn put_len(out: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+    put_tag(out, field, LEN);
+    put_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+/// Encodes an envelope as an `Envelope` message.
+pub fn encode_envelope(envelope: &Envelope) -> Result<Vec<u8>, EncodeError> {
+    let mut out = Vec::new();
+    put_tag(&mut out, 1, VARINT);
+    put_varint(&mut out, envelope.seq);
+    put_len(&mut out, 2, &encode_message(&envelope.event)?);
+    put_tag(&mut out, 3, VARINT);
+    put_varint(&mut out, envelope.timestamp);
+    if let Some(window) = &envelope.window {
+        put_len(&mut out, 4, &encode_message(window)?);
+    }
+    if let Some(workspace) = &envelope.workspace {
+        put_len(&mut out, 5, &encode_message(workspace)?);
+    }
+    Ok(out)
+}
+
+/// Encodes an envelope prefixed with its length as a varint, the framing
+/// used on the client socket (`writeDelimitedTo` in most protobuf runtimes).
+pub fn encode_envelope_delimited(envelope: &Envelope) -> Result<Vec<u8>, EncodeError> {
+    let message = encode_envelope(envelope)?;
+    let mut out = Vec::with_capacity(message.len() + 5);
+    put_varint(&mut out, message.len() as u64);
+    out.extend_from_slice(&message);
+    Ok(out)
+}
+
+/// Encodes any struct as a message body without a tag.
+pub fn encode_message<T: Serialize>(value: &T) -> Result<Vec<u8>, EncodeError> {
+    let mut out = Vec::new();
+    value.serialize(FieldSerializer {
+        out: &mut out,
+        field: 0,
+    })?;
+    Ok(out)
+}
+
+#[derive(Debug)]
+pub struct EncodeError(String);
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "protobuf encoding failed: {}", self.0)
+    }
+}
+
+impl std::error::Error for EncodeError {}
+
+impl ser::Error for EncodeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        EncodeError(msg.to_string())
+    }
+}
+
+fn unsupported(what: &str) -> EncodeError {
+    EncodeError(format!("{} has no protobuf mapping", what))
+}
+
+// === Serializer ===
+
+/// Writes one value as field `field`; field 0 is the unframed top level.
+struct FieldSerializer<'a> {
+    out: &'a mut Vec<u8>,
+    field: u32,
+}
+
+impl FieldSerializer<'_> {
+    fn varint(self, value: u64) -> Result<(), EncodeError> {
+        put_tag(self.out, self.field, VARINT);
+        put_varint(self.out, value);
+        Ok(())
+    }
+}
+
+impl<'a> ser::Serializer for FieldSerializer<'a> {
+    type Ok = ();
+    type Error = EncodeError;
+    type SerializeSeq = RepeatedSerializer<'a>;
+    type SerializeTuple = RepeatedSerializer<'a>;
+    type SerializeTupleStruct = RepeatedSerializer<'a>;
+    type SerializeTupleVariant = Impossible<(), EncodeError>;
+    type SerializeMap = Impossible<(), EncodeError>;
+    type SerializeStruct = MessageSerializer<'a>;
+    type SerializeStructVariant = Impossible<(), EncodeError>;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<(), EncodeError> {
+        self.varint(u64::from(v))
+    }
+    // Negative integers are sign extended, as for `int32`/`int64`.
+    fn serialize_i8(self, v: i8) -> Result<(), EncodeError> {
+        self.varint(i64::from(v) as u64)
+    }
+    fn serialize_i16(self, v: i16) -> Result<(), EncodeError> {
+        self.varint(i64::from(v) as u64)
+    }
+    fn serialize_i32(self, v: i32) -> Result<(), EncodeError> {
+        self.varint(i64::from(v) as u64)
+    }
+    fn serialize_i64(self, v: i64) -> Result<(), EncodeError> {
+        self.varint(v as u64)
+    }
+    fn serialize_u8(self, v: u8) -> Result<(), EncodeError> {
+        self.varint(u64::from(v))
+    }
+    fn serialize_u16(self, v: u16) -> Result<(), EncodeError> {
+        self.varint(u64::from(v))
+    }
+    fn serialize_u32(self, v: u32) -> Result<(), EncodeError> {
+        self.varint(u64::from(v))
+    }
+    fn serialize_u64(self, v: u64) -> Result<(), EncodeError> {
+        self.varint(v)
+    }
+    fn serialize_f32(self, v: f32) -> Result<(), EncodeError> {
+        put_tag(self.out, self.field, FIXED32);
+        self.out.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+    fn serialize_f64(self, v: f64) -> Result<(), EncodeError> {
+        put_tag(self.out, self.field, FIXED64);
+        self.out.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+    fn serialize_char(self, v: char) -> Result<(), EncodeError> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+    fn serialize_str(self, v: &str) -> Result<(), EncodeError> {
+        self.serialize_bytes(v.as_bytes())
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), EncodeError> {
+        put_len(self.out, self.field, v);
+        Ok(())
+    }
+    // Absent optional fields are simply not written.
+    fn serialize_none(self) -> Result<(), EncodeError> {
+        Ok(())
+    }
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), EncodeError> {
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<(), EncodeError> {
+        Ok(())
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), EncodeError> {
+        Ok(())
+    }
+    // Plain enums map to protobuf enums numbered in declaration order.
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<(), EncodeError> {
+        self.varint(u64::from(variant_index))
+    }
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), EncodeError> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), EncodeError> {
+        Err(unsupported(name))
+    }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<RepeatedSerializer<'a>, EncodeError> {
+        Ok(RepeatedSerializer {
+            out: self.out,
+            field: self.field,
+        })
+    }
+    fn serialize_tuple(self, _len: usize) -> Result<RepeatedSerializer<'a>, EncodeError> {
+        self.serialize_seq(None)
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<RepeatedSerializer<'a>, EncodeError> {
+        self.serialize_seq(None)
+    }
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, EncodeError> {
+        Err(unsupported(name))
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, EncodeError> {
+        Err(unsupported("map"))
+    }
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<MessageSerializer<'a>, EncodeError> {
+        Ok(MessageSerializer {
+            out: self.out,
+            field: self.field,
+            name,
+            buf: Vec::new(),
+            next_field: 0,
+            variant: None,
+            content_written: false,
+        })
+    }
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, EncodeError> {
+        Err(unsupported(name))
+    }
+}
+
+/// Writes each element as another occurrence of the same field.
+struct RepeatedSerializer<'a> {
+    out: &'a mut Vec<u8>,
+    field: u32,
+}
+
+impl RepeatedSerializer<'_> {
+    fn element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), EncodeError> {
+        value.serialize(FieldSerializer {
+            out: self.out,
+            field: self.field,
+        })
+    }
+}
+
+impl ser::SerializeSeq for RepeatedSerializer<'_> {
+    type Ok = ();
+    type Error = EncodeError;
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), EncodeError> {
+        self.element(value)
+    }
+    fn end(self) -> Result<(), EncodeError> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTuple for RepeatedSerializer<'_> {
+    type Ok = ();
+    type Error = EncodeError;
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), EncodeError> {
+        self.element(value)
+    }
+    fn end(self) -> Result<(), EncodeError> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleStruct for RepeatedSerializer<'_> {
+    type Ok = ();
+    type Error = EncodeError;
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), EncodeError> {
+        self.element(value)
+    }
+    fn end(self) -> Result<(), EncodeError> {
+        Ok(())
+    }
+}
+
+/// Collects a nested message. Adjacently tagged enums arrive as a struct
+/// whose first field is a unit variant of the same name; the content that
+/// follows is written to the `oneof` field of that variant.
+struct MessageSerializer<'a> {
+    out: &'a mut Vec<u8>,
+    field: u32,
+    name: &'static str,
+    buf: Vec<u8>,
+    next_field: u32,
+    variant: Option<u32>,
+    content_written: bool,
+}
+
+impl ser::SerializeStruct for MessageSerializer<'_> {
+    type Ok = ();
+    type Error = EncodeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        _key: &'static str,
+        value: &T,
+    ) -> Result<(), EncodeError> {
+        if self.variant.is_none()
+            && let Ok(index) = value.serialize(TagProbe {
+                enum_name: self.name,
+            })
+        {
+            self.variant = Some(index);
+            return Ok(());
+        }
+        let field = match self.variant {
+            Some(index) => {
+                self.content_written = true;
+                index + 1
+            }
+            None => {
+                self.next_field += 1;
+                self.next_field
+            }
+        };
+        value.serialize(FieldSerializer {
+            out: &mut self.buf,
+            field,
+        })
+    }
+
+    fn skip_field(&mut self, _key: &'static str) -> Result<(), EncodeError> {
+        self.next_field += 1;
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<(), EncodeError> {
+        // Unit variants still select their `oneof` field with an empty message.
+        if let Some(index) = self.variant
+            && !self.content_written
+        {
+            put_len(&mut self.buf, index + 1, &[]);
+        }
+        if self.field == 0 {
+            self.out.extend_from_slice(&self.buf);
+        } else {
+            put_len(self.out, self.field, &self.buf);
+        }
+        Ok(())
+    }
+}
+
+/// Succeeds only for the tag of an adjacently tagged enum named `enum_name`,
+/// yielding the variant index.
+struct TagProbe {
+    enum_name: &'static str,
+}
+
+impl ser::Serializer for TagProbe {
+    type Ok = u32;
+    type Error = EncodeError;
+    type SerializeSeq = Impossible<u32, EncodeError>;
+    type SerializeTuple = Impossible<u32, EncodeError>;
+    type SerializeTupleStruct = Impossible<u32, EncodeError>;
+    type SerializeTupleVariant = Impossible<u32, EncodeError>;
+    type SerializeMap = Impossible<u32, EncodeError>;
+    type SerializeStruct = Impossible<u32, EncodeError>;
+    type SerializeStructVariant = Impossible<u32, EncodeError>;
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<u32, EncodeError> {
+        if name == self.enum_name {
+            Ok(variant_index)
+        } else {
+            Err(unsupported(name))
+        }
+    }
+
+    fn serialize_bool(self, _v: bool) -> Result<u32, EncodeError> {
+        Err(unsupported("tag"))
+    }
+    fn serialize_i8(self, _v: i8) -> Result<u32, EncodeError> {
+        Err(unsupported("tag"))
+    }
+    fn serialize_i16(self, _v: i16) -> Result<u32, EncodeError> {
+        Err(unsupported("tag"))
+    }
+    fn serialize_i32(self, _v: i32) -> Result<u32, EncodeError> {
+        Err(unsupported("tag"))
+    }
+    fn serialize_i64(self, _v: i64) -> Result<u32, EncodeError> {
+        Err(unsupported("tag"))
+    }
+    fn serialize_u8(self, _v: u8) -> Result<u32, EncodeError> {
+        Err(unsupported("tag"))
+    }
+    fn serialize_u16(self, _v: u16) -> Result<u32, EncodeError> {
+        Err(unsupported("tag"))
+    }
+    fn serialize_u32(self, _v: u32) -> Result<u32, EncodeError> {
+        Err(unsupported("tag"))
+    }
+    fn serialize_u64(self, _v: u64) -> Result<u32, EncodeError> {
+        Err(unsupported("tag"))
+    }
+    fn serialize_f32(self, _v: f32) -> Result<u32, EncodeError> {
+        Err(unsupported("tag"))
+    }
+    fn serialize_f64(self, _v: f64) -> Result<u32, EncodeError> {
+        Err(unsupported("tag"))
+    }
+    fn serialize_char(self, _v: char) -> Result<u32, EncodeError> {
+        Err(unsupported("tag"))
+    }
+    fn serialize_str(self, _v: &str) -> Result<u32, EncodeError> {
+        Err(unsupported("tag"))
+    }
+    fn serialize_bytes(self, _v: &[u8]) -> Result<u32, EncodeError> {
+        Err(unsupported("tag"))
+    }
+    fn serialize_none(self) -> Result<u32, EncodeError> {
+        Err(unsupported("tag"))
+    }
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<u32, EncodeError> {
+        Err(unsupported("tag"))
+    }
+    fn serialize_unit(self) -> Result<u32, EncodeError> {
+        Err(unsupported("tag"))
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<u32, EncodeError> {
+        Err(unsupported("tag"))
+    }
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _value: &T,
+    ) -> Result<u32, EncodeError> {
+        Err(unsupported("tag"))
+    }
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<u32, EncodeError> {
+        Err(unsupported("tag"))
+    }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, EncodeError> {
+        Err(unsupported("tag"))
+    }
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, EncodeError> {
+        Err(unsupported("tag"))
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, EncodeError> {
+        Err(unsupported("tag"))
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, EncodeError> {
+        Err(unsupported("tag"))
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, EncodeError> {
+        Err(unsupported("tag"))
+    }
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, EncodeError> {
+        Err(unsupported("tag"))
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, EncodeError> {
+        Err(unsupported("tag"))
+    }
+}
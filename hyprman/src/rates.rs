@@ -0,0 +1,126 @@
+use hyprman::event::{Address, HyprlandEvent, event_type};
+use serde_json::{Value, json};
+use std::{collections::HashMap, time::Instant};
+
+// === Event Rates ===
+
+/// Seconds of history kept per counter; the longest window reported.
+const HISTORY_SECS: u64 = 60;
+/// Window addresses listed as top talkers in `status`.
+const TOP_TALKERS: usize = 10;
+
+/// Events counted per second over the last minute.
+struct Counter {
+    /// Second the bucket was last used for, and the events counted in it.
+    buckets: [(u64, u32); HISTORY_SECS as usize],
+}
+
+impl Default for Counter {
+    fn default() -> Counter {
+        Counter {
+            buckets: [(0, 0); HISTORY_SECS as usize],
+        }
+    }
+}
+
+impl Counter {
+    fn add(&mut self, second: u64) {
+        let bucket = &mut self.buckets[(second % HISTORY_SECS) as usize];
+        if bucket.0 != second {
+            *bucket = (second, 0);
+        }
+        bucket.1 += 1;
+    }
+
+    /// Counts in the buckets of the last `secs` seconds up to `now`.
+    fn recent(&self, now: u64, secs: u64) -> impl Iterator<Item = u32> + '_ {
+        self.buckets
+            .iter()
+            .filter(move |(second, _)| *second <= now && now - second < secs)
+            .map(|(_, count)| *count)
+    }
+
+    fn sum(&self, now: u64, secs: u64) -> u32 {
+        self.recent(now, secs).sum()
+    }
+}
+
+/// How often each event type arrived recently, and which windows caused the
+/// most title changes and moves. Helps tuning hook `debounce` settings.
+pub struct EventRates {
+    started: Instant,
+    events: HashMap<&'static str, Counter>,
+    /// Events per type since the daemon started.
+    totals: HashMap<&'static str, u64>,
+    talkers: HashMap<Address, Counter>,
+}
+
+impl EventRates {
+    pub fn new() -> EventRates {
+        EventRates {
+            started: Instant::now(),
+            events: HashMap::new(),
+            totals: HashMap::new(),
+            talkers: HashMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, event: &HyprlandEvent) {
+        let now = self.started.elapsed().as_secs();
+        self.events.entry(event_type(event)).or_default().add(now);
+        *self.totals.entry(event_type(event)).or_default() += 1;
+        if let HyprlandEvent::WindowTitle { window_address }
+        | HyprlandEvent::WindowTitleV2 { window_address, .. }
+        | HyprlandEvent::MoveWindow { window_address, .. }
+        | HyprlandEvent::MoveWindowV2 { window_address, .. } = event
+        {
+            if !self.talkers.contains_key(window_address) {
+                // Closed windows would otherwise pile up.
+                self.talkers
+                    .retain(|_, counter| counter.sum(now, HISTORY_SECS) > 0);
+            }
+            self.talkers
+                .entry(window_address.clone())
+                .or_default()
+                .add(now);
+        }
+    }
+
+    /// Events per type in the last 1, 10 and 60 seconds with the busiest
+    /// second of the last minute, and the top talkers of the last minute.
+    pub fn report(&self) -> Value {
+        let now = self.started.elapsed().as_secs();
+        let events: serde_json::Map<String, Value> = self
+            .events
+            .iter()
+            .filter(|(_, counter)| counter.sum(now, HISTORY_SECS) > 0)
+            .map(|(name, counter)| {
+                let rates = json!({
+                    "1s": counter.sum(now, 1),
+                    "10s": counter.sum(now, 10),
+                    "60s": counter.sum(now, HISTORY_SECS),
+                    "peak_1s": counter.recent(now, HISTORY_SECS).max().unwrap_or(0),
+                });
+                (name.to_string(), rates)
+            })
+            .collect();
+        let mut talkers: Vec<(&Address, u32)> = self
+            .talkers
+            .iter()
+            .map(|(address, counter)| (address, counter.sum(now, HISTORY_SECS)))
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        talkers.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        let top_talkers: Vec<Value> = talkers
+            .into_iter()
+            .take(TOP_TALKERS)
+            .map(|(address, count)| json!({ "window_address": address, "60s": count }))
+            .collect();
+        json!({ "events": events, "top_talkers": top_talkers })
+    }
+
+    /// Events per type since the daemon started.
+    pub fn totals(&self) -> Value {
+        json!(self.totals)
+    }
+}
@@ -0,0 +1,154 @@
+//! JSON Schema of the events written to clients, for generating typed
+//! bindings in other languages. Derived from the event table in `event`, so
+//! it cannot drift from what the daemon sends.
+
+use crate::{
+    event::{
+        Address, FloatingState, FullscreenState, PROTOCOL_VERSION, ScreencastOwner, variant_schemas,
+    },
+    hypr::State,
+};
+use serde_json::{Map, Value, json};
+
+/// A type that appears in event data.
+pub trait Schema {
+    fn schema() -> Value;
+}
+
+impl Schema for String {
+    fn schema() -> Value {
+        json!({ "type": "string" })
+    }
+}
+
+impl Schema for bool {
+    fn schema() -> Value {
+        json!({ "type": "boolean" })
+    }
+}
+
+impl Schema for i64 {
+    fn schema() -> Value {
+        json!({ "type": "integer" })
+    }
+}
+
+impl Schema for u64 {
+    fn schema() -> Value {
+        json!({ "type": "integer", "minimum": 0 })
+    }
+}
+
+impl Schema for u32 {
+    fn schema() -> Value {
+        json!({ "type": "integer", "minimum": 0 })
+    }
+}
+
+impl<T: Schema> Schema for Option<T> {
+    fn schema() -> Value {
+        json!({ "anyOf": [T::schema(), { "type": "null" }] })
+    }
+}
+
+impl<T: Schema> Schema for Vec<T> {
+    fn schema() -> Value {
+        json!({ "type": "array", "items": T::schema() })
+    }
+}
+
+impl Schema for Address {
+    fn schema() -> Value {
+        json!({
+            "type": "string",
+            "pattern": "^[0-9a-f]*$",
+            "description": "Window address in lowercase hex without the 0x prefix",
+        })
+    }
+}
+
+impl Schema for FullscreenState {
+    fn schema() -> Value {
+        json!({ "enum": ["off", "on"] })
+    }
+}
+
+impl Schema for FloatingState {
+    fn schema() -> Value {
+        json!({ "enum": ["tiled", "floating"] })
+    }
+}
+
+impl Schema for ScreencastOwner {
+    fn schema() -> Value {
+        json!({ "enum": ["monitor", "window"] })
+    }
+}
+
+impl Schema for State {
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "monitors": { "type": "array" },
+                "workspaces": { "type": "array" },
+                "clients": { "type": "array" },
+            },
+            "required": ["monitors", "workspaces", "clients"],
+            "description": "Monitors, workspaces and clients as hyprctl -j reports them",
+        })
+    }
+}
+
+/// One variant: its name in `event` and, unless it is a unit variant, the
+/// fields of `data`.
+pub fn variant(name: &str, data: Option<Vec<(&str, Value)>>) -> Value {
+    let mut properties = Map::new();
+    properties.insert("event".to_string(), json!({ "const": name }));
+    let mut required = vec!["event"];
+    if let Some(fields) = data {
+        let names: Vec<&str> = fields.iter().map(|(field, _)| *field).collect();
+        let fields: Map<String, Value> = fields
+            .into_iter()
+            .map(|(field, schema)| (field.to_string(), schema))
+            .collect();
+        properties.insert(
+            "data".to_string(),
+            json!({
+                "type": "object",
+                "properties": fields,
+                "required": names,
+            }),
+        );
+        required.push("data");
+    }
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    })
+}
+
+/// Schema of a line on the client socket with the default `keys = "mixed"`:
+/// an event, inside an envelope carrying `seq` and `timestamp` unless it
+/// was meant for one client only, such as `Metadata`.
+pub fn envelope_schema() -> Value {
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": format!("hyprman event (protocol version {})", PROTOCOL_VERSION),
+        "type": "object",
+        "properties": {
+            "seq": u64::schema(),
+            "timestamp": u64::schema(),
+            "window": {
+                "type": "object",
+                "description": "The window a window event is about as hyprctl -j clients reports it, with enrich",
+            },
+            "workspace": {
+                "type": "object",
+                "description": "The workspace a workspace event is about as hyprctl -j workspaces reports it, with enrich",
+            },
+        },
+        "oneOf": variant_schemas(),
+    })
+}
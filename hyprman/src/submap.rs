@@ -0,0 +1,91 @@
+use crate::audit::{AuditLog, AuditRecord};
+use hyprman::event::HyprlandEvent;
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+// === Submap Timeouts ===
+
+/// Resets submaps configured in `submap_timeouts` once no event arrived for
+/// their timeout, so an accidental keypress cannot leave Hyprland stuck in
+/// e.g. a resize mode.
+pub struct SubmapTimeouts {
+    /// Seconds per submap name.
+    timeouts: HashMap<String, u64>,
+    timer: Arc<Mutex<Timer>>,
+}
+
+#[derive(Default)]
+struct Timer {
+    /// When the active submap is reset, and its name.
+    deadline: Option<(Instant, String)>,
+    running: bool,
+}
+
+impl SubmapTimeouts {
+    pub fn new(timeouts: HashMap<String, u64>) -> SubmapTimeouts {
+        SubmapTimeouts {
+            timeouts,
+            timer: Arc::new(Mutex::new(Timer::default())),
+        }
+    }
+
+    pub fn set_timeouts(&mut self, timeouts: HashMap<String, u64>) {
+        self.timeouts = timeouts;
+    }
+
+    /// Starts the timer when a timed submap is entered and restarts it on
+    /// every other event while it is active.
+    pub fn handle(&self, event: &HyprlandEvent, dry_run: bool, audit: &Arc<AuditLog>) {
+        let mut timer = self.timer.lock().unwrap();
+        let submap = match (event, &timer.deadline) {
+            (HyprlandEvent::Submap { submap_name }, _) => submap_name.clone(),
+            (_, Some((_, submap))) => submap.clone(),
+            (_, None) => return,
+        };
+        timer.deadline = self
+            .timeouts
+            .get(&submap)
+            .map(|secs| (Instant::now() + Duration::from_secs(*secs), submap));
+        if timer.deadline.is_none() || timer.running {
+            return;
+        }
+        timer.running = true;
+        let timer = self.timer.clone();
+        let audit = audit.clone();
+        thread::spawn(move || {
+            let submap = loop {
+                let wait = {
+                    let mut timer = timer.lock().unwrap();
+                    match &timer.deadline {
+                        Some((at, _)) if *at > Instant::now() => *at - Instant::now(),
+                        Some(_) => {
+                            timer.running = false;
+                            break timer.deadline.take().map(|(_, submap)| submap);
+                        }
+                        None => {
+                            timer.running = false;
+                            return;
+                        }
+                    }
+                };
+                thread::sleep(wait);
+            };
+            let trigger = HyprlandEvent::Submap {
+                submap_name: submap.unwrap_or_default(),
+            };
+            AuditRecord {
+                source: "submap_timeout",
+                name: None,
+                trigger: Some(&trigger),
+                command: "submap reset".to_string(),
+                result: Value::Null,
+            }
+            .dispatch(dry_run, &audit);
+        });
+    }
+}
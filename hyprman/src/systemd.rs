@@ -0,0 +1,101 @@
+use log::{info, warn};
+use std::{
+    env,
+    os::{
+        linux::net::SocketAddrExt,
+        unix::net::{SocketAddr, UnixDatagram},
+    },
+    time::{Duration, Instant},
+};
+
+// === systemd Service Notifications ===
+
+/// Talks to systemd over `$NOTIFY_SOCKET` when started by a `Type=notify`
+/// unit; does nothing otherwise.
+pub struct Notifier {
+    socket: Option<(UnixDatagram, SocketAddr)>,
+    /// Half of `WatchdogSec=`, when the unit enables the watchdog for us.
+    watchdog_interval: Option<Duration>,
+    last_ping: Instant,
+}
+
+impl Notifier {
+    /// Reads the notification environment. Call before spawning threads.
+    pub fn from_env() -> Notifier {
+        let socket = env::var("NOTIFY_SOCKET")
+            .ok()
+            .and_then(|path| match connect(&path) {
+                Ok(socket) => Some(socket),
+                Err(e) => {
+                    warn!("Failed to open systemd notify socket {}: {}", path, e);
+                    None
+                }
+            });
+        let watchdog_interval = watchdog_usec().map(|usec| Duration::from_micros(usec / 2));
+        if let Some(interval) = watchdog_interval {
+            info!("Pinging the systemd watchdog every {:?}", interval);
+        }
+        Notifier {
+            socket,
+            watchdog_interval,
+            last_ping: Instant::now(),
+        }
+    }
+
+    /// Tells systemd the daemon accepts clients.
+    pub fn ready(&self) {
+        self.send("READY=1");
+    }
+
+    /// Tells systemd the daemon is shutting down.
+    pub fn stopping(&self) {
+        self.send("STOPPING=1");
+    }
+
+    /// Pings the watchdog if half its timeout has passed since the last ping.
+    pub fn watchdog(&mut self) {
+        if let Some(interval) = self.watchdog_interval
+            && self.last_ping.elapsed() >= interval
+        {
+            self.send("WATCHDOG=1");
+            self.last_ping = Instant::now();
+        }
+    }
+
+    /// The longest the caller may sleep between `watchdog` calls.
+    pub fn watchdog_interval(&self) -> Option<Duration> {
+        self.watchdog_interval
+    }
+
+    fn send(&self, state: &str) {
+        if let Some((socket, address)) = &self.socket
+            && let Err(e) = socket.send_to_addr(state.as_bytes(), address)
+        {
+            warn!("Failed to notify systemd of {}: {}", state, e);
+        }
+    }
+}
+
+/// Paths starting with `@` name an abstract socket.
+fn connect(path: &str) -> std::io::Result<(UnixDatagram, SocketAddr)> {
+    let address = match path.strip_prefix('@') {
+        Some(name) => SocketAddr::from_abstract_name(name)?,
+        None => SocketAddr::from_pathname(path)?,
+    };
+    Ok((UnixDatagram::unbound()?, address))
+}
+
+/// `$WATCHDOG_USEC`, unless `$WATCHDOG_PID` says it is meant for another
+/// process.
+fn watchdog_usec() -> Option<u64> {
+    if let Ok(pid) = env::var("WATCHDOG_PID")
+        && pid.parse() != Ok(std::process::id())
+    {
+        return None;
+    }
+    env::var("WATCHDOG_USEC")
+        .ok()?
+        .parse()
+        .ok()
+        .filter(|&usec| usec > 0)
+}
@@ -0,0 +1,82 @@
+use log::{error, info};
+use std::{
+    io::Write,
+    net::Shutdown,
+    os::unix::net::{UnixListener, UnixStream},
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, SyncSender, TrySendError},
+    },
+    thread,
+};
+
+// === Raw Event Passthrough ===
+
+struct RawClient {
+    sender: SyncSender<Arc<[u8]>>,
+    /// Shut down to disconnect a client that is not keeping up.
+    socket: UnixStream,
+}
+
+/// Clients of the raw socket, which get the bytes read from Hyprland's
+/// socket2 exactly as they arrived, in its native `event>>data` format.
+#[derive(Default)]
+pub struct RawTee {
+    clients: Mutex<Vec<RawClient>>,
+}
+
+impl RawTee {
+    /// Queues the bytes for every raw client, dropping those that hung up or
+    /// have too much queued.
+    pub fn send(&self, bytes: &[u8]) {
+        let mut clients = self.clients.lock().unwrap();
+        if clients.is_empty() {
+            return;
+        }
+        let bytes: Arc<[u8]> = Arc::from(bytes);
+        clients.retain(|client| match client.sender.try_send(bytes.clone()) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                error!("Disconnecting raw client that is not keeping up");
+                let _ = client.socket.shutdown(Shutdown::Both);
+                false
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        });
+    }
+
+    fn add(&self, socket: UnixStream, queue_limit: usize) {
+        let (sender, receiver) = mpsc::sync_channel::<Arc<[u8]>>(queue_limit);
+        let mut writer = match socket.try_clone() {
+            Ok(writer) => writer,
+            Err(e) => {
+                error!("Failed to clone raw client socket: {}", e);
+                return;
+            }
+        };
+        thread::spawn(move || {
+            for bytes in receiver {
+                if writer.write_all(&bytes).is_err() {
+                    break;
+                }
+            }
+        });
+        self.clients
+            .lock()
+            .unwrap()
+            .push(RawClient { sender, socket });
+    }
+}
+
+/// Accepts raw clients; anything they write is ignored.
+pub fn raw_server_thread(listener: UnixListener, tee: &RawTee, queue_limit: usize) {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                info!("Raw client connected");
+                tee.add(stream, queue_limit);
+            }
+            Err(e) => error!("Failed to accept raw client connection: {}", e),
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use hyprman::event::{Address, HyprlandEvent};
+use serde::Serialize;
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// === Window Title History ===
+
+/// Windows whose titles are remembered, including closed ones; the window
+/// that changed its title the longest ago is forgotten first.
+const MAX_WINDOWS: usize = 256;
+
+pub fn default_title_history_size() -> usize {
+    32
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TitleChange {
+    pub timestamp_ms: u64,
+    pub title: String,
+}
+
+/// The most recent titles of each window, oldest first, as reported by
+/// `windowtitlev2`. Kept after a window closes to answer "what was that
+/// page I had open".
+pub struct TitleHistory {
+    size: usize,
+    windows: HashMap<Address, VecDeque<TitleChange>>,
+}
+
+impl TitleHistory {
+    pub fn new(size: usize) -> TitleHistory {
+        TitleHistory {
+            size,
+            windows: HashMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, event: &HyprlandEvent) {
+        let HyprlandEvent::WindowTitleV2 {
+            window_address,
+            window_title,
+        } = event
+        else {
+            return;
+        };
+        if self.size == 0 {
+            return;
+        }
+        if !self.windows.contains_key(window_address) && self.windows.len() >= MAX_WINDOWS {
+            let oldest = self
+                .windows
+                .iter()
+                .min_by_key(|(_, titles)| titles.back().map_or(0, |t| t.timestamp_ms))
+                .map(|(address, _)| address.clone());
+            if let Some(oldest) = oldest {
+                self.windows.remove(&oldest);
+            }
+        }
+        let titles = self.windows.entry(window_address.clone()).or_default();
+        if titles
+            .back()
+            .is_some_and(|last| last.title == *window_title)
+        {
+            return;
+        }
+        if titles.len() >= self.size {
+            titles.pop_front();
+        }
+        titles.push_back(TitleChange {
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() as u64),
+            title: window_title.clone(),
+        });
+    }
+
+    /// Titles of the window, oldest first; empty for unknown windows.
+    pub fn get(&self, address: &Address) -> Vec<TitleChange> {
+        self.windows
+            .get(address)
+            .map(|titles| titles.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
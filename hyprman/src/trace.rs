@@ -0,0 +1,59 @@
+use crate::output::KeyCase;
+use hyprman::{
+    error::Result,
+    event::{ParseMode, parse_event_line},
+    hypr::{create_socket, socket2_path},
+};
+use std::{
+    io::{BufRead, BufReader, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// === Event Tracing ===
+
+/// Local time of day with milliseconds, e.g. `14:03:27.512`.
+fn timestamp() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let seconds = now.as_secs() as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&seconds, &mut tm) }.is_null() {
+        return format!("{}.{:03}", now.as_secs(), now.subsec_millis());
+    }
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec,
+        now.subsec_millis()
+    )
+}
+
+/// Reads Hyprland's event socket directly, without a daemon, and prints
+/// every line with the time it arrived and the event it parses into, or
+/// why it does not parse.
+pub fn run_trace(keys: KeyCase) -> Result<()> {
+    let path = socket2_path()?;
+    let reader = BufReader::new(create_socket(&path)?);
+    eprintln!("Tracing {}", path);
+    let mut stdout = std::io::stdout().lock();
+    for line in reader.split(b'\n') {
+        let line = line?;
+        // Window titles may carry invalid UTF-8, as in the daemon.
+        let line = String::from_utf8_lossy(&line);
+        let parsed = match parse_event_line(&line, ParseMode::Strict) {
+            Ok(event) => keys.to_json(&event),
+            Err(e) => format!("error: {}", e),
+        };
+        writeln!(
+            stdout,
+            "{} {}\n    {}",
+            timestamp(),
+            line.trim_end(),
+            parsed
+        )?;
+        stdout.flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,735 @@
+use crate::CAPABILITIES;
+use hyprman::event::PROTOCOL_VERSION;
+
+// === Usage Text ===
+
+/// A flag, command or variable with its description. `--help` and the man
+/// page are both rendered from these tables so they cannot drift apart.
+pub struct Entry {
+    pub usage: &'static str,
+    pub description: &'static str,
+}
+
+const fn entry(usage: &'static str, description: &'static str) -> Entry {
+    Entry { usage, description }
+}
+
+/// An option of a command, with the placeholder of its value if it takes one.
+pub struct Flag {
+    pub long: &'static str,
+    pub short: Option<char>,
+    pub value: Option<&'static str>,
+    pub description: &'static str,
+}
+
+const fn flag(long: &'static str, value: Option<&'static str>, description: &'static str) -> Flag {
+    Flag {
+        long,
+        short: None,
+        value,
+        description,
+    }
+}
+
+impl Flag {
+    fn usage(&self) -> String {
+        let short = match self.short {
+            Some(short) => format!("-{}, ", short),
+            None => "    ".to_string(),
+        };
+        match self.value {
+            Some(value) => format!("{}--{} {}", short, self.long, value),
+            None => format!("{}--{}", short, self.long),
+        }
+    }
+}
+
+/// A subcommand with its positional arguments and options.
+pub struct Subcommand {
+    pub name: &'static str,
+    /// Flags that selected the mode before there were subcommands; still
+    /// accepted in place of the name.
+    pub aliases: &'static [&'static str],
+    pub args: &'static str,
+    pub description: &'static str,
+    pub flags: &'static [Flag],
+}
+
+impl Subcommand {
+    fn usage(&self) -> String {
+        match self.args {
+            "" => self.name.to_string(),
+            args => format!("{} {}", self.name, args),
+        }
+    }
+}
+
+/// Options every command takes.
+pub const GLOBAL_FLAGS: &[Flag] = &[
+    Flag {
+        short: Some('c'),
+        ..flag(
+            "config",
+            Some("PATH"),
+            "Read this config file instead of $XDG_CONFIG_HOME/hyprman/config.toml.",
+        )
+    },
+    flag(
+        "profile",
+        Some("NAME"),
+        "Apply the [profile.NAME] section of the config file on top of the rest.",
+    ),
+    flag(
+        "instance",
+        Some("SIGNATURE"),
+        "Use this Hyprland instance instead of the one of $HYPRLAND_INSTANCE_SIGNATURE. \
+         Without either, the newest running instance is used.",
+    ),
+    flag(
+        "socket1",
+        Some("PATH"),
+        "Use this Hyprland request socket instead of the one of $HYPRLAND_INSTANCE_SIGNATURE.",
+    ),
+    flag(
+        "socket2",
+        Some("PATH"),
+        "Use this Hyprland event socket instead of the one of $HYPRLAND_INSTANCE_SIGNATURE.",
+    ),
+    Flag {
+        short: Some('h'),
+        ..flag("help", None, "Show the help of the command.")
+    },
+];
+
+const OUTPUT: Flag = Flag {
+    short: Some('o'),
+    ..flag(
+        "output",
+        Some("FILE"),
+        "Replace FILE with every update instead of printing it.",
+    )
+};
+
+const DEDUPE_OUTPUT: Flag = flag(
+    "dedupe-output",
+    None,
+    "Skip updates that equal the previous one.",
+);
+
+const CLIENT_FLAGS: &[Flag] = &[OUTPUT, DEDUPE_OUTPUT];
+
+const ACTIVEWINDOW_FLAGS: &[Flag] = &[
+    OUTPUT,
+    DEDUPE_OUTPUT,
+    flag(
+        "fields",
+        Some("FIELDS"),
+        "Print only these comma-separated fields.",
+    ),
+];
+
+pub const SUBCOMMANDS: &[Subcommand] = &[
+    Subcommand {
+        name: "client",
+        aliases: &["-f", "--filter"],
+        args: "[FILTER]",
+        description: "Print the events matching a subscription filter (default: all). This \
+                      is what runs without a command.",
+        flags: CLIENT_FLAGS,
+    },
+    Subcommand {
+        name: "activewindow",
+        aliases: &["-a", "--activewindow"],
+        args: "[FIELDS]",
+        description: "Track active window changes, printing only the comma-separated FIELDS \
+                      if given.",
+        flags: ACTIVEWINDOW_FLAGS,
+    },
+    Subcommand {
+        name: "workspaces",
+        aliases: &["-w", "--workspaces"],
+        args: "",
+        description: "Track workspaces and print them on every change.",
+        flags: CLIENT_FLAGS,
+    },
+    Subcommand {
+        name: "groups",
+        aliases: &["-g", "--groups"],
+        args: "",
+        description: "Track window groups and their tabs.",
+        flags: CLIENT_FLAGS,
+    },
+    Subcommand {
+        name: "daemon",
+        aliases: &["-d", "--daemon"],
+        args: "",
+        description: "Run Hyprman as a daemon.",
+        flags: &[
+            flag(
+                "foreground",
+                None,
+                "Stay in the foreground and log to stderr, e.g. under a Type=simple \
+                 systemd user service.",
+            ),
+            flag("no-daemonize", None, "Same as --foreground."),
+            flag(
+                "rules-dry-run",
+                None,
+                "Log what hooks and window rules would run instead of running it.",
+            ),
+        ],
+    },
+    Subcommand {
+        name: "restart",
+        aliases: &["-r", "--restart"],
+        args: "",
+        description: "Restart the running daemon.",
+        flags: &[],
+    },
+    Subcommand {
+        name: "kill",
+        aliases: &["-k", "--kill"],
+        args: "",
+        description: "Stop the running daemon.",
+        flags: &[],
+    },
+    Subcommand {
+        name: "dnd",
+        aliases: &[],
+        args: "[on|off|toggle]",
+        description: "Silence hooks while events keep streaming; prints the current state \
+                      without an argument.",
+        flags: &[],
+    },
+    Subcommand {
+        name: "profile",
+        aliases: &[],
+        args: "[NAME|none]",
+        description: "Switch the active hook profile, or print it.",
+        flags: &[],
+    },
+    Subcommand {
+        name: "reload",
+        aliases: &[],
+        args: "",
+        description: "Re-read the config file in the running daemon, as sending it SIGHUP \
+                      does; clients stay connected.",
+        flags: &[],
+    },
+    Subcommand {
+        name: "status",
+        aliases: &[],
+        args: "",
+        description: "Show the daemon's PID, uptime, Hyprland connection, memory use, \
+                      client queues, event counters and rates.",
+        flags: &[],
+    },
+    Subcommand {
+        name: "trigger",
+        aliases: &[],
+        args: "NAME",
+        description: "Send a trigger event for hooks to react to.",
+        flags: &[],
+    },
+    Subcommand {
+        name: "get",
+        aliases: &[],
+        args: "title-history ADDRESS | focus-history [COUNT] | groups | fullscreen",
+        description: "Print the recent titles of a window oldest first, the recently focused \
+                      windows newest first with when and how long they had the focus, the \
+                      window groups with their tabs in order, or the fullscreen window of \
+                      each workspace that has one.",
+        flags: &[],
+    },
+    Subcommand {
+        name: "check",
+        aliases: &[],
+        args: "",
+        description: "Check the config file without starting anything: socket paths, hook \
+                      and multicast event names, window rules and binds.",
+        flags: &[],
+    },
+    Subcommand {
+        name: "trace",
+        aliases: &[],
+        args: "",
+        description: "Read Hyprland's event socket directly, bypassing the daemon, and print \
+                      every line with the time it arrived and the event it parses into, or \
+                      the parse error. Lines missing fields are errors here, as with \
+                      parse_mode = \"strict\".",
+        flags: &[],
+    },
+    Subcommand {
+        name: "schema",
+        aliases: &[],
+        args: "",
+        description: "Print a JSON Schema of the events written to clients with the default \
+                      keys, for generating typed bindings in other languages.",
+        flags: &[],
+    },
+    Subcommand {
+        name: "version",
+        aliases: &["-V", "--version"],
+        args: "",
+        description: "Show the build, protocol version and the newest Hyprland event set \
+                      understood.",
+        flags: &[],
+    },
+    Subcommand {
+        name: "completions",
+        aliases: &[],
+        args: "SHELL",
+        description: "Print the completion script for bash, zsh or fish.",
+        flags: &[],
+    },
+    Subcommand {
+        name: "manpage",
+        aliases: &[],
+        args: "",
+        description: "Print this documentation as a roff man page.",
+        flags: &[],
+    },
+    Subcommand {
+        name: "help",
+        aliases: &[],
+        args: "[COMMAND]",
+        description: "Show this help, or the help of a command.",
+        flags: &[],
+    },
+];
+
+pub const ENVIRONMENT: &[Entry] = &[
+    entry(
+        "HYPRMAN_SOCKET",
+        "Daemon socket used by the client modes instead of the configured one.",
+    ),
+    entry(
+        "HYPRMAN_KEY",
+        "Overrides a config key, e.g. HYPRMAN_LOG_LEVEL=debug; a double underscore \
+         separates section and key, as in HYPRMAN_LOGGING__MAX_SIZE. Values are read as \
+         TOML, or as a string if they do not parse.",
+    ),
+    entry(
+        "NOTIFY_SOCKET, WATCHDOG_USEC",
+        "Set by a Type=notify systemd unit; the daemon reports readiness and pings the \
+         watchdog every half interval while its Hyprland event thread runs.",
+    ),
+    entry(
+        "HYPRLAND_INSTANCE_SIGNATURE",
+        "Hyprland instance to use unless --instance is given. Set for hooks and commands \
+         the daemon runs, also when the instance was found under $XDG_RUNTIME_DIR/hypr/.",
+    ),
+    entry(
+        "XDG_CONFIG_HOME",
+        "Base of the config file path unless --config is given, ~/.config if unset.",
+    ),
+    entry(
+        "XDG_RUNTIME_DIR",
+        "Base of the runtime directory holding sockets and the locked PID file, \
+         $XDG_RUNTIME_DIR/hyprman/SIGNATURE/ with one directory per Hyprland instance; \
+         /run/user/UID if unset.",
+    ),
+    entry(
+        "XDG_STATE_HOME",
+        "Base of the daemon's log file, ~/.local/state if unset.",
+    ),
+];
+
+/// Process exit codes, as returned by `HyprmanError::exit_code`.
+const EXIT_STATUS: &[Entry] = &[
+    entry("0", "Success."),
+    entry("1", "An I/O error."),
+    entry("2", "The command line does not parse."),
+    entry("3", "The config file could not be read or parsed."),
+    entry("4", "A required environment variable is not set."),
+    entry(
+        "5",
+        "Hyprland or the daemon is not listening on its socket.",
+    ),
+    entry("6", "Hyprland or the daemon sent something unexpected."),
+    entry(
+        "7",
+        "The daemon is already running, is not running, or rejected a control command.",
+    ),
+];
+
+/// Top-level keys of config.toml.
+const CONFIG: &[Entry] = &[
+    entry(
+        "client_socket_path",
+        "Socket clients connect to for events (default: hyprman.sock).",
+    ),
+    entry(
+        "auto_spawn_daemon",
+        "Start the daemon from the client modes when it is not running (default: false).",
+    ),
+    entry(
+        "socket_mode, socket_group",
+        "Permissions and group of the client, sandbox and raw sockets, e.g. 0o660 and a \
+         group a bar running as another user is in.",
+    ),
+    entry(
+        "umask",
+        "File mode creation mask of the daemon (default: 0o022).",
+    ),
+    entry(
+        "sandbox_socket_path",
+        "Extra client socket for sandboxed apps; relative to $XDG_RUNTIME_DIR.",
+    ),
+    entry(
+        "raw_socket_path",
+        "Socket passing Hyprland's event>>data stream through unmodified.",
+    ),
+    entry(
+        "hyprland_socket1_path, hyprland_socket2_path",
+        "Hyprland's request and event sockets, instead of deriving them from the environment.",
+    ),
+    entry(
+        "control_socket_path, control_socket_mode",
+        "Socket for admin commands and its permissions (default: control.sock, 0o600).",
+    ),
+    entry(
+        "control_permissions",
+        "Which users and groups may run which control commands.",
+    ),
+    entry(
+        "pause_buffer_limit",
+        "Events kept for a paused client before it gets a snapshot on resume.",
+    ),
+    entry(
+        "ack_queue_limit",
+        "Events queued for a client in ack mode that stopped acknowledging.",
+    ),
+    entry(
+        "client_queue_limit",
+        "Events waiting for a client that is not keeping up before it is disconnected.",
+    ),
+    entry(
+        "read_buffer_size",
+        "Bytes read at once from Hyprland's event socket and from clients.",
+    ),
+    entry(
+        "suppress_legacy_events",
+        "Leave out events Hyprland follows with a newer one, e.g. workspace for \
+         workspacev2, for every client; hooks still see them.",
+    ),
+    entry(
+        "parse_mode",
+        "Socket2 lines missing trailing fields: lenient fills in defaults and logs a \
+         warning, strict turns them into ParseError events.",
+    ),
+    entry(
+        "durable_buffer_size, durable_overflow",
+        "Events kept for a disconnected durable subscription, and what happens past that.",
+    ),
+    entry("multicast", "UDP sink broadcasting every event."),
+    entry(
+        "watch_config",
+        "Reload the config whenever the file changes (default: true).",
+    ),
+    entry(
+        "audit_log",
+        "JSON lines file recording every command hooks and window rules run.",
+    ),
+    entry(
+        "nice, dispatch_cpu",
+        "Niceness of the daemon and CPU its dispatch thread is pinned to.",
+    ),
+    entry(
+        "history_size",
+        "Events kept for clients reconnecting with resume_from.",
+    ),
+    entry(
+        "title_history_size",
+        "Titles remembered per window for get title-history (default: 32).",
+    ),
+    entry(
+        "focus_history_size",
+        "Focus changes remembered for get focus-history (default: 64).",
+    ),
+    entry(
+        "restore_focus",
+        "Focus the window last focused on a workspace when switching to it.",
+    ),
+    entry(
+        "overlay_namespaces",
+        "Layer namespaces reported as OverlayShown and OverlayHidden events.",
+    ),
+    entry(
+        "submap_timeouts",
+        "Seconds without events after which a submap is reset, per submap.",
+    ),
+    entry(
+        "key_case",
+        "Casing of JSON keys: mixed, snake_case or camelCase.",
+    ),
+    entry(
+        "escape",
+        "Markup escaping of titles and classes in client-mode output.",
+    ),
+    entry(
+        "activewindow, workspaces",
+        "Settings of the -a and -w client modes.",
+    ),
+    entry(
+        "[[hooks]]",
+        "Commands, sounds and dispatches run on events.",
+    ),
+    entry(
+        "[[window_rules]]",
+        "Window properties set while windows match.",
+    ),
+    entry(
+        "[[binds]]",
+        "Key binds registered in Hyprland that run hyprman trigger.",
+    ),
+    entry(
+        "sound_player, profile",
+        "Player used by sound hooks, and the hook profile active at start.",
+    ),
+    entry(
+        "[profile.NAME]",
+        "Keys and sections applied over the rest with --profile NAME, e.g. other socket \
+         paths, hooks or window rules per machine. Set the hook profile as hook_profile \
+         next to these.",
+    ),
+    entry(
+        "log_level",
+        "What is logged, as a RUST_LOG filter (default: info for the daemon, error \
+         otherwise); RUST_LOG takes precedence.",
+    ),
+    entry(
+        "log_target",
+        "Where the daemon logs: stderr, file or journald (default: file, stderr with \
+         --foreground).",
+    ),
+    entry(
+        "[logging]",
+        "Log file of the file target: file (default: \
+         $XDG_STATE_HOME/hyprman/hyprman.log), rotated past max_size bytes keeping \
+         max_files old ones.",
+    ),
+];
+
+/// Options of the handshake line clients send after connecting.
+const HANDSHAKE: &[Entry] = &[
+    entry(
+        "all, EVENT,...",
+        "Event names to receive; all or nothing selects every event.",
+    ),
+    entry(
+        "format=json|protobuf",
+        "JSON lines, or length-delimited protobuf frames.",
+    ),
+    entry("keys=mixed|snake_case|camelCase", "Casing of JSON keys."),
+    entry(
+        "resume_from=SEQ",
+        "Replay the events after SEQ that are still in the history.",
+    ),
+    entry(
+        "ack=WINDOW",
+        "Keep at most WINDOW events unacknowledged; acknowledge with ack SEQ.",
+    ),
+    entry(
+        "durable=NAME",
+        "Keep collecting events for NAME while disconnected.",
+    ),
+    entry(
+        "canonical",
+        "Receive one event per change where Hyprland sends an old and a new one: the \
+         newer event, or ActiveWindowChanged and FocusedMonChanged merging both halves. \
+         Old events are left out once Hyprland has sent the new one, and sequence numbers \
+         skip them.",
+    ),
+    entry(
+        "enrich",
+        "Window events carry their window as hyprctl -j clients reports it, in window \
+         next to data, so class, title, workspace and floating state need no query; \
+         workspacev2, createworkspacev2 and other events naming a workspace id carry it \
+         with its monitor and window count in workspace. Only looked up while such a \
+         client is connected.",
+    ),
+];
+
+/// Lines clients may send after the handshake.
+const CLIENT_COMMANDS: &[Entry] = &[
+    entry("pause, resume", "Hold back events, and deliver them again."),
+    entry("ack SEQ", "Acknowledge the events up to SEQ in ack mode."),
+    entry(
+        "dispatch ARGS",
+        "Forward a dispatch to Hyprland if control_permissions grant dispatch; answered \
+         with a DispatchReply event.",
+    ),
+];
+
+const WIDTH: usize = 80;
+const INDENT: usize = 31;
+
+fn print_rows<'a>(rows: impl IntoIterator<Item = (String, &'a str)>) {
+    for (usage, description) in rows {
+        let mut lines = wrap(description, WIDTH - INDENT).into_iter();
+        let first = lines.next().unwrap_or_default();
+        if usage.len() + 2 < INDENT - 1 {
+            println!("  {:<width$}{}", usage, first, width = INDENT - 2);
+        } else {
+            println!("  {}", usage);
+            println!("{:INDENT$}{}", "", first);
+        }
+        for line in lines {
+            println!("{:INDENT$}{}", "", line);
+        }
+    }
+}
+
+fn print_entries(entries: &[Entry]) {
+    print_rows(entries.iter().map(|e| (e.usage.to_string(), e.description)));
+}
+
+fn print_flags<'a>(flags: impl IntoIterator<Item = &'a Flag>) {
+    print_rows(flags.into_iter().map(|f| (f.usage(), f.description)));
+}
+
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if line.len() + 1 + word.len() <= width => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+    lines
+}
+
+/// Print usage help text.
+pub fn print_help() {
+    println!("Usage: hyprman [COMMAND] [OPTIONS]");
+    println!();
+    println!("Commands:");
+    print_rows(SUBCOMMANDS.iter().map(|s| (s.usage(), s.description)));
+    println!();
+    println!("Options:");
+    print_flags(GLOBAL_FLAGS);
+    println!();
+    println!("Environment:");
+    print_entries(ENVIRONMENT);
+    println!();
+    println!("Without a command, Hyprman runs in client mode with the 'all' subscription.");
+    println!("Run 'hyprman help COMMAND' for the options of a command.");
+}
+
+/// Print the usage and options of one command.
+pub fn print_command_help(command: &Subcommand) {
+    println!("Usage: hyprman {} [OPTIONS]", command.usage());
+    if !command.aliases.is_empty() {
+        println!("       hyprman {} ...", command.aliases.join(" | "));
+    }
+    println!();
+    for line in wrap(command.description, WIDTH) {
+        println!("{}", line);
+    }
+    println!();
+    println!("Options:");
+    print_flags(command.flags.iter().chain(GLOBAL_FLAGS));
+}
+
+// === Man Page ===
+
+/// Escapes text for roff.
+fn roff(text: &str) -> String {
+    let text = text.replace('\\', "\\e").replace('-', "\\-");
+    match text.starts_with(['.', '\'']) {
+        true => format!("\\&{}", text),
+        false => text,
+    }
+}
+
+fn print_roff_flags(flags: &[Flag]) {
+    for flag in flags {
+        println!(".TP");
+        println!("\\fB{}\\fR", roff(flag.usage().trim_start()));
+        println!("{}", roff(flag.description));
+    }
+}
+
+fn print_roff_entries(entries: &[Entry]) {
+    for entry in entries {
+        println!(".TP");
+        println!("\\fB{}\\fR", roff(entry.usage.trim_start()));
+        println!("{}", roff(entry.description));
+    }
+}
+
+/// Prints hyprman(1) in roff.
+pub fn print_manpage() {
+    println!(
+        ".TH HYPRMAN 1 \"\" \"hyprman {}\" \"User Commands\"",
+        env!("CARGO_PKG_VERSION")
+    );
+    println!(".SH NAME");
+    println!("hyprman \\- event daemon and clients for Hyprland");
+    println!(".SH SYNOPSIS");
+    println!("\\fBhyprman\\fR [\\fICOMMAND\\fR] [\\fIOPTIONS\\fR]");
+    println!(".SH DESCRIPTION");
+    println!(
+        "Hyprman reads Hyprland's event socket once and fans the events out to any number \
+         of clients, runs hooks on them and keeps window rules applied. Without a command it \
+         runs in client mode with the 'all' subscription."
+    );
+    println!(".SH OPTIONS");
+    print_roff_flags(GLOBAL_FLAGS);
+    println!(".SH COMMANDS");
+    for command in SUBCOMMANDS {
+        println!(".TP");
+        println!("\\fB{}\\fR", roff(&command.usage()));
+        println!("{}", roff(command.description));
+        if !command.aliases.is_empty() {
+            println!("Also spelled {}.", roff(&command.aliases.join(", ")));
+        }
+        if !command.flags.is_empty() {
+            println!(".RS");
+            print_roff_flags(command.flags);
+            println!(".RE");
+        }
+    }
+    println!(".SH CONFIGURATION");
+    println!(
+        "The daemon reads \\fI$XDG_CONFIG_HOME/hyprman/config.toml\\fR; without it every \
+         key has its default. Relative paths are resolved against \
+         \\fI$XDG_RUNTIME_DIR/hyprman/SIGNATURE/\\fR, SIGNATURE being that of the Hyprland \
+         instance, so daemons for different instances do not meet. Top-level keys:"
+    );
+    print_roff_entries(CONFIG);
+    println!(".SH PROTOCOL");
+    println!(
+        "Clients connect to the client socket and send one handshake line of comma-separated \
+         event names and options. The daemon answers with a Metadata event naming protocol \
+         version {} and its capabilities, followed by one envelope per event: \
+         {{\"seq\":N,\"timestamp\":MS,\"event\":NAME,\"data\":{{...}}}}, MS being \
+         milliseconds since the Unix epoch. A daemon shutting down sends \
+         {{\"event\":\"DaemonShutdown\"}} before it closes the connection. Handshake options:",
+        PROTOCOL_VERSION
+    );
+    print_roff_entries(HANDSHAKE);
+    println!(".PP\nAfter the handshake clients may send:");
+    print_roff_entries(CLIENT_COMMANDS);
+    println!(
+        ".PP\nCapabilities advertised by this build: {}.",
+        roff(&CAPABILITIES.join(", "))
+    );
+    println!(".SH SANDBOXES");
+    println!(
+        "Setting \\fBsandbox_socket_path\\fR, e.g. to \\fIhyprman.sock\\fR, creates a second \
+         client socket at a stable path directly in \\fI$XDG_RUNTIME_DIR\\fR that can be \
+         handed to a Flatpak with \\fBflatpak override \\-\\-filesystem=xdg\\-run/hyprman.sock\\fR; \
+         inside the sandbox point \\fBHYPRMAN_SOCKET\\fR at it. Clients on that socket, and \
+         Flatpak clients on any socket, receive events but may not dispatch, and on the \
+         control socket may only run status and get."
+    );
+    println!(".SH ENVIRONMENT");
+    print_roff_entries(ENVIRONMENT);
+    println!(".SH EXIT STATUS");
+    print_roff_entries(EXIT_STATUS);
+}
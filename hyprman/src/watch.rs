@@ -0,0 +1,117 @@
+use crate::{
+    Daemon,
+    control::{self, ControlCommand},
+};
+use log::{error, info};
+use std::{
+    ffi::{CString, OsStr, OsString},
+    io,
+    os::unix::ffi::OsStrExt,
+    path::Path,
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+// === Config File Watcher ===
+
+/// Editors tend to write a file in several steps; changes arriving within
+/// this window are folded into a single reload.
+const SETTLE_TIME: Duration = Duration::from_millis(200);
+
+/// Reloads the config whenever the file is written or replaced. A config that
+/// fails to load is reported and the current hooks are kept.
+pub fn config_watch_thread(daemon: Arc<Daemon>) {
+    let path = Path::new(&daemon.config_path);
+    let (Some(dir), Some(file_name)) = (path.parent(), path.file_name()) else {
+        error!("Cannot watch config file {}", daemon.config_path);
+        return;
+    };
+    if !dir.exists() {
+        info!(
+            "Not watching {}: {} does not exist",
+            daemon.config_path,
+            dir.display()
+        );
+        return;
+    }
+    // Watch the directory, since saving often replaces the file with a new one.
+    let fd = match watch_directory(dir) {
+        Ok(fd) => fd,
+        Err(e) => {
+            error!("Failed to watch {}: {}", dir.display(), e);
+            return;
+        }
+    };
+    info!("Watching {} for changes", daemon.config_path);
+    let mut buffer = [0u8; 4096];
+    loop {
+        match read_events(fd, &mut buffer) {
+            Ok(names) if names.iter().any(|name| name.as_os_str() == file_name) => {
+                thread::sleep(SETTLE_TIME);
+                drain_events(fd, &mut buffer);
+                if !Path::new(&daemon.config_path).exists() {
+                    continue;
+                }
+                info!("Config file changed, reloading");
+                control::execute(&daemon, ControlCommand::Reload);
+            }
+            Ok(_) => {}
+            Err(e) => {
+                error!("Failed to read config changes: {}", e);
+                return;
+            }
+        }
+    }
+}
+
+fn watch_directory(dir: &Path) -> io::Result<i32> {
+    let dir = CString::new(dir.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let mask = libc::IN_CLOSE_WRITE | libc::IN_MOVED_TO | libc::IN_CREATE;
+    if unsafe { libc::inotify_add_watch(fd, dir.as_ptr(), mask) } < 0 {
+        let e = io::Error::last_os_error();
+        unsafe { libc::close(fd) };
+        return Err(e);
+    }
+    Ok(fd)
+}
+
+/// Blocks until at least one change arrives and returns the affected names.
+fn read_events(fd: i32, buffer: &mut [u8]) -> io::Result<Vec<OsString>> {
+    let len = unsafe { libc::read(fd, buffer.as_mut_ptr() as *mut libc::c_void, buffer.len()) };
+    if len < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let header = size_of::<libc::inotify_event>();
+    let mut names = Vec::new();
+    let mut offset = 0;
+    while offset + header <= len as usize {
+        let event = unsafe {
+            std::ptr::read_unaligned(buffer[offset..].as_ptr() as *const libc::inotify_event)
+        };
+        let name = &buffer[offset + header..offset + header + event.len as usize];
+        let end = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+        names.push(OsStr::from_bytes(&name[..end]).to_os_string());
+        offset += header + event.len as usize;
+    }
+    Ok(names)
+}
+
+/// Discards changes that are already queued without blocking.
+fn drain_events(fd: i32, buffer: &mut [u8]) {
+    let mut poll = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    while unsafe { libc::poll(&mut poll, 1, 0) } > 0 {
+        if read_events(fd, buffer).is_err() {
+            return;
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{
+    audit::{AuditLog, AuditRecord},
+    check::Findings,
+    hooks::Pattern,
+};
+use hyprman::{
+    event::{Address, HyprlandEvent},
+    hypr::{Client, query_json},
+};
+use log::warn;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// === Dynamic Window Rules ===
+
+/// Properties applied to matching windows while they match, configured as
+/// `[[window_rules]]`. Rules are re-evaluated whenever windows open, change
+/// focus or change their title.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WindowRule {
+    // Identifies the rule in the audit log.
+    #[serde(default)]
+    name: Option<String>,
+    // Regular expressions the window's class and title must match.
+    #[serde(default)]
+    class: Option<Pattern>,
+    #[serde(default)]
+    title: Option<Pattern>,
+    // Only match the focused window if true, only the others if false.
+    #[serde(default)]
+    focused: Option<bool>,
+    // Properties set with `dispatch setprop`, e.g. { alpha = "0.8" }. They
+    // are unset again once the window no longer matches.
+    #[serde(default)]
+    props: BTreeMap<String, String>,
+    // Make the window float, once, when it first matches.
+    #[serde(default)]
+    float: bool,
+    // Pin the window, once, when it first matches; implies `float`.
+    #[serde(default)]
+    pin: bool,
+}
+
+/// Reports rules that match every window or change nothing.
+pub fn check(rules: &[WindowRule], findings: &mut Findings) {
+    for (i, rule) in rules.iter().enumerate() {
+        let context = match &rule.name {
+            Some(name) => format!("window rule '{}'", name),
+            None => format!("window_rules[{}]", i),
+        };
+        if rule.class.is_none() && rule.title.is_none() && rule.focused.is_none() {
+            findings.warning(format!("{}: matches every window", context));
+        }
+        if rule.props.is_empty() && !rule.float && !rule.pin {
+            findings.warning(format!(
+                "{}: sets no props and neither floats nor pins",
+                context
+            ));
+        }
+    }
+}
+
+impl WindowRule {
+    fn matches(&self, client: &Client) -> bool {
+        self.class
+            .as_ref()
+            .is_none_or(|Pattern(class)| class.is_match(&client.class))
+            && self
+                .title
+                .as_ref()
+                .is_none_or(|Pattern(title)| title.is_match(&client.title))
+            && self
+                .focused
+                .is_none_or(|focused| focused == (client.focus_history_id == 0))
+    }
+}
+
+/// The configured rules and what they applied to which window.
+pub struct WindowRules {
+    rules: Vec<WindowRule>,
+    /// Properties currently set by rules, by window address.
+    applied: HashMap<Address, BTreeMap<String, String>>,
+    /// Windows that were already floated or pinned.
+    forced: HashSet<Address>,
+}
+
+impl WindowRules {
+    pub fn new(rules: Vec<WindowRule>) -> WindowRules {
+        WindowRules {
+            rules,
+            applied: HashMap::new(),
+            forced: HashSet::new(),
+        }
+    }
+
+    /// Swaps in reloaded rules; properties they no longer set are unset on
+    /// the next evaluation.
+    pub fn set_rules(&mut self, rules: Vec<WindowRule>) {
+        self.rules = rules;
+    }
+
+    /// Brings every window in line with the rules if `event` may have changed
+    /// which of them match.
+    pub fn apply(&mut self, event: &HyprlandEvent, dry_run: bool, audit: &AuditLog) {
+        if self.rules.is_empty() && self.applied.is_empty() {
+            return;
+        }
+        if !matches!(
+            event,
+            HyprlandEvent::OpenWindow { .. }
+                | HyprlandEvent::CloseWindow { .. }
+                | HyprlandEvent::ActiveWindowV2 { .. }
+                | HyprlandEvent::WindowTitleV2 { .. }
+        ) {
+            return;
+        }
+        let clients = match query_json::<Vec<Client>>("j/clients") {
+            Ok(clients) => clients,
+            Err(e) => {
+                warn!("Could not query clients for window rules: {}", e);
+                return;
+            }
+        };
+        let apply = |command: String, rule: Option<&str>| {
+            AuditRecord {
+                source: "window_rule",
+                name: rule,
+                trigger: Some(event),
+                command,
+                result: Value::Null,
+            }
+            .dispatch(dry_run, audit)
+        };
+        for client in clients.iter().filter(|c| c.mapped) {
+            let address = &client.address;
+            let prefixed = address.prefixed();
+            let mut wanted: BTreeMap<&str, (&str, Option<&str>)> = BTreeMap::new();
+            for rule in self.rules.iter().filter(|r| r.matches(client)) {
+                let name = rule.name.as_deref();
+                if (rule.float || rule.pin) && self.forced.insert(address.clone()) {
+                    if !client.floating {
+                        apply(format!("setfloating address:{}", prefixed), name);
+                    }
+                    if rule.pin && !client.pinned {
+                        apply(format!("pin address:{}", prefixed), name);
+                    }
+                }
+                for (prop, value) in &rule.props {
+                    wanted.insert(prop, (value, name));
+                }
+            }
+            let current = self.applied.remove(address).unwrap_or_default();
+            for (prop, (value, name)) in &wanted {
+                if current.get(*prop).is_none_or(|v| v != value) {
+                    apply(
+                        format!("setprop address:{} {} {}", prefixed, prop, value),
+                        *name,
+                    );
+                }
+            }
+            for prop in current.keys().filter(|p| !wanted.contains_key(p.as_str())) {
+                apply(format!("setprop address:{} {} unset", prefixed, prop), None);
+            }
+            if !wanted.is_empty() {
+                self.applied.insert(
+                    address.clone(),
+                    wanted
+                        .into_iter()
+                        .map(|(prop, (value, _))| (prop.to_string(), value.to_string()))
+                        .collect(),
+                );
+            }
+        }
+        // Forget closed windows.
+        let open: HashSet<&Address> = clients.iter().map(|c| &c.address).collect();
+        self.applied.retain(|address, _| open.contains(address));
+        self.forced.retain(|address| open.contains(address));
+    }
+}
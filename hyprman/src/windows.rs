@@ -0,0 +1,112 @@
+use hyprman::{
+    event::{Address, FloatingState, HyprlandEvent},
+    hypr::{Client, query_clients},
+};
+use log::warn;
+use std::collections::HashMap;
+
+// === Window Details for Enriched Events ===
+
+/// Every window as `j/clients` reports it, so window events can carry the
+/// details of their window to clients that subscribed with `enrich`. Titles,
+/// workspaces, floating and pinned state follow the events; the rest, such
+/// as position and focus history, is as of the last query. Re-read from
+/// socket1 when an event names a window it does not know.
+#[derive(Debug, Default)]
+pub struct WindowCache {
+    windows: HashMap<Address, Client>,
+}
+
+impl WindowCache {
+    /// Updates the cached windows and, if `lookup` is set, returns the
+    /// window `event` is about as it is after the event.
+    pub fn handle(&mut self, event: &HyprlandEvent, lookup: bool) -> Option<Box<Client>> {
+        match event {
+            HyprlandEvent::WindowTitleV2 {
+                window_address,
+                window_title,
+            } => self.update(window_address, |c| c.title = window_title.clone()),
+            HyprlandEvent::MoveWindowV2 {
+                window_address,
+                workspace_id,
+                workspace_name,
+            } => self.update(window_address, |c| {
+                c.workspace.id = *workspace_id;
+                c.workspace.name = workspace_name.clone();
+            }),
+            HyprlandEvent::ChangeFloatingMode {
+                window_address,
+                floating,
+            } => self.update(window_address, |c| {
+                c.floating = *floating == FloatingState::Floating
+            }),
+            HyprlandEvent::Pin {
+                window_address,
+                pin_state,
+            } => self.update(window_address, |c| c.pinned = *pin_state),
+            HyprlandEvent::Minimized {
+                window_address,
+                minimized,
+            } => self.update(window_address, |c| c.minimized = Some(*minimized)),
+            // What these change is not in the event; read every window again
+            // when next needed.
+            HyprlandEvent::MoveIntoGroup { .. }
+            | HyprlandEvent::MoveOutOfGroup { .. }
+            | HyprlandEvent::ToggleGroup { .. }
+            | HyprlandEvent::Fullscreen { .. }
+            | HyprlandEvent::HyprlandRestarted { .. } => self.windows.clear(),
+            _ => {}
+        }
+        let window_address = window_address(event)?;
+        let window = if lookup {
+            self.get(window_address)
+        } else {
+            None
+        };
+        if let HyprlandEvent::CloseWindow { window_address } = event {
+            self.windows.remove(window_address);
+        }
+        window
+    }
+
+    fn update(&mut self, address: &Address, change: impl FnOnce(&mut Client)) {
+        if let Some(client) = self.windows.get_mut(address) {
+            change(client);
+        }
+    }
+
+    fn get(&mut self, address: &Address) -> Option<Box<Client>> {
+        if !self.windows.contains_key(address) {
+            match query_clients() {
+                Ok(windows) => self.windows = windows,
+                Err(e) => warn!("Could not query clients to enrich events: {}", e),
+            }
+        }
+        self.windows.get(address).cloned().map(Box::new)
+    }
+}
+
+/// The window an event is about, if it is about exactly one.
+fn window_address(event: &HyprlandEvent) -> Option<&Address> {
+    match event {
+        HyprlandEvent::ActiveWindowV2 { window_address }
+        | HyprlandEvent::OpenWindow { window_address, .. }
+        | HyprlandEvent::CloseWindow { window_address }
+        | HyprlandEvent::MoveWindow { window_address, .. }
+        | HyprlandEvent::MoveWindowV2 { window_address, .. }
+        | HyprlandEvent::ChangeFloatingMode { window_address, .. }
+        | HyprlandEvent::Urgent { window_address }
+        | HyprlandEvent::WindowTitle { window_address }
+        | HyprlandEvent::WindowTitleV2 { window_address, .. }
+        | HyprlandEvent::MoveIntoGroup { window_address }
+        | HyprlandEvent::MoveOutOfGroup { window_address }
+        | HyprlandEvent::Pin { window_address, .. }
+        | HyprlandEvent::Minimized { window_address, .. }
+        | HyprlandEvent::Bell { window_address }
+        | HyprlandEvent::ActiveWindowChanged { window_address, .. } => {
+            Some(window_address).filter(|address| !address.is_empty())
+        }
+        HyprlandEvent::FullscreenChanged { window_address, .. } => window_address.as_ref(),
+        _ => None,
+    }
+}
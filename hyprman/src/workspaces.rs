@@ -0,0 +1,442 @@
+use crate::{
+    Config, connect_unix_socket,
+    output::{Escape, KeyCase, Output},
+    parse_event, try_load_config,
+};
+use hyprman::error::Result;
+use hyprman::event::{Address, HyprlandEvent};
+use hyprman::hypr::{
+    Workspace, query_clients, query_monitors, query_workspaces, set_instance_signature,
+};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+// === Workspaces Client Configuration ===
+
+#[derive(Debug, Deserialize)]
+pub struct WorkspacesConfig {
+    // How special workspaces (scratchpads) appear in the output.
+    #[serde(default)]
+    special: SpecialWorkspaces,
+    // Adds an "indicator" string per workspace rendered from its windows.
+    #[serde(default)]
+    indicator: Indicator,
+    // Character repeated once per window for `indicator = "dots"`.
+    #[serde(default = "default_dot")]
+    dot: String,
+    // Window class -> icon for `indicator = "icons"`.
+    #[serde(default)]
+    icons: HashMap<String, String>,
+    // Icon for classes missing from `icons`; falls back to `dot`.
+    default_icon: Option<String>,
+    // Adds a "label" string per workspace, e.g. "{name} ({windows})".
+    // Supports {id}, {name} and {windows}.
+    label: Option<String>,
+}
+
+impl Default for WorkspacesConfig {
+    fn default() -> Self {
+        WorkspacesConfig {
+            special: SpecialWorkspaces::default(),
+            indicator: Indicator::default(),
+            dot: default_dot(),
+            icons: HashMap::new(),
+            default_icon: None,
+            label: None,
+        }
+    }
+}
+
+fn default_dot() -> String {
+    "●".to_string()
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Indicator {
+    #[default]
+    None,
+    /// One `dot` per window.
+    Dots,
+    /// One icon per window, looked up by window class.
+    Icons,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SpecialWorkspaces {
+    /// Listed among the regular workspaces.
+    #[default]
+    Include,
+    /// Left out of the output.
+    Exclude,
+    /// Output becomes `{"workspaces": [...], "special": [...]}`.
+    Separate,
+}
+
+// === Workspaces Client Mode ===
+
+/// Output shape for `special = "separate"`.
+#[derive(Serialize)]
+struct SeparatedWorkspaces {
+    workspaces: Vec<Workspace>,
+    special: Vec<Workspace>,
+}
+
+/// What the workspaces client remembers about a window.
+struct TrackedWindow {
+    workspace_id: i64,
+    class: String,
+    minimized: bool,
+}
+
+/// State the workspaces client keeps between events.
+struct WorkspacesState {
+    workspaces: Vec<Workspace>,
+    /// Workspace shown on each monitor, keyed by monitor name.
+    active_workspaces: HashMap<String, i64>,
+    focused_monitor: Option<String>,
+    /// Special workspace opened on each monitor, keyed by monitor name.
+    active_specials: HashMap<String, String>,
+    /// Every window, keyed by address.
+    windows: HashMap<Address, TrackedWindow>,
+    /// Windows that requested attention and have not been focused since.
+    urgent_windows: HashSet<Address>,
+}
+
+impl WorkspacesState {
+    fn query() -> Result<WorkspacesState> {
+        let mut state = WorkspacesState {
+            workspaces: query_workspaces()?,
+            active_workspaces: HashMap::new(),
+            focused_monitor: None,
+            active_specials: HashMap::new(),
+            windows: HashMap::new(),
+            urgent_windows: HashSet::new(),
+        };
+        state.refresh_monitors()?;
+        state.refresh_windows()?;
+        Ok(state)
+    }
+
+    fn refresh_monitors(&mut self) -> Result<()> {
+        let monitors = query_monitors()?;
+        self.focused_monitor = monitors.iter().find(|m| m.focused).map(|m| m.name.clone());
+        self.active_specials = monitors
+            .iter()
+            .filter(|m| !m.special_workspace.name.is_empty())
+            .map(|m| (m.name.clone(), m.special_workspace.name.clone()))
+            .collect();
+        self.active_workspaces = monitors
+            .into_iter()
+            .map(|m| (m.name, m.active_workspace.id))
+            .collect();
+        Ok(())
+    }
+
+    fn refresh_windows(&mut self) -> Result<()> {
+        // Only events tell which windows are minimized; keep what they said.
+        let previous = std::mem::take(&mut self.windows);
+        self.windows = query_clients()?
+            .into_values()
+            .map(|c| {
+                let window = TrackedWindow {
+                    workspace_id: c.workspace.id,
+                    class: c.class,
+                    minimized: previous.get(&c.address).is_some_and(|w| w.minimized),
+                };
+                (c.address, window)
+            })
+            .collect();
+        Ok(())
+    }
+
+    fn workspace_id(&self, name: &str) -> Option<i64> {
+        self.workspaces
+            .iter()
+            .find(|w| w.name == name)
+            .map(|w| w.id)
+    }
+
+    /// Focusing a workspace counts as attending to its urgent windows.
+    fn clear_urgent(&mut self, workspace_id: i64) {
+        let windows = &self.windows;
+        self.urgent_windows
+            .retain(|address| windows.get(address).map(|w| w.workspace_id) != Some(workspace_id));
+    }
+
+    fn handle(&mut self, event: HyprlandEvent) -> Result<()> {
+        match event {
+            HyprlandEvent::FocusedMonV2 {
+                monitor_name,
+                workspace_id,
+            } => {
+                self.active_workspaces
+                    .insert(monitor_name.clone(), workspace_id);
+                self.focused_monitor = Some(monitor_name);
+                self.clear_urgent(workspace_id);
+            }
+            HyprlandEvent::WorkspaceV2 { workspace_id, .. } => {
+                // The switch happens on whichever monitor owns the workspace.
+                match self
+                    .workspaces
+                    .iter()
+                    .find(|w| w.id == workspace_id)
+                    .and_then(|w| w.monitor.clone())
+                {
+                    Some(monitor) => {
+                        self.active_workspaces.insert(monitor.clone(), workspace_id);
+                        self.focused_monitor = Some(monitor);
+                    }
+                    None => {
+                        self.workspaces = query_workspaces()?;
+                        self.refresh_monitors()?;
+                    }
+                }
+                self.clear_urgent(workspace_id);
+            }
+            HyprlandEvent::MoveWorkspaceV2 { .. }
+            | HyprlandEvent::MonitorAddedV2 { .. }
+            | HyprlandEvent::MonitorRemoved { .. } => {
+                self.workspaces = query_workspaces()?;
+                self.refresh_monitors()?;
+            }
+            HyprlandEvent::ActiveSpecial {
+                workspace_name,
+                monitor_name,
+            } => {
+                // An empty name means the special workspace was closed.
+                if workspace_name.is_empty() {
+                    self.active_specials.remove(&monitor_name);
+                } else {
+                    self.active_specials.insert(monitor_name, workspace_name);
+                }
+            }
+            HyprlandEvent::OpenWindow {
+                window_address,
+                workspace_name,
+                window_class,
+                ..
+            } => {
+                if self.workspace_id(&workspace_name).is_none() {
+                    self.workspaces = query_workspaces()?;
+                }
+                if let Some(workspace_id) = self.workspace_id(&workspace_name) {
+                    let window = TrackedWindow {
+                        workspace_id,
+                        class: window_class,
+                        minimized: false,
+                    };
+                    self.windows.insert(window_address, window);
+                }
+            }
+            HyprlandEvent::MoveWindowV2 {
+                window_address,
+                workspace_id,
+                ..
+            } => {
+                if !self.workspaces.iter().any(|w| w.id == workspace_id) {
+                    self.workspaces = query_workspaces()?;
+                }
+                match self.windows.get_mut(&window_address) {
+                    Some(window) => window.workspace_id = workspace_id,
+                    None => self.refresh_windows()?,
+                }
+            }
+            HyprlandEvent::CloseWindow { window_address } => {
+                self.windows.remove(&window_address);
+                self.urgent_windows.remove(&window_address);
+            }
+            HyprlandEvent::Urgent { window_address } => {
+                if !self.windows.contains_key(&window_address) {
+                    self.refresh_windows()?;
+                }
+                self.urgent_windows.insert(window_address);
+            }
+            HyprlandEvent::ActiveWindowV2 { window_address } => {
+                self.urgent_windows.remove(&window_address);
+            }
+            HyprlandEvent::Minimized {
+                window_address,
+                minimized,
+            } => {
+                if !self.windows.contains_key(&window_address) {
+                    self.refresh_windows()?;
+                }
+                if let Some(window) = self.windows.get_mut(&window_address) {
+                    window.minimized = minimized;
+                }
+            }
+            _ => {
+                self.workspaces = query_workspaces()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Renders the windows of a workspace as configured by `indicator`.
+    fn indicator(&self, config: &WorkspacesConfig, workspace_id: i64) -> Option<String> {
+        let mut windows: Vec<(&Address, &TrackedWindow)> = self
+            .windows
+            .iter()
+            .filter(|(_, w)| w.workspace_id == workspace_id)
+            .collect();
+        // Keep the rendering stable between updates.
+        windows.sort_by_key(|(address, _)| *address);
+        match config.indicator {
+            Indicator::None => None,
+            Indicator::Dots => Some(config.dot.repeat(windows.len())),
+            Indicator::Icons => Some(
+                windows
+                    .iter()
+                    .map(|(_, w)| {
+                        config
+                            .icons
+                            .get(&w.class)
+                            .or(config.default_icon.as_ref())
+                            .unwrap_or(&config.dot)
+                            .as_str()
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// The workspaces sorted by id with the derived flags filled in.
+    fn render(&self, config: &WorkspacesConfig) -> Vec<Workspace> {
+        let urgent_workspaces: HashSet<i64> = self
+            .urgent_windows
+            .iter()
+            .filter_map(|address| self.windows.get(address).map(|w| w.workspace_id))
+            .collect();
+        let mut window_counts: HashMap<i64, u32> = HashMap::new();
+        let mut minimized_counts: HashMap<i64, u32> = HashMap::new();
+        for window in self.windows.values() {
+            *window_counts.entry(window.workspace_id).or_default() += 1;
+            if window.minimized {
+                *minimized_counts.entry(window.workspace_id).or_default() += 1;
+            }
+        }
+        let focused_id = self
+            .focused_monitor
+            .as_ref()
+            .and_then(|monitor| self.active_workspaces.get(monitor));
+        let mut workspaces = self.workspaces.clone();
+        workspaces.sort_by_key(|w| w.id);
+        let focused_special = self
+            .focused_monitor
+            .as_ref()
+            .and_then(|monitor| self.active_specials.get(monitor));
+        for workspace in workspaces.iter_mut() {
+            let (visible, focused) = if workspace.is_special() {
+                (
+                    self.active_specials.values().any(|n| *n == workspace.name),
+                    focused_special == Some(&workspace.name),
+                )
+            } else {
+                (
+                    self.active_workspaces
+                        .values()
+                        .any(|&id| id == workspace.id),
+                    focused_id == Some(&workspace.id),
+                )
+            };
+            if visible {
+                workspace.visible = Some(true);
+            }
+            if focused {
+                workspace.focused = Some(true);
+            }
+            if urgent_workspaces.contains(&workspace.id) {
+                workspace.urgent = Some(true);
+            }
+            // Counted from the tracked windows, as j/workspaces goes stale.
+            let windows = window_counts.get(&workspace.id).copied().unwrap_or(0);
+            workspace.windows = Some(windows);
+            if windows > 0 {
+                workspace.occupied = Some(true);
+            }
+            workspace.minimized = minimized_counts.get(&workspace.id).copied();
+            workspace.label = config.label.as_ref().map(|label| {
+                label
+                    .replace("{id}", &workspace.id.to_string())
+                    .replace("{name}", &workspace.name)
+                    .replace("{windows}", &windows.to_string())
+            });
+            workspace.indicator = self.indicator(config, workspace.id);
+        }
+        workspaces
+    }
+
+    fn print(&self, config: &WorkspacesConfig, escape: Escape, keys: KeyCase, output: &mut Output) {
+        let mut workspaces = self.render(config);
+        for workspace in workspaces.iter_mut() {
+            workspace.name = escape.apply(&workspace.name);
+            if let Some(title) = workspace.last_window_title.as_mut() {
+                *title = escape.apply(title);
+            }
+        }
+        let serialized = match config.special {
+            SpecialWorkspaces::Include => keys.to_json(&workspaces),
+            SpecialWorkspaces::Exclude => keys.to_json(
+                &workspaces
+                    .into_iter()
+                    .filter(|w| !w.is_special())
+                    .collect::<Vec<_>>(),
+            ),
+            SpecialWorkspaces::Separate => {
+                let (special, regular): (Vec<_>, Vec<_>) =
+                    workspaces.into_iter().partition(|w| w.is_special());
+                keys.to_json(&SeparatedWorkspaces {
+                    workspaces: regular,
+                    special,
+                })
+            }
+        };
+        output.write(&serialized);
+    }
+}
+
+/// Prints the workspaces as json flagging the ones visible on any monitor, the
+/// one on the focused monitor, the occupied ones and the ones holding urgent
+/// windows. Picks up new settings when the daemon reloads the config.
+pub fn run_workspaces_client(mut config: Config, mut output: Output) -> Result<()> {
+    let subscription_line = String::from(
+        "workspacev2,focusedmonv2,createworkspacev2,destroyworkspacev2,moveworkspacev2,renameworkspace,activespecial,openwindow,closewindow,movewindowv2,monitoraddedv2,monitorremoved,urgent,minimized,activewindowv2,hyprmanreloaded,hyprlandrestarted\n",
+    );
+    info!("Using subscription line: {}", subscription_line);
+    let mut connection = connect_unix_socket(&config, subscription_line)?;
+    let mut state = WorkspacesState::query()?;
+    state.print(
+        &config.workspaces,
+        config.escape,
+        config.key_case,
+        &mut output,
+    );
+    while let Some(event_line) = connection.next_line()? {
+        let event = parse_event(&event_line)?;
+        if let HyprlandEvent::HyprmanReloaded { config_path } = event {
+            match try_load_config(&config_path, config.config_profile.as_deref()) {
+                Ok(reloaded) => {
+                    config.workspaces = reloaded.workspaces;
+                    config.escape = reloaded.escape;
+                    config.key_case = reloaded.key_case;
+                }
+                Err(e) => error!("Failed to reload config: {}", e),
+            }
+        } else if let HyprlandEvent::HyprlandRestarted { instance_signature } = event {
+            set_instance_signature(instance_signature);
+            state = WorkspacesState::query()?;
+        } else {
+            state.handle(event)?;
+        }
+        state.print(
+            &config.workspaces,
+            config.escape,
+            config.key_case,
+            &mut output,
+        );
+    }
+    Ok(())
+}